@@ -0,0 +1,1037 @@
+use cosmwasm_std::{
+    log, to_binary, Api, Binary, Env, Extern, HandleResponse, HumanAddr, InitResponse, Querier,
+    StdError, StdResult, Storage,
+};
+use secret_toolkit::crypto::sha_256;
+use secret_toolkit::utils::{HandleCallback, InitCallback};
+
+use crate::msg::{
+    AdoptablePet, Difficulty, FactoryCallback, HandleMsg, InitMsg, Intervals, Memorial,
+    MemorialInfo, PetHandleMsg, PetInfo, PetInitMsg, QueryAnswer, QueryMsg, Stage,
+};
+use crate::state::{
+    adoption_pool, adoption_pool_read, append_memorial, append_owner_pet, config, config_read,
+    get_memorials, get_owner_pet_ids, hall_of_fame, hall_of_fame_read, load_pet, pending,
+    pending_read, remove_owner_pet, save_pet, world_events, world_events_read, AdoptionListing,
+    PendingPet, PetCode, State, StoredPet, TokenInfo,
+};
+use shared::name::validate_name;
+use shared::paging::page_start;
+use shared::world::{cancel_world_event, schedule_world_event, WorldEvent};
+
+/// Longest lives kept in the hall of fame, so it's cheap to keep sorted
+pub const HALL_OF_FAME_SIZE: usize = 100;
+
+pub fn init<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: InitMsg,
+) -> StdResult<InitResponse> {
+    let state = State {
+        owner: deps.api.canonical_address(&env.message.sender)?,
+        pet_code: PetCode {
+            code_id: msg.pet_code_id,
+            code_hash: msg.pet_code_hash,
+        },
+        token: TokenInfo {
+            address: HumanAddr::from(msg.token_address),
+            code_hash: msg.token_code_hash,
+        },
+        prng_seed: sha_256(&msg.prng_seed.0).to_vec(),
+        pet_count: 0,
+    };
+    config(&mut deps.storage).save(&state)?;
+    Ok(InitResponse::default())
+}
+
+pub fn handle<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    msg: HandleMsg,
+) -> StdResult<HandleResponse> {
+    match msg {
+        HandleMsg::CreatePet {
+            name,
+            difficulty,
+            intervals,
+        } => try_create_pet(deps, env, name, difficulty, intervals),
+        HandleMsg::RegisterPet { pet_id, password } => {
+            try_register_pet(deps, env, pet_id, password)
+        }
+        HandleMsg::SetPetCode { code_id, code_hash } => {
+            try_set_pet_code(deps, env, code_id, code_hash)
+        }
+        HandleMsg::BuryPet { pet_id, memorial } => try_bury_pet(deps, env, pet_id, memorial),
+        HandleMsg::RenamePet { pet_id, name } => try_rename_pet(deps, env, pet_id, name),
+        HandleMsg::PutUpForAdoption {
+            pet_id,
+            born_at,
+            deadline,
+        } => try_put_up_for_adoption(deps, env, pet_id, born_at, deadline),
+        HandleMsg::ChangeOwner { pet_id, owner } => try_change_owner(deps, env, pet_id, owner),
+        HandleMsg::VisitPet {
+            pet_id,
+            host,
+            host_code_hash,
+        } => try_visit_pet(deps, env, pet_id, host, host_code_hash),
+        HandleMsg::ScheduleWorldEvent { event } => try_schedule_world_event(deps, env, event),
+        HandleMsg::CancelWorldEvent { name } => try_cancel_world_event(deps, env, name),
+    }
+}
+
+pub fn try_create_pet<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    name: String,
+    difficulty: Option<Difficulty>,
+    intervals: Option<Intervals>,
+) -> StdResult<HandleResponse> {
+    // same rules as the Pet's, which would fail to instantiate otherwise
+    validate_name(&name)?;
+    let state = config_read(&deps.storage).load()?;
+    let pet_id = state.pet_count;
+    let password = new_password(&state.prng_seed, pet_id, &env);
+    pending(&mut deps.storage).save(&Some(PendingPet {
+        pet_id,
+        password: password.clone(),
+        name: name.clone(),
+        owner: deps.api.canonical_address(&env.message.sender)?,
+    }))?;
+
+    let intervals = intervals.unwrap_or_default();
+    let init_msg = PetInitMsg {
+        name: name.clone(),
+        token_code_hash: state.token.code_hash.clone(),
+        token_address: state.token.address.0.clone(),
+        difficulty,
+        satiated_interval: intervals.satiated_interval,
+        starving_interval: intervals.starving_interval,
+        owner: Some(env.message.sender.clone()),
+        admin: Some(deps.api.human_address(&state.owner)?),
+        factory: Some(FactoryCallback {
+            address: env.contract.address.clone(),
+            code_hash: env.contract_code_hash.clone(),
+            pet_id,
+            password: Binary(password.clone()),
+        }),
+        prng_seed: Binary(sha_256(&[state.prng_seed.clone(), password].concat()).to_vec()),
+    };
+    // labels have to be unique across the chain
+    let label = format!("{} #{} ({})", name, pet_id, env.contract.address);
+    let instantiate = init_msg.to_cosmos_msg(
+        label,
+        state.pet_code.code_id,
+        state.pet_code.code_hash,
+        None,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![instantiate],
+        log: vec![log("pet_id", pet_id)],
+        data: None,
+    })
+}
+
+pub fn try_register_pet<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+    password: Binary,
+) -> StdResult<HandleResponse> {
+    let pending_pet = match pending_read(&deps.storage).may_load()?.flatten() {
+        Some(pending_pet) if pending_pet.pet_id == pet_id && pending_pet.password == password.0 => {
+            pending_pet
+        }
+        _ => return Err(StdError::unauthorized()),
+    };
+    pending(&mut deps.storage).save(&None)?;
+
+    let pet = StoredPet {
+        address: env.message.sender.clone(),
+        name: pending_pet.name,
+        owner: pending_pet.owner.clone(),
+        created_at: env.block.time,
+    };
+    save_pet(&mut deps.storage, pet_id, &pet)?;
+    append_owner_pet(&mut deps.storage, &pending_pet.owner, pet_id)?;
+    config(&mut deps.storage).update(|mut state| {
+        state.pet_count += 1;
+        Ok(state)
+    })?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("pet_id", pet_id),
+            log("pet_address", env.message.sender),
+        ],
+        data: None,
+    })
+}
+
+pub fn try_set_pet_code<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    code_id: u64,
+    code_hash: String,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    config(&mut deps.storage).update(|mut state| {
+        if state.owner != sender {
+            return Err(StdError::unauthorized());
+        }
+        state.pet_code = PetCode { code_id, code_hash };
+        Ok(state)
+    })?;
+    Ok(HandleResponse::default())
+}
+
+pub fn try_bury_pet<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+    memorial: Memorial,
+) -> StdResult<HandleResponse> {
+    registered_pet(deps, &env, pet_id)?;
+    // the name it died with, the pet may have been renamed since it was created
+    let memorial = MemorialInfo {
+        pet_id,
+        name: memorial.name.clone(),
+        memorial,
+    };
+    append_memorial(&mut deps.storage, &memorial)?;
+    let mut hall_of_fame = hall_of_fame(&mut deps.storage, memorial.memorial.difficulty);
+    let mut ranking = hall_of_fame.may_load()?.unwrap_or_default();
+    // after the lives that were at least as long, so earlier deaths keep their place
+    let rank = ranking
+        .iter()
+        .position(|other| other.memorial.lifespan < memorial.memorial.lifespan)
+        .unwrap_or(ranking.len());
+    ranking.insert(rank, memorial);
+    ranking.truncate(HALL_OF_FAME_SIZE);
+    hall_of_fame.save(&ranking)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("buried", pet_id)],
+        data: None,
+    })
+}
+
+pub fn try_rename_pet<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+    name: String,
+) -> StdResult<HandleResponse> {
+    let mut pet = registered_pet(deps, &env, pet_id)?;
+    pet.name = name;
+    save_pet(&mut deps.storage, pet_id, &pet)?;
+    Ok(HandleResponse::default())
+}
+
+pub fn try_put_up_for_adoption<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+    born_at: u64,
+    deadline: u64,
+) -> StdResult<HandleResponse> {
+    registered_pet(deps, &env, pet_id)?;
+    let mut pool = adoption_pool(&mut deps.storage);
+    let mut listings = pool.may_load()?.unwrap_or_default();
+    listings.retain(|listing| listing.pet_id != pet_id);
+    listings.push(AdoptionListing {
+        pet_id,
+        born_at,
+        deadline,
+    });
+    pool.save(&listings)?;
+    Ok(HandleResponse::default())
+}
+
+/// The pet leaves the pool, and shows up among the new owner's pets
+pub fn try_change_owner<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+    owner: HumanAddr,
+) -> StdResult<HandleResponse> {
+    let mut pet = registered_pet(deps, &env, pet_id)?;
+    let owner = deps.api.canonical_address(&owner)?;
+    if owner != pet.owner {
+        remove_owner_pet(&mut deps.storage, &pet.owner, pet_id)?;
+        append_owner_pet(&mut deps.storage, &owner, pet_id)?;
+        pet.owner = owner;
+        save_pet(&mut deps.storage, pet_id, &pet)?;
+    }
+    let mut pool = adoption_pool(&mut deps.storage);
+    let mut listings = pool.may_load()?.unwrap_or_default();
+    listings.retain(|listing| listing.pet_id != pet_id);
+    pool.save(&listings)?;
+    Ok(HandleResponse::default())
+}
+
+/// The host checks the guest is on its friend list, if not, the whole visit fails
+pub fn try_visit_pet<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+    host: HumanAddr,
+    host_code_hash: String,
+) -> StdResult<HandleResponse> {
+    let guest = registered_pet(deps, &env, pet_id)?.address;
+    let visit = PetHandleMsg::ReceiveVisit { guest }.to_cosmos_msg(host_code_hash, host, None)?;
+    Ok(HandleResponse {
+        messages: vec![visit],
+        log: vec![log("visit", pet_id)],
+        data: None,
+    })
+}
+
+pub fn try_schedule_world_event<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    event: WorldEvent,
+) -> StdResult<HandleResponse> {
+    check_if_admin(deps, &env)?;
+    let mut events = world_events_read(&deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    let name = event.name.clone();
+    schedule_world_event(&mut events, event, env.block.time)?;
+    world_events(&mut deps.storage).save(&events)?;
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("world_event", name)],
+        data: None,
+    })
+}
+
+pub fn try_cancel_world_event<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    name: String,
+) -> StdResult<HandleResponse> {
+    check_if_admin(deps, &env)?;
+    let mut events = world_events_read(&deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    cancel_world_event(&mut events, &name, env.block.time)?;
+    world_events(&mut deps.storage).save(&events)?;
+    Ok(HandleResponse::default())
+}
+
+fn check_if_admin<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: &Env,
+) -> StdResult<()> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    if config_read(&deps.storage).load()?.owner != sender {
+        return Err(StdError::unauthorized());
+    }
+    Ok(())
+}
+
+/// Loads the pet, as long as it's the one sending the message
+fn registered_pet<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: &Env,
+    pet_id: u64,
+) -> StdResult<StoredPet> {
+    match load_pet(&deps.storage, pet_id) {
+        Ok(pet) if pet.address == env.message.sender => Ok(pet),
+        _ => Err(StdError::unauthorized()),
+    }
+}
+
+/// One-time secret the new Pet has to echo back in `RegisterPet`, so nobody else can register
+fn new_password(prng_seed: &[u8], pet_id: u64, env: &Env) -> Vec<u8> {
+    let mut data = prng_seed.to_vec();
+    data.extend_from_slice(&pet_id.to_be_bytes());
+    data.extend_from_slice(&env.block.height.to_be_bytes());
+    data.extend_from_slice(&env.block.time.to_be_bytes());
+    sha_256(&data).to_vec()
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => query_config(deps),
+        QueryMsg::Pets { page, page_size } => query_pets(deps, page.unwrap_or(0), page_size),
+        QueryMsg::PetsByOwner {
+            owner,
+            page,
+            page_size,
+        } => query_pets_by_owner(deps, &owner, page.unwrap_or(0), page_size),
+        QueryMsg::Graveyard { page, page_size } => {
+            let (memorials, total) = get_memorials(&deps.storage, page.unwrap_or(0), page_size)?;
+            to_binary(&QueryAnswer::Memorials { memorials, total })
+        }
+        QueryMsg::AdoptionPool {
+            time,
+            stage,
+            page,
+            page_size,
+        } => query_adoption_pool(deps, time, stage, page.unwrap_or(0), page_size),
+        QueryMsg::HallOfFame {
+            difficulty,
+            page,
+            page_size,
+        } => query_hall_of_fame(deps, difficulty, page.unwrap_or(0), page_size),
+        QueryMsg::WorldEvents {} => to_binary(&QueryAnswer::WorldEvents {
+            events: world_events_read(&deps.storage)
+                .may_load()?
+                .unwrap_or_default(),
+        }),
+    }
+}
+
+fn query_config<S: Storage, A: Api, Q: Querier>(deps: &Extern<S, A, Q>) -> StdResult<Binary> {
+    let state = config_read(&deps.storage).load()?;
+    to_binary(&QueryAnswer::Config {
+        owner: deps.api.human_address(&state.owner)?,
+        pet_code_id: state.pet_code.code_id,
+        pet_code_hash: state.pet_code.code_hash,
+        token_address: state.token.address,
+        token_code_hash: state.token.code_hash,
+    })
+}
+
+fn query_pets<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    page: u32,
+    page_size: u32,
+) -> StdResult<Binary> {
+    let total = config_read(&deps.storage).load()?.pet_count;
+    let start = (page as u64) * (page_size as u64);
+    let end = total.min(start + page_size as u64);
+    let pets: StdResult<Vec<PetInfo>> = (start..end).map(|pet_id| pet_info(deps, pet_id)).collect();
+    to_binary(&QueryAnswer::Pets { pets: pets?, total })
+}
+
+fn query_pets_by_owner<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    owner: &HumanAddr,
+    page: u32,
+    page_size: u32,
+) -> StdResult<Binary> {
+    let owner = deps.api.canonical_address(owner)?;
+    let (pet_ids, total) = get_owner_pet_ids(&deps.storage, &owner, page, page_size)?;
+    let pets: StdResult<Vec<PetInfo>> = pet_ids
+        .into_iter()
+        .map(|pet_id| pet_info(deps, pet_id))
+        .collect();
+    to_binary(&QueryAnswer::Pets { pets: pets?, total })
+}
+
+fn query_adoption_pool<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    time: u64,
+    stage: Option<Stage>,
+    page: u32,
+    page_size: u32,
+) -> StdResult<Binary> {
+    let pool = adoption_pool_read(&deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    let mut pets = vec![];
+    for listing in pool {
+        // past the deadline the pet is the shelter's, even if nobody told it yet
+        if listing.deadline <= time {
+            continue;
+        }
+        let listing_stage = Stage::at_age(time.saturating_sub(listing.born_at));
+        if stage.map_or(false, |stage| stage != listing_stage) {
+            continue;
+        }
+        let pet = load_pet(&deps.storage, listing.pet_id)?;
+        pets.push(AdoptablePet {
+            pet_id: listing.pet_id,
+            address: pet.address,
+            name: pet.name,
+            stage: listing_stage,
+            deadline: listing.deadline,
+        });
+    }
+    let total = pets.len() as u64;
+    let pets = pets
+        .into_iter()
+        .skip(page_start(page, page_size))
+        .take(page_size as _)
+        .collect();
+    to_binary(&QueryAnswer::AdoptionPool { pets, total })
+}
+
+fn query_hall_of_fame<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    difficulty: Difficulty,
+    page: u32,
+    page_size: u32,
+) -> StdResult<Binary> {
+    let hall_of_fame = hall_of_fame_read(&deps.storage, difficulty)
+        .may_load()?
+        .unwrap_or_default();
+    let total = hall_of_fame.len() as u64;
+    let memorials = hall_of_fame
+        .into_iter()
+        .skip(page_start(page, page_size))
+        .take(page_size as _)
+        .collect();
+    to_binary(&QueryAnswer::Memorials { memorials, total })
+}
+
+fn pet_info<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    pet_id: u64,
+) -> StdResult<PetInfo> {
+    let pet = load_pet(&deps.storage, pet_id)?;
+    Ok(PetInfo {
+        pet_id,
+        address: pet.address,
+        name: pet.name,
+        owner: deps.api.human_address(&pet.owner)?,
+        created_at: pet.created_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{from_binary, CosmosMsg, Uint128, WasmMsg};
+
+    use crate::msg::CauseOfDeath;
+    use shared::world::MAX_PAST_WORLD_EVENTS;
+
+    fn init_helper() -> Extern<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies(20, &[]);
+        let msg = InitMsg {
+            pet_code_id: 4,
+            pet_code_hash: "pet_hash".to_string(),
+            token_code_hash: "food_hash".to_string(),
+            token_address: "food".to_string(),
+            prng_seed: Binary::from("seed".as_bytes()),
+        };
+        init(&mut deps, mock_env("admin", &[]), msg).unwrap();
+        deps
+    }
+
+    /// Creates a pet on behalf of `owner` and replays the Pet's post-init callback
+    fn create_pet(
+        deps: &mut Extern<MockStorage, MockApi, MockQuerier>,
+        owner: &str,
+        name: &str,
+        pet_address: &str,
+    ) {
+        let msg = HandleMsg::CreatePet {
+            name: name.to_string(),
+            difficulty: None,
+            intervals: None,
+        };
+        let res = handle(deps, mock_env(owner, &[]), msg).unwrap();
+        let pet_init: PetInitMsg = match &res.messages[0] {
+            CosmosMsg::Wasm(WasmMsg::Instantiate { msg, code_id, .. }) => {
+                assert_eq!(*code_id, 4);
+                from_binary(msg).unwrap()
+            }
+            _ => panic!("Expected a pet instantiation"),
+        };
+        assert_eq!(pet_init.owner, Some(HumanAddr::from(owner)));
+        let callback = pet_init.factory.unwrap();
+        let msg = HandleMsg::RegisterPet {
+            pet_id: callback.pet_id,
+            password: callback.password,
+        };
+        handle(deps, mock_env(pet_address, &[]), msg).unwrap();
+    }
+
+    fn query_pets_helper(
+        deps: &Extern<MockStorage, MockApi, MockQuerier>,
+        msg: QueryMsg,
+    ) -> (Vec<PetInfo>, u64) {
+        match from_binary(&query(deps, msg).unwrap()).unwrap() {
+            QueryAnswer::Pets { pets, total } => (pets, total),
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    #[test]
+    fn create_and_index_pets() {
+        let mut deps = init_helper();
+        create_pet(&mut deps, "alice", "Tama", "pet-one");
+        create_pet(&mut deps, "bob", "Gotchi", "pet-two");
+        create_pet(&mut deps, "alice", "Mametchi", "pet-three");
+
+        let (pets, total) = query_pets_helper(
+            &deps,
+            QueryMsg::Pets {
+                page: None,
+                page_size: 2,
+            },
+        );
+        assert_eq!(total, 3);
+        assert_eq!(pets.len(), 2);
+        assert_eq!(pets[0].name, "Tama");
+        assert_eq!(pets[1].address, HumanAddr::from("pet-two"));
+
+        let (pets, total) = query_pets_helper(
+            &deps,
+            QueryMsg::Pets {
+                page: Some(1),
+                page_size: 2,
+            },
+        );
+        assert_eq!(total, 3);
+        assert_eq!(pets.len(), 1);
+        assert_eq!(pets[0].pet_id, 2);
+
+        let (pets, total) = query_pets_helper(
+            &deps,
+            QueryMsg::PetsByOwner {
+                owner: HumanAddr::from("alice"),
+                page: None,
+                page_size: 10,
+            },
+        );
+        assert_eq!(total, 2);
+        assert_eq!(pets[0].address, HumanAddr::from("pet-one"));
+        assert_eq!(pets[1].address, HumanAddr::from("pet-three"));
+        assert_eq!(pets[1].owner, HumanAddr::from("alice"));
+    }
+
+    #[test]
+    fn register_requires_pending_pet_and_password() {
+        let mut deps = init_helper();
+        let msg = HandleMsg::RegisterPet {
+            pet_id: 0,
+            password: Binary::from("guess".as_bytes()),
+        };
+        let res = handle(&mut deps, mock_env("impostor", &[]), msg.clone());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+
+        let create = HandleMsg::CreatePet {
+            name: "Tama".to_string(),
+            difficulty: None,
+            intervals: None,
+        };
+        handle(&mut deps, mock_env("alice", &[]), create).unwrap();
+        let res = handle(&mut deps, mock_env("impostor", &[]), msg);
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+    }
+
+    #[test]
+    fn create_pet_validates_name() {
+        let mut deps = init_helper();
+        for name in ["Ta", "<b>Tama</b>"] {
+            let msg = HandleMsg::CreatePet {
+                name: name.to_string(),
+                difficulty: None,
+                intervals: None,
+            };
+            let res = handle(&mut deps, mock_env("alice", &[]), msg);
+            assert!(res.is_err());
+        }
+    }
+
+    #[test]
+    fn only_owner_sets_pet_code() {
+        let mut deps = init_helper();
+        let msg = HandleMsg::SetPetCode {
+            code_id: 5,
+            code_hash: "new_hash".to_string(),
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), msg.clone());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        handle(&mut deps, mock_env("admin", &[]), msg).unwrap();
+        assert_eq!(
+            config_read(&deps.storage).load().unwrap().pet_code.code_id,
+            5
+        );
+    }
+
+    fn bury(
+        deps: &mut Extern<MockStorage, MockApi, MockQuerier>,
+        sender: &str,
+        pet_id: u64,
+        name: &str,
+        lifespan: u64,
+        difficulty: Difficulty,
+    ) -> StdResult<HandleResponse> {
+        let msg = HandleMsg::BuryPet {
+            pet_id,
+            memorial: Memorial {
+                name: name.to_string(),
+                owner: HumanAddr::from("alice"),
+                born_at: 1000,
+                died_at: 1000 + lifespan,
+                lifespan,
+                food_eaten: Uint128(100),
+                cause: CauseOfDeath::Starvation,
+                difficulty,
+            },
+        };
+        handle(deps, mock_env(sender, &[]), msg)
+    }
+
+    fn memorials(deps: &Extern<MockStorage, MockApi, MockQuerier>, msg: QueryMsg) -> Vec<String> {
+        match from_binary(&query(deps, msg).unwrap()).unwrap() {
+            QueryAnswer::Memorials { memorials, .. } => memorials
+                .into_iter()
+                .map(|memorial| memorial.name)
+                .collect(),
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    #[test]
+    fn graveyard_and_hall_of_fame() {
+        let mut deps = init_helper();
+        create_pet(&mut deps, "alice", "Tama", "pet-one");
+        create_pet(&mut deps, "bob", "Gotchi", "pet-two");
+        create_pet(&mut deps, "alice", "Mametchi", "pet-three");
+
+        let res = bury(&mut deps, "pet-two", 0, "Tama", 500, Difficulty::Normal);
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = bury(&mut deps, "pet-two", 9, "Tama", 500, Difficulty::Normal);
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+
+        bury(&mut deps, "pet-one", 0, "Tama", 500, Difficulty::Normal).unwrap();
+        bury(&mut deps, "pet-two", 1, "Gotchi", 900, Difficulty::Normal).unwrap();
+        bury(
+            &mut deps,
+            "pet-three",
+            2,
+            "Mametchi",
+            500,
+            Difficulty::Normal,
+        )
+        .unwrap();
+        // revived pets die again
+        bury(&mut deps, "pet-one", 0, "Tama", 700, Difficulty::Normal).unwrap();
+        // hardcore lives are ranked on their own
+        bury(
+            &mut deps,
+            "pet-three",
+            2,
+            "Mametchi",
+            300,
+            Difficulty::Hardcore,
+        )
+        .unwrap();
+
+        let graveyard = QueryMsg::Graveyard {
+            page: None,
+            page_size: 3,
+        };
+        assert_eq!(
+            memorials(&deps, graveyard),
+            vec!["Mametchi", "Tama", "Mametchi"]
+        );
+        let hall_of_fame = QueryMsg::HallOfFame {
+            difficulty: Difficulty::Normal,
+            page: None,
+            page_size: 10,
+        };
+        assert_eq!(
+            memorials(&deps, hall_of_fame),
+            vec!["Gotchi", "Tama", "Tama", "Mametchi"]
+        );
+        let hall_of_fame = QueryMsg::HallOfFame {
+            difficulty: Difficulty::Normal,
+            page: Some(1),
+            page_size: 3,
+        };
+        assert_eq!(memorials(&deps, hall_of_fame), vec!["Mametchi"]);
+        let hall_of_fame = QueryMsg::HallOfFame {
+            difficulty: Difficulty::Hardcore,
+            page: None,
+            page_size: 10,
+        };
+        assert_eq!(memorials(&deps, hall_of_fame), vec!["Mametchi"]);
+        let hall_of_fame = QueryMsg::HallOfFame {
+            difficulty: Difficulty::Easy,
+            page: None,
+            page_size: 10,
+        };
+        assert!(memorials(&deps, hall_of_fame).is_empty());
+    }
+
+    #[test]
+    fn pets_report_renames() {
+        let mut deps = init_helper();
+        create_pet(&mut deps, "alice", "Tama", "pet-one");
+        let rename = |name: &str| HandleMsg::RenamePet {
+            pet_id: 0,
+            name: name.to_string(),
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), rename("Mametchi"));
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        handle(&mut deps, mock_env("pet-one", &[]), rename("Mametchi")).unwrap();
+
+        let (pets, _) = query_pets_helper(
+            &deps,
+            QueryMsg::Pets {
+                page: None,
+                page_size: 10,
+            },
+        );
+        assert_eq!(pets[0].name, "Mametchi");
+    }
+
+    #[test]
+    fn visits_go_through_the_factory() {
+        let mut deps = init_helper();
+        create_pet(&mut deps, "alice", "Tama", "pet-one");
+        let visit = HandleMsg::VisitPet {
+            pet_id: 0,
+            host: HumanAddr::from("pet-two"),
+            host_code_hash: "pet_hash".to_string(),
+        };
+        // a wallet can't pass itself off as the pet
+        let res = handle(&mut deps, mock_env("alice", &[]), visit.clone());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = handle(&mut deps, mock_env("pet-one", &[]), visit).unwrap();
+        let message = PetHandleMsg::ReceiveVisit {
+            guest: HumanAddr::from("pet-one"),
+        }
+        .to_cosmos_msg("pet_hash".to_string(), HumanAddr::from("pet-two"), None)
+        .unwrap();
+        assert_eq!(res.messages, vec![message]);
+    }
+
+    fn world_event(name: &str, start: u64, end: u64) -> HandleMsg {
+        HandleMsg::ScheduleWorldEvent {
+            event: WorldEvent {
+                name: name.to_string(),
+                start,
+                end,
+                hunger_rate: 150,
+                cost_rate: 200,
+            },
+        }
+    }
+
+    fn world_event_names(deps: &Extern<MockStorage, MockApi, MockQuerier>) -> Vec<String> {
+        match from_binary(&query(deps, QueryMsg::WorldEvents {}).unwrap()).unwrap() {
+            QueryAnswer::WorldEvents { events } => {
+                events.into_iter().map(|event| event.name).collect()
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    fn env_at(sender: &str, time: u64) -> Env {
+        let mut env = mock_env(sender, &[]);
+        env.block.time = time;
+        env
+    }
+
+    fn extract_error_msg<T>(result: StdResult<T>) -> String {
+        match result {
+            Err(StdError::GenericErr { msg, .. }) => msg,
+            Err(err) => panic!("Unexpected error: {:?}", err),
+            Ok(_) => panic!("Expected an error"),
+        }
+    }
+
+    #[test]
+    fn admin_schedules_world_events_for_every_pet() {
+        let mut deps = init_helper();
+        let start = 1_000_000;
+        let hour = 3600;
+        let heatwave = world_event("heatwave", start + hour, start + 3 * hour);
+        let res = handle(&mut deps, env_at("pet-one", start), heatwave.clone());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = handle(
+            &mut deps,
+            env_at("admin", start + hour + 1),
+            heatwave.clone(),
+        );
+        assert_eq!(
+            extract_error_msg(res),
+            "World events can't start in the past"
+        );
+        handle(&mut deps, env_at("admin", start), heatwave.clone()).unwrap();
+        let res = handle(&mut deps, env_at("admin", start), heatwave);
+        assert_eq!(
+            extract_error_msg(res),
+            "There's already a world event with that name"
+        );
+        let festival = world_event("festival", start + 2 * hour, start + 5 * hour);
+        handle(&mut deps, env_at("admin", start), festival).unwrap();
+        assert_eq!(world_event_names(&deps), vec!["heatwave", "festival"]);
+
+        // cancelling keeps the part that already happened, pets lived through it
+        let cancel = |name: &str| HandleMsg::CancelWorldEvent {
+            name: name.to_string(),
+        };
+        let res = handle(
+            &mut deps,
+            env_at("admin", start + 4 * hour),
+            cancel("heatwave"),
+        );
+        assert_eq!(extract_error_msg(res), "No such world event");
+        handle(
+            &mut deps,
+            env_at("admin", start + 4 * hour),
+            cancel("festival"),
+        )
+        .unwrap();
+        assert_eq!(world_event_names(&deps), vec!["heatwave", "festival"]);
+
+        // only the latest of the events that ended are kept
+        let later = start + 10 * hour;
+        for index in 0..MAX_PAST_WORLD_EVENTS as u64 {
+            let name = format!("storm {}", index);
+            let event = world_event(&name, later + index, later + index + 1);
+            handle(&mut deps, env_at("admin", later + index), event).unwrap();
+        }
+        let names = world_event_names(&deps);
+        assert_eq!(names.len(), MAX_PAST_WORLD_EVENTS + 1);
+        assert_eq!(names[0], "festival");
+    }
+
+    fn adoption_pool_names(
+        deps: &Extern<MockStorage, MockApi, MockQuerier>,
+        time: u64,
+        stage: Option<Stage>,
+    ) -> Vec<String> {
+        let msg = QueryMsg::AdoptionPool {
+            time,
+            stage,
+            page: None,
+            page_size: 10,
+        };
+        match from_binary(&query(deps, msg).unwrap()).unwrap() {
+            QueryAnswer::AdoptionPool { pets, .. } => {
+                pets.into_iter().map(|pet| pet.name).collect()
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    #[test]
+    fn abandoned_pets_find_new_owners() {
+        let mut deps = init_helper();
+        create_pet(&mut deps, "alice", "Tama", "pet-one");
+        create_pet(&mut deps, "bob", "Gotchi", "pet-two");
+        let now = 1_000_000_000;
+        let deadline = now + 7 * 86400;
+        let put_up = |pet_id, born_at| HandleMsg::PutUpForAdoption {
+            pet_id,
+            born_at,
+            deadline,
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), put_up(0, now));
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        handle(&mut deps, mock_env("pet-one", &[]), put_up(0, now)).unwrap();
+        handle(
+            &mut deps,
+            mock_env("pet-two", &[]),
+            put_up(1, now - 10 * 86400),
+        )
+        .unwrap();
+
+        assert_eq!(
+            adoption_pool_names(&deps, now + 3600, None),
+            vec!["Tama", "Gotchi"]
+        );
+        assert_eq!(
+            adoption_pool_names(&deps, now + 3600, Some(Stage::Baby)),
+            vec!["Tama"]
+        );
+        assert_eq!(
+            adoption_pool_names(&deps, now + 3600, Some(Stage::Adult)),
+            vec!["Gotchi"]
+        );
+        assert!(adoption_pool_names(&deps, deadline, None).is_empty());
+
+        let msg = HandleMsg::ChangeOwner {
+            pet_id: 0,
+            owner: HumanAddr::from("carol"),
+        };
+        handle(&mut deps, mock_env("pet-one", &[]), msg).unwrap();
+        assert_eq!(adoption_pool_names(&deps, now + 3600, None), vec!["Gotchi"]);
+        let owned_by = |owner: &str| {
+            let msg = QueryMsg::PetsByOwner {
+                owner: HumanAddr::from(owner),
+                page: None,
+                page_size: 10,
+            };
+            let (pets, total) = query_pets_helper(&deps, msg);
+            (
+                pets.into_iter().map(|pet| pet.name).collect::<Vec<_>>(),
+                total,
+            )
+        };
+        assert_eq!(owned_by("alice"), (vec![], 0));
+        assert_eq!(owned_by("carol"), (vec!["Tama".to_string()], 1));
+    }
+
+    #[test]
+    fn owner_index_follows_pets_that_change_hands() {
+        let mut deps = init_helper();
+        create_pet(&mut deps, "alice", "Tama", "pet-one");
+        create_pet(&mut deps, "alice", "Gotchi", "pet-two");
+        create_pet(&mut deps, "alice", "Mametchi", "pet-three");
+        let change_owner = |pet_id, owner: &str| HandleMsg::ChangeOwner {
+            pet_id,
+            owner: HumanAddr::from(owner),
+        };
+        handle(&mut deps, mock_env("pet-one", &[]), change_owner(0, "bob")).unwrap();
+        handle(
+            &mut deps,
+            mock_env("pet-two", &[]),
+            change_owner(1, "alice"),
+        )
+        .unwrap();
+        handle(
+            &mut deps,
+            mock_env("pet-one", &[]),
+            change_owner(0, "alice"),
+        )
+        .unwrap();
+        handle(
+            &mut deps,
+            mock_env("pet-three", &[]),
+            change_owner(2, "bob"),
+        )
+        .unwrap();
+
+        let owned_by = |owner: &str, page, page_size| {
+            let msg = QueryMsg::PetsByOwner {
+                owner: HumanAddr::from(owner),
+                page: Some(page),
+                page_size,
+            };
+            let (pets, total) = query_pets_helper(&deps, msg);
+            (
+                pets.into_iter().map(|pet| pet.name).collect::<Vec<_>>(),
+                total,
+            )
+        };
+        assert_eq!(
+            owned_by("alice", 0, 10),
+            (vec!["Tama".to_string(), "Gotchi".to_string()], 2)
+        );
+        assert_eq!(owned_by("alice", 1, 1), (vec!["Gotchi".to_string()], 2));
+        assert_eq!(owned_by("bob", 0, 10), (vec!["Mametchi".to_string()], 1));
+
+        // pages too far out are empty instead of overflowing
+        assert_eq!(owned_by("alice", u32::MAX, u32::MAX), (vec![], 2));
+        let graveyard = QueryMsg::Graveyard {
+            page: Some(u32::MAX),
+            page_size: 2,
+        };
+        assert!(memorials(&deps, graveyard).is_empty());
+        let hall_of_fame = QueryMsg::HallOfFame {
+            difficulty: Difficulty::Normal,
+            page: Some(u32::MAX),
+            page_size: 2,
+        };
+        assert!(memorials(&deps, hall_of_fame).is_empty());
+    }
+}
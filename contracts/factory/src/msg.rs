@@ -0,0 +1,283 @@
+use cosmwasm_std::{Binary, HumanAddr, Uint128};
+use schemars::JsonSchema;
+use secret_toolkit::utils::{HandleCallback, InitCallback};
+use serde::{Deserialize, Serialize};
+
+use shared::world::WorldEvent;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InitMsg {
+    pub pet_code_id: u64,
+    pub pet_code_hash: String,
+    pub token_code_hash: String,
+    pub token_address: String,
+    pub prng_seed: Binary,
+}
+
+/// Mirrors the Pet contract's `Duration`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Duration {
+    Seconds(u64),
+    Minutes(u64),
+    Hours(u64),
+    Days(u64),
+}
+
+/// Mirrors the Pet contract's `Difficulty`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Hardcore,
+    Custom,
+}
+
+impl Difficulty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Hardcore => "hardcore",
+            Difficulty::Custom => "custom",
+        }
+    }
+}
+
+/// Life stage by age, for browsing the adoption pool
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// Under a day old
+    Baby,
+    /// Under a week old
+    Child,
+    /// Under 30 days old
+    Adult,
+    Elder,
+}
+
+impl Stage {
+    pub fn at_age(age: u64) -> Self {
+        const DAY: u64 = 24 * 60 * 60;
+        match age {
+            age if age < DAY => Stage::Baby,
+            age if age < 7 * DAY => Stage::Child,
+            age if age < 30 * DAY => Stage::Adult,
+            _ => Stage::Elder,
+        }
+    }
+}
+
+/// Satiation and starvation periods passed through to the new Pet
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Intervals {
+    pub satiated_interval: Option<Duration>,
+    pub starving_interval: Option<Duration>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleMsg {
+    CreatePet {
+        name: String,
+        /// Defaults to `Normal`, intervals easier than the preset's make the pet `Custom`
+        difficulty: Option<Difficulty>,
+        intervals: Option<Intervals>,
+    },
+    /// Post-init callback, sent by the Pet contract instantiated in `CreatePet`
+    RegisterPet {
+        pet_id: u64,
+        password: Binary,
+    },
+    SetPetCode {
+        code_id: u64,
+        code_hash: String,
+    },
+    /// Sent by a registered Pet when it dies
+    BuryPet {
+        pet_id: u64,
+        memorial: Memorial,
+    },
+    /// Sent by a registered Pet when its owner renames it
+    RenamePet {
+        pet_id: u64,
+        name: String,
+    },
+    /// Sent by a registered Pet when its owner abandons it
+    PutUpForAdoption {
+        pet_id: u64,
+        born_at: u64,
+        deadline: u64,
+    },
+    /// Sent by a registered Pet when it's adopted, or taken in by the shelter
+    ChangeOwner {
+        pet_id: u64,
+        owner: HumanAddr,
+    },
+    /// Sent by a registered Pet when its owner takes it to visit `host`. Passed on to the host,
+    /// which can then trust the guest is a pet
+    VisitPet {
+        pet_id: u64,
+        host: HumanAddr,
+        host_code_hash: String,
+    },
+    /// Admin only, names have to be unique among the events that haven't ended
+    ScheduleWorldEvent {
+        event: WorldEvent,
+    },
+    /// Admin only, an event that already started ends right away
+    CancelWorldEvent {
+        name: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    Pets {
+        page: Option<u32>,
+        page_size: u32,
+    },
+    PetsByOwner {
+        owner: HumanAddr,
+        page: Option<u32>,
+        page_size: u32,
+    },
+    /// Latest death first
+    Graveyard {
+        page: Option<u32>,
+        page_size: u32,
+    },
+    /// Pets that can still be adopted at `time`, the ones abandoned first come first
+    AdoptionPool {
+        time: u64,
+        stage: Option<Stage>,
+        page: Option<u32>,
+        page_size: u32,
+    },
+    /// Longest lives first, only the top `HALL_OF_FAME_SIZE` of each difficulty are kept
+    HallOfFame {
+        difficulty: Difficulty,
+        page: Option<u32>,
+        page_size: u32,
+    },
+    /// Every event the pets still go by, the ones that ended recently too
+    WorldEvents {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PetInfo {
+    pub pet_id: u64,
+    pub address: HumanAddr,
+    pub name: String,
+    pub owner: HumanAddr,
+    pub created_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryAnswer {
+    Config {
+        owner: HumanAddr,
+        pet_code_id: u64,
+        pet_code_hash: String,
+        token_address: HumanAddr,
+        token_code_hash: String,
+    },
+    Pets {
+        pets: Vec<PetInfo>,
+        total: u64,
+    },
+    Memorials {
+        memorials: Vec<MemorialInfo>,
+        total: u64,
+    },
+    AdoptionPool {
+        pets: Vec<AdoptablePet>,
+        total: u64,
+    },
+    WorldEvents {
+        events: Vec<WorldEvent>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AdoptablePet {
+    pub pet_id: u64,
+    pub address: HumanAddr,
+    pub name: String,
+    pub stage: Stage,
+    pub deadline: u64,
+}
+
+/// Mirrors the Pet contract's `CauseOfDeath`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CauseOfDeath {
+    Starvation,
+    Sickness,
+}
+
+/// Mirrors the Pet contract's `Memorial`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Memorial {
+    pub name: String,
+    pub owner: HumanAddr,
+    pub born_at: u64,
+    pub died_at: u64,
+    pub lifespan: u64,
+    pub food_eaten: Uint128,
+    pub cause: CauseOfDeath,
+    pub difficulty: Difficulty,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MemorialInfo {
+    pub pet_id: u64,
+    pub name: String,
+    pub memorial: Memorial,
+}
+
+/// Factory details handed to a Pet, so it can report back once instantiated
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FactoryCallback {
+    pub address: HumanAddr,
+    pub code_hash: String,
+    pub pet_id: u64,
+    pub password: Binary,
+}
+
+/// Mirrors the Pet contract's `InitMsg`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PetInitMsg {
+    pub name: String,
+    pub token_code_hash: String,
+    pub token_address: String,
+    pub difficulty: Option<Difficulty>,
+    pub satiated_interval: Option<Duration>,
+    pub starving_interval: Option<Duration>,
+    pub owner: Option<HumanAddr>,
+    pub admin: Option<HumanAddr>,
+    pub factory: Option<FactoryCallback>,
+    pub prng_seed: Binary,
+}
+
+impl InitCallback for PetInitMsg {
+    const BLOCK_SIZE: usize = 256;
+}
+
+/// Pet messages the factory sends
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PetHandleMsg {
+    ReceiveVisit { guest: HumanAddr },
+}
+
+impl HandleCallback for PetHandleMsg {
+    const BLOCK_SIZE: usize = 256;
+}
@@ -0,0 +1,241 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CanonicalAddr, HumanAddr, ReadonlyStorage, StdError, StdResult, Storage};
+use cosmwasm_storage::{
+    singleton, singleton_read, PrefixedStorage, ReadonlyPrefixedStorage, ReadonlySingleton,
+    Singleton,
+};
+use secret_toolkit::storage::{AppendStore, AppendStoreMut, TypedStore, TypedStoreMut};
+
+use shared::paging::page_start;
+use shared::world::WorldEvent;
+
+use crate::msg::{Difficulty, MemorialInfo};
+
+pub static CONFIG_KEY: &[u8] = b"config";
+pub static PENDING_KEY: &[u8] = b"pending";
+pub const PREFIX_PETS: &[u8] = b"pets";
+pub const PREFIX_OWNER_PETS: &[u8] = b"owner_pets";
+pub const PREFIX_OWNER_PET_POSITIONS: &[u8] = b"owner_pet_positions";
+pub const PREFIX_GRAVEYARD: &[u8] = b"graveyard";
+pub static HALL_OF_FAME_KEY: &[u8] = b"hall_of_fame";
+pub static ADOPTION_POOL_KEY: &[u8] = b"adoption_pool";
+pub static WORLD_EVENTS_KEY: &[u8] = b"world_events";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenInfo {
+    pub code_hash: String,
+    pub address: HumanAddr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PetCode {
+    pub code_id: u64,
+    pub code_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct State {
+    pub owner: CanonicalAddr,
+    pub pet_code: PetCode,
+    pub token: TokenInfo,
+    pub prng_seed: Vec<u8>,
+    pub pet_count: u64,
+}
+
+/// Pet which was instantiated, but didn't report its address yet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PendingPet {
+    pub pet_id: u64,
+    pub password: Vec<u8>,
+    pub name: String,
+    pub owner: CanonicalAddr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StoredPet {
+    pub address: HumanAddr,
+    pub name: String,
+    pub owner: CanonicalAddr,
+    pub created_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AdoptionListing {
+    pub pet_id: u64,
+    pub born_at: u64,
+    pub deadline: u64,
+}
+
+pub fn config<S: Storage>(storage: &mut S) -> Singleton<S, State> {
+    singleton(storage, CONFIG_KEY)
+}
+
+pub fn config_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, State> {
+    singleton_read(storage, CONFIG_KEY)
+}
+
+pub fn pending<S: Storage>(storage: &mut S) -> Singleton<S, Option<PendingPet>> {
+    singleton(storage, PENDING_KEY)
+}
+
+pub fn pending_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Option<PendingPet>> {
+    singleton_read(storage, PENDING_KEY)
+}
+
+/// Each difficulty is ranked on its own, so easy lives don't crowd out the hard ones
+pub fn hall_of_fame<S: Storage>(
+    storage: &mut S,
+    difficulty: Difficulty,
+) -> Singleton<S, Vec<MemorialInfo>> {
+    singleton(storage, &hall_of_fame_key(difficulty))
+}
+
+pub fn hall_of_fame_read<S: Storage>(
+    storage: &S,
+    difficulty: Difficulty,
+) -> ReadonlySingleton<S, Vec<MemorialInfo>> {
+    singleton_read(storage, &hall_of_fame_key(difficulty))
+}
+
+fn hall_of_fame_key(difficulty: Difficulty) -> Vec<u8> {
+    [HALL_OF_FAME_KEY, difficulty.name().as_bytes()].concat()
+}
+
+/// Abandoned pets are few and adopted quickly, so the pool is kept in a single list
+pub fn adoption_pool<S: Storage>(storage: &mut S) -> Singleton<S, Vec<AdoptionListing>> {
+    singleton(storage, ADOPTION_POOL_KEY)
+}
+
+pub fn adoption_pool_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Vec<AdoptionListing>> {
+    singleton_read(storage, ADOPTION_POOL_KEY)
+}
+
+/// Every pet of the factory lives by the same schedule, the pets read it from here
+pub fn world_events<S: Storage>(storage: &mut S) -> Singleton<S, Vec<WorldEvent>> {
+    singleton(storage, WORLD_EVENTS_KEY)
+}
+
+pub fn world_events_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Vec<WorldEvent>> {
+    singleton_read(storage, WORLD_EVENTS_KEY)
+}
+
+// Pets
+
+pub fn save_pet<S: Storage>(storage: &mut S, pet_id: u64, pet: &StoredPet) -> StdResult<()> {
+    let mut store = PrefixedStorage::new(PREFIX_PETS, storage);
+    let mut store = TypedStoreMut::attach(&mut store);
+    store.store(&pet_id.to_be_bytes(), pet)
+}
+
+pub fn load_pet<S: ReadonlyStorage>(storage: &S, pet_id: u64) -> StdResult<StoredPet> {
+    let store = ReadonlyPrefixedStorage::new(PREFIX_PETS, storage);
+    let store = TypedStore::attach(&store);
+    store.load(&pet_id.to_be_bytes())
+}
+
+pub fn append_owner_pet<S: Storage>(
+    storage: &mut S,
+    owner: &CanonicalAddr,
+    pet_id: u64,
+) -> StdResult<()> {
+    let mut store = PrefixedStorage::multilevel(&[PREFIX_OWNER_PETS, owner.as_slice()], storage);
+    let mut store = AppendStoreMut::attach_or_create(&mut store)?;
+    let position = store.len();
+    store.push(&pet_id)?;
+    set_owner_pet_position(storage, pet_id, position);
+    Ok(())
+}
+
+/// The owner's last pet takes the place of the removed one, so the list stays without gaps
+pub fn remove_owner_pet<S: Storage>(
+    storage: &mut S,
+    owner: &CanonicalAddr,
+    pet_id: u64,
+) -> StdResult<()> {
+    let position = owner_pet_position(storage, pet_id)?;
+    let mut store = PrefixedStorage::multilevel(&[PREFIX_OWNER_PETS, owner.as_slice()], storage);
+    let mut store = AppendStoreMut::attach_or_create(&mut store)?;
+    let last = store.pop()?;
+    if last == pet_id {
+        return Ok(());
+    }
+    store.set_at(position, &last)?;
+    set_owner_pet_position(storage, last, position);
+    Ok(())
+}
+
+fn owner_pet_position<S: ReadonlyStorage>(storage: &S, pet_id: u64) -> StdResult<u32> {
+    let position = ReadonlyPrefixedStorage::new(PREFIX_OWNER_PET_POSITIONS, storage)
+        .get(&pet_id.to_be_bytes())
+        .ok_or_else(|| StdError::not_found("owner pet position"))?;
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&position);
+    Ok(u32::from_be_bytes(bytes))
+}
+
+fn set_owner_pet_position<S: Storage>(storage: &mut S, pet_id: u64, position: u32) {
+    PrefixedStorage::new(PREFIX_OWNER_PET_POSITIONS, storage)
+        .set(&pet_id.to_be_bytes(), &position.to_be_bytes());
+}
+
+/// Returns a page of pet ids owned by `owner` and the total count. Pets are in the order the
+/// owner got them, except where a pet that changed hands left its place to the last one
+pub fn get_owner_pet_ids<S: ReadonlyStorage>(
+    storage: &S,
+    owner: &CanonicalAddr,
+    page: u32,
+    page_size: u32,
+) -> StdResult<(Vec<u64>, u64)> {
+    let store =
+        ReadonlyPrefixedStorage::multilevel(&[PREFIX_OWNER_PETS, owner.as_slice()], storage);
+
+    // If the owner never had a pet, return an empty list
+    let store = AppendStore::<u64, _, _>::attach(&store);
+    let store = if let Some(result) = store {
+        result?
+    } else {
+        return Ok((vec![], 0));
+    };
+
+    let pet_ids: StdResult<Vec<u64>> = store
+        .iter()
+        .skip(page_start(page, page_size))
+        .take(page_size as _)
+        .collect();
+    pet_ids.map(|pet_ids| (pet_ids, store.len() as u64))
+}
+
+// Graveyard
+
+pub fn append_memorial<S: Storage>(storage: &mut S, memorial: &MemorialInfo) -> StdResult<()> {
+    let mut store = PrefixedStorage::new(PREFIX_GRAVEYARD, storage);
+    let mut store = AppendStoreMut::attach_or_create(&mut store)?;
+    store.push(memorial)
+}
+
+/// Returns a page of memorials, latest death first, and the total count
+pub fn get_memorials<S: ReadonlyStorage>(
+    storage: &S,
+    page: u32,
+    page_size: u32,
+) -> StdResult<(Vec<MemorialInfo>, u64)> {
+    let store = ReadonlyPrefixedStorage::new(PREFIX_GRAVEYARD, storage);
+
+    // If no pet died yet, return an empty list
+    let store = AppendStore::<MemorialInfo, _, _>::attach(&store);
+    let store = if let Some(result) = store {
+        result?
+    } else {
+        return Ok((vec![], 0));
+    };
+
+    let memorials: StdResult<Vec<MemorialInfo>> = store
+        .iter()
+        .rev()
+        .skip(page_start(page, page_size))
+        .take(page_size as _)
+        .collect();
+    memorials.map(|memorials| (memorials, store.len() as u64))
+}
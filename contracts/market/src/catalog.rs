@@ -0,0 +1,106 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{HumanAddr, StdError, StdResult, Storage, Uint128};
+use cosmwasm_storage::{singleton, singleton_read, ReadonlySingleton, Singleton};
+
+pub static CATALOG_KEY: &[u8] = b"catalog";
+pub const MAX_CATALOG_ITEMS: usize = 100;
+const MAX_SKU_LENGTH: usize = 30;
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemPrice {
+    Uscrt(Uint128),
+    /// Paid through FOOD's `Send`, the FOOD is burned
+    Food(Uint128),
+}
+
+impl ItemPrice {
+    pub fn amount(self) -> Uint128 {
+        match self {
+            ItemPrice::Uscrt(amount) | ItemPrice::Food(amount) => amount,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Delivery {
+    /// The market is a minter of the token
+    Mint,
+    /// The market holds the stock and transfers it
+    Transfer,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemToken {
+    /// Fungible items like medicine, `amount` of the token per item
+    Snip20 {
+        address: HumanAddr,
+        code_hash: String,
+        amount: Uint128,
+        delivery: Delivery,
+    },
+    /// Every item is a new NFT the market mints, like a cosmetic
+    Snip721 {
+        address: HumanAddr,
+        code_hash: String,
+    },
+}
+
+impl ItemToken {
+    pub fn address(&self) -> &HumanAddr {
+        match self {
+            ItemToken::Snip20 { address, .. } | ItemToken::Snip721 { address, .. } => address,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct CatalogItem {
+    pub sku: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub price: ItemPrice,
+    pub token: ItemToken,
+    /// Items left, `None` for no limit
+    pub stock: Option<u32>,
+}
+
+impl CatalogItem {
+    pub fn validate(&self, food: &HumanAddr) -> StdResult<()> {
+        if self.sku.is_empty() || self.sku.len() > MAX_SKU_LENGTH {
+            return Err(StdError::generic_err(format!(
+                "SKU has to be 1-{} bytes long",
+                MAX_SKU_LENGTH
+            )));
+        }
+        if self.name.is_empty() {
+            return Err(StdError::generic_err("Items need a name"));
+        }
+        if self.price.amount().u128() == 0 {
+            return Err(StdError::generic_err("Items need a price above 0"));
+        }
+        if let ItemToken::Snip20 { amount, .. } = self.token {
+            if amount.u128() == 0 {
+                return Err(StdError::generic_err(
+                    "Items need an amount of the token above 0",
+                ));
+            }
+        }
+        if self.token.address() == food {
+            return Err(StdError::generic_err("FOOD is sold with BuyFood"));
+        }
+        Ok(())
+    }
+}
+
+pub fn catalog<S: Storage>(storage: &mut S) -> Singleton<S, Vec<CatalogItem>> {
+    singleton(storage, CATALOG_KEY)
+}
+
+pub fn catalog_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Vec<CatalogItem>> {
+    singleton_read(storage, CATALOG_KEY)
+}
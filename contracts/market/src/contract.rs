@@ -1,15 +1,43 @@
-use crate::msg::{HandleMsg, InitMsg, QueryMsg};
-use crate::state::{config, config_read, State, TokenInfo};
+use crate::catalog::{
+    catalog, catalog_read, CatalogItem, Delivery, ItemPrice, ItemToken, MAX_CATALOG_ITEMS,
+};
+use crate::curve::{BondingCurve, Pricing};
+use crate::msg::{
+    ContractStatusResponse, FoodHandleMsg, FoodQueryMsg, HandleAnswer, HandleMsg, HealthProblem,
+    InitMsg, MintAction, MintersResponse, NftHandleMsg, NftMetadata, PaymentOption, PaymentToken,
+    QueryAnswer, QueryMsg, QueryWithPermit, ReceiveMsg, ResponseStatus::Success, TokenBalance,
+    TokenConfigResponse,
+};
+use crate::promo::{
+    hash_code, load_promo, promo_hash, promo_uses, remove_promo, save_promo, set_promo_uses,
+    PromoBonus, PromoCode,
+};
+use crate::receipts::{append_receipt, get_receipts, Receipt};
+use crate::referrals::{bind_referrer, load_referral_totals, read_referrer, save_referral_totals};
+use crate::sales::{sale_purchases, set_sale_purchases, SaleWindow, MAX_SALES};
+use crate::state::{
+    config, config_read, curve_balance, read_viewing_key, set_curve_balance, token_treasury,
+    token_treasury_read, treasury, treasury_read, write_viewing_key, State, TokenInfo,
+    PREFIX_REVOKED_PERMITS,
+};
 use cosmwasm_std::{
-    Api, Binary, Coin, Env, Extern, HandleResponse, HumanAddr, InitResponse, Querier, StdResult,
-    Storage, Uint128, StdError,
+    from_binary, log, to_binary, Api, BankMsg, Binary, CanonicalAddr, Coin, CosmosMsg, Env, Extern,
+    HandleResponse, HumanAddr, InitResponse, Querier, StdError, StdResult, Storage, Uint128,
 };
+use secret_toolkit::permit::{validate, Permission, Permit, RevokedPermits};
 use secret_toolkit::snip20;
+use secret_toolkit::utils::{HandleCallback, Query};
+use shared::paging::page_start;
+use shared::viewing_key::{ViewingKey, VIEWING_KEY_SIZE};
 
 const TOKEN_DENOM: &str = "uscrt";
 /// Ammount of food tokens you can get for 1 SCRT
 const DEFAULT_EXCHANGE_RATE: u64 = 100;
 const BLOCK_SIZE: usize = 256;
+const MAX_GIFT_RECIPIENTS: usize = 50;
+const MAX_PROMO_NAME_LENGTH: usize = 30;
+const MAX_ITEM_QUANTITY: u32 = 50;
+const DEFAULT_REFERRAL_PERCENT: u64 = 5;
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
@@ -18,14 +46,30 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<InitResponse> {
     let state = State {
         owner: deps.api.canonical_address(&env.message.sender)?,
+        contract_address: env.contract.address.clone(),
+        prng_seed: msg.prng_seed.to_vec(),
         token: TokenInfo {
             address: HumanAddr::from(msg.token_address),
             code_hash: msg.token_code_hash,
         },
-        exchange_rate: msg.exchange_rate.unwrap_or(DEFAULT_EXCHANGE_RATE),
+        payment_options: vec![PaymentOption {
+            denom: TOKEN_DENOM.to_string(),
+            rate: msg.exchange_rate.unwrap_or(DEFAULT_EXCHANGE_RATE),
+            min_purchase: None,
+        }],
+        payment_tokens: vec![],
+        pricing: Pricing::Fixed,
+        curve_supply: Uint128(0),
+        sales: vec![],
+        referral_percent: DEFAULT_REFERRAL_PERCENT,
+        rewarders: vec![],
     };
+    validate_payment_options(&state.payment_options)?;
     // market contract should be added as a food token minter
     config(&mut deps.storage).save(&state)?;
+    treasury(&mut deps.storage).save(&vec![])?;
+    token_treasury(&mut deps.storage).save(&vec![])?;
+    catalog(&mut deps.storage).save(&vec![])?;
     println!("Contract was initialized by {}", env.message.sender);
     Ok(InitResponse::default())
 }
@@ -36,71 +80,2816 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     msg: HandleMsg,
 ) -> StdResult<HandleResponse> {
     match msg {
-        HandleMsg::BuyFood {} => try_buy_food(deps, &env),
+        HandleMsg::BuyFood {
+            recipient,
+            memo,
+            promo_code,
+            referrer,
+        } => {
+            let recipient = recipient.unwrap_or_else(|| env.message.sender.clone());
+            try_buy_food(deps, &env, vec![recipient], memo, promo_code, referrer)
+        }
+        HandleMsg::GiftFood {
+            recipients,
+            memo,
+            promo_code,
+        } => try_buy_food(deps, &env, recipients, memo, promo_code, None),
+        HandleMsg::SetReferralPercent { percent } => try_set_referral_percent(deps, &env, percent),
+        HandleMsg::SetPaymentOptions { options } => try_set_payment_options(deps, &env, options),
+        HandleMsg::Withdraw {
+            denom,
+            amount,
+            recipient,
+        } => try_withdraw(deps, &env, denom, amount, recipient),
+        HandleMsg::SetPaymentTokens { tokens } => try_set_payment_tokens(deps, &env, tokens),
+        HandleMsg::SetPricing { pricing } => try_set_pricing(deps, &env, pricing),
+        HandleMsg::AddSale { sale } => try_add_sale(deps, &env, sale),
+        HandleMsg::AddItem { item } => try_add_item(deps, &env, item),
+        HandleMsg::UpdateItem { item } => try_update_item(deps, &env, item),
+        HandleMsg::SetItemStock { sku, stock } => try_set_item_stock(deps, &env, sku, stock),
+        HandleMsg::RemoveItem { sku } => try_remove_item(deps, &env, sku),
+        HandleMsg::BuyItem { sku, quantity } => {
+            let paid = match env.message.sent_funds.as_slice() {
+                [coin] if coin.denom == TOKEN_DENOM => coin.amount,
+                _ => Uint128(0),
+            };
+            let buyer = env.message.sender.clone();
+            try_buy_item(deps, &env, buyer, sku, quantity, ItemPrice::Uscrt(paid))
+        }
+        HandleMsg::CancelSale { name } => try_cancel_sale(deps, &env, name),
+        HandleMsg::WithdrawToken {
+            address,
+            amount,
+            recipient,
+        } => try_withdraw_token(deps, &env, address, amount, recipient),
+        HandleMsg::Receive {
+            from, amount, msg, ..
+        } => try_receive(deps, &env, from, amount, msg),
+        HandleMsg::SetRewarders { rewarders } => try_set_rewarders(deps, &env, rewarders),
+        HandleMsg::MintReward {
+            recipient,
+            amount,
+            memo,
+        } => try_mint_reward(deps, &env, recipient, amount, memo),
+        HandleMsg::AddPromoCode {
+            code,
+            name,
+            bonus,
+            max_uses,
+            per_address_limit,
+            expires_at,
+        } => {
+            let promo = PromoCode {
+                name,
+                bonus,
+                max_uses,
+                uses: 0,
+                per_address_limit,
+                expires_at,
+            };
+            try_add_promo_code(deps, &env, code, promo)
+        }
+        HandleMsg::RemovePromoCode { name } => try_remove_promo_code(deps, &env, name),
+        HandleMsg::CreateViewingKey { entropy, .. } => try_create_key(deps, env, entropy),
+        HandleMsg::SetViewingKey { key, .. } => try_set_key(deps, env, key),
+        HandleMsg::RevokePermit { permit_name, .. } => try_revoke_permit(deps, env, permit_name),
     }
 }
 
 pub fn try_buy_food<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: &Env,
+    recipients: Vec<HumanAddr>,
+    memo: Option<String>,
+    promo_code: Option<String>,
+    referrer: Option<HumanAddr>,
+) -> StdResult<HandleResponse> {
+    let payment = match env.message.sent_funds.as_slice() {
+        [coin] if coin.amount.u128() > 0 => coin,
+        _ => {
+            return Err(StdError::generic_err(
+                "Send exactly one coin of an accepted denom to buy FOOD",
+            ))
+        }
+    };
+    let mut state = config_read(&deps.storage).load()?;
+    let sale = match &state.pricing {
+        Pricing::Fixed => {
+            let option = state
+                .payment_options
+                .iter()
+                .find(|option| option.denom == payment.denom)
+                .ok_or_else(|| {
+                    StdError::generic_err(format!("{} is not accepted as payment", payment.denom))
+                })?;
+            Sale {
+                buyer: env.message.sender.clone(),
+                paid: payment.amount,
+                asset: payment.denom.clone(),
+                food: fixed_price_food(payment.amount, option.rate)?,
+                rate: Some(option.rate),
+                min_purchase: option.min_purchase,
+                promo_code,
+                referrer,
+            }
+        }
+        Pricing::BondingCurve(curve) => {
+            if payment.denom != curve.denom {
+                return Err(priced_on_curve(curve));
+            }
+            let (food, cost) = curve.quote_buy(state.curve_supply.u128(), payment.amount.u128());
+            Sale {
+                buyer: env.message.sender.clone(),
+                paid: Uint128(cost),
+                asset: payment.denom.clone(),
+                food: Uint128(food),
+                rate: None,
+                min_purchase: None,
+                promo_code,
+                referrer,
+            }
+        }
+    };
+    let mut res = sell_food(deps, env, &state, sale.clone(), recipients.clone(), memo)?;
+    if let Pricing::BondingCurve(_) = state.pricing {
+        // bonuses are minted off the curve, so only the FOOD paid for moves the price, and
+        // only that FOOD can be sold back
+        state.curve_supply = Uint128(state.curve_supply.u128() + sale.food.u128());
+        config(&mut deps.storage).save(&state)?;
+        credit_curve_food(deps, &recipients, sale.food.u128())?;
+        let change = payment.amount.u128() - sale.paid.u128();
+        if change > 0 {
+            res.messages.push(CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: env.message.sender.clone(),
+                amount: vec![Coin::new(change, &payment.denom)],
+            }));
+        }
+    }
+    treasury(&mut deps.storage).update(|mut balances| {
+        deposit(&mut balances, &Coin::new(sale.paid.u128(), &payment.denom));
+        Ok(balances)
+    })?;
+    Ok(res)
+}
+
+/// Splits the FOOD bought on the curve between the recipients like the mint does, the first
+/// one gets what doesn't split evenly
+fn credit_curve_food<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    recipients: &[HumanAddr],
+    food: u128,
+) -> StdResult<()> {
+    let count = recipients.len() as u128;
+    for (index, recipient) in recipients.iter().enumerate() {
+        let share = if index == 0 {
+            food / count + food % count
+        } else {
+            food / count
+        };
+        let recipient = deps.api.canonical_address(recipient)?;
+        let balance = curve_balance(&deps.storage, &recipient);
+        set_curve_balance(&mut deps.storage, &recipient, balance + share);
+    }
+    Ok(())
+}
+
+fn fixed_price_food(paid: Uint128, rate: u64) -> StdResult<Uint128> {
+    paid.u128()
+        .checked_mul(rate as u128)
+        .map(Uint128)
+        .ok_or_else(|| StdError::generic_err("Purchase is too large"))
+}
+
+fn priced_on_curve(curve: &BondingCurve) -> StdError {
+    StdError::generic_err(format!(
+        "FOOD is priced on a bonding curve, pay in {}",
+        curve.denom
+    ))
+}
+
+/// `Send` of a payment token, so the token contract is the message sender. FOOD pays for
+/// catalog items, or is sold back down the bonding curve
+pub fn try_receive<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    from: HumanAddr,
+    amount: Uint128,
+    msg: Option<Binary>,
 ) -> StdResult<HandleResponse> {
-    let sender = &env.message.sender;
-    let sent_scrt_funds = env
-        .message
-        .sent_funds
+    let state = config_read(&deps.storage).load()?;
+    if amount.u128() == 0 {
+        return Err(StdError::generic_err("No tokens sent"));
+    }
+    let msg: Option<ReceiveMsg> = msg.map(|msg| from_binary(&msg)).transpose()?;
+    if env.message.sender == state.token.address {
+        return match msg {
+            Some(ReceiveMsg::BuyItem { sku, quantity }) => {
+                try_buy_item(deps, env, from, sku, quantity, ItemPrice::Food(amount))
+            }
+            _ => try_sell_food(deps, env, state, from, amount),
+        };
+    }
+    if let Pricing::BondingCurve(curve) = &state.pricing {
+        return Err(priced_on_curve(curve));
+    }
+    let token = state
+        .payment_tokens
         .iter()
-        .find(|coin: &&Coin| (&coin).denom == TOKEN_DENOM)
-        .unwrap()
-        .amount;
-    if sent_scrt_funds == Uint128::from(0 as u64) {
-        return Err(StdError::generic_err("No funds or unsupported tokens sent"));
+        .find(|token| token.address == env.message.sender)
+        .ok_or_else(|| {
+            StdError::generic_err(format!("{} is not accepted as payment", env.message.sender))
+        })?;
+    let msg = msg.unwrap_or(ReceiveMsg::BuyFood {
+        recipient: None,
+        memo: None,
+        promo_code: None,
+        referrer: None,
+    });
+    let (recipients, memo, promo_code, referrer) = match msg {
+        ReceiveMsg::BuyFood {
+            recipient,
+            memo,
+            promo_code,
+            referrer,
+        } => (
+            vec![recipient.unwrap_or_else(|| from.clone())],
+            memo,
+            promo_code,
+            referrer,
+        ),
+        ReceiveMsg::GiftFood {
+            recipients,
+            memo,
+            promo_code,
+        } => (recipients, memo, promo_code, None),
+        ReceiveMsg::BuyItem { .. } => {
+            return Err(StdError::generic_err("Items are paid in uscrt or FOOD"))
+        }
+    };
+    let sale = Sale {
+        buyer: from,
+        paid: amount,
+        asset: token.address.to_string(),
+        food: fixed_price_food(amount, token.rate)?,
+        rate: Some(token.rate),
+        min_purchase: token.min_purchase,
+        promo_code,
+        referrer,
+    };
+    let res = sell_food(deps, env, &state, sale, recipients, memo)?;
+    token_treasury(&mut deps.storage).update(|mut balances| {
+        match balances
+            .iter_mut()
+            .find(|balance| balance.address == token.address)
+        {
+            Some(balance) => {
+                balance.amount = Uint128(balance.amount.u128().saturating_add(amount.u128()))
+            }
+            None => balances.push(TokenBalance {
+                address: token.address.clone(),
+                code_hash: token.code_hash.clone(),
+                amount,
+            }),
+        }
+        Ok(balances)
+    })?;
+    Ok(res)
+}
+
+fn try_sell_food<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    mut state: State,
+    from: HumanAddr,
+    amount: Uint128,
+) -> StdResult<HandleResponse> {
+    let curve = match &state.pricing {
+        Pricing::BondingCurve(curve) if curve.sell_back => curve.clone(),
+        _ => return Err(StdError::generic_err("FOOD can't be sold back")),
+    };
+    let seller = deps.api.canonical_address(&from)?;
+    let bought = curve_balance(&deps.storage, &seller);
+    if amount.u128() > bought {
+        return Err(StdError::generic_err(format!(
+            "Only {} FOOD can be sold back",
+            bought
+        )));
+    }
+    let refund = curve.sell_refund(state.curve_supply.u128(), amount.u128())?;
+    if refund == 0 {
+        return Err(StdError::generic_err(
+            "Too little FOOD to get anything back",
+        ));
+    }
+    state.curve_supply = Uint128(state.curve_supply.u128() - amount.u128());
+    config(&mut deps.storage).save(&state)?;
+    set_curve_balance(&mut deps.storage, &seller, bought - amount.u128());
+    treasury(&mut deps.storage).update(|mut balances| {
+        let balance = balances
+            .iter_mut()
+            .find(|balance| balance.denom == curve.denom)
+            .filter(|balance| balance.amount.u128() >= refund)
+            .ok_or_else(|| {
+                StdError::generic_err(format!("Not enough {} to pay the refund", curve.denom))
+            })?;
+        balance.amount = Uint128(balance.amount.u128() - refund);
+        balances.retain(|balance| balance.amount.u128() > 0);
+        Ok(balances)
+    })?;
+    Ok(HandleResponse {
+        messages: vec![
+            snip20::burn_msg(
+                amount,
+                None,
+                BLOCK_SIZE,
+                state.token.code_hash.clone(),
+                state.token.address.clone(),
+            )?,
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: env.contract.address.clone(),
+                to_address: from,
+                amount: vec![Coin::new(refund, &curve.denom)],
+            }),
+        ],
+        log: vec![
+            log("food_sold", amount),
+            log("refund", format!("{}{}", refund, curve.denom)),
+        ],
+        data: None,
+    })
+}
+
+/// Payment for FOOD, in a native denom or a payment token
+#[derive(Clone)]
+struct Sale {
+    buyer: HumanAddr,
+    paid: Uint128,
+    /// Native denom or address of the payment token
+    asset: String,
+    /// FOOD the payment buys before any bonus
+    food: Uint128,
+    /// FOOD per unit of the asset, `None` on the curve
+    rate: Option<u64>,
+    min_purchase: Option<Uint128>,
+    promo_code: Option<String>,
+    referrer: Option<HumanAddr>,
+}
+
+/// Mints the FOOD of a sale split evenly between the recipients, the first one gets what
+/// doesn't split evenly. The buyer gets a receipt, the best sale on and the bonus of the promo
+/// code if there's one. The buyer's referrer is minted their share in the same batch
+fn sell_food<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    state: &State,
+    sale: Sale,
+    recipients: Vec<HumanAddr>,
+    memo: Option<String>,
+) -> StdResult<HandleResponse> {
+    // FOOD would refuse the mint anyway, deep inside its own contract
+    if let Ok(false) = is_minter(deps, state) {
+        return Err(StdError::generic_err(
+            "The market isn't a FOOD minter, the owner has to add it first",
+        ));
+    }
+    if recipients.is_empty() || recipients.len() > MAX_GIFT_RECIPIENTS {
+        return Err(StdError::generic_err(format!(
+            "FOOD can go to 1 to {} recipients",
+            MAX_GIFT_RECIPIENTS
+        )));
+    }
+    if let Some(min_purchase) = sale.min_purchase {
+        if sale.paid < min_purchase {
+            return Err(StdError::generic_err(format!(
+                "The minimum purchase is {}{}",
+                min_purchase, sale.asset
+            )));
+        }
+    }
+    let buyer = deps.api.canonical_address(&sale.buyer)?;
+    let mut food_amount = sale.food.u128();
+    let time = env.block.time;
+    let applied_sale = best_sale(&deps.storage, &state.sales, &buyer, food_amount, time);
+    if let Some((window, sale_food)) = &applied_sale {
+        food_amount = *sale_food;
+        let bought = sale_purchases(&deps.storage, window, &buyer);
+        set_sale_purchases(&mut deps.storage, window, &buyer, bought + sale_food);
+    }
+    let promo_name = match sale.promo_code {
+        Some(code) => {
+            let (name, bonus) =
+                redeem_promo_code(&mut deps.storage, &code, &buyer, env.block.time)?;
+            food_amount = food_amount
+                .checked_add(bonus.extra_food(food_amount))
+                .ok_or_else(|| StdError::generic_err("Purchase is too large"))?;
+            Some(name)
+        }
+        None => None,
+    };
+    let food_amount = Uint128(food_amount);
+    let count = recipients.len() as u128;
+    let share = food_amount.u128() / count;
+    if share == 0 {
+        return Err(StdError::generic_err(format!(
+            "Purchase is too small to split between {} recipients",
+            count
+        )));
+    }
+    let mut actions: Vec<MintAction> = recipients
+        .into_iter()
+        .map(|recipient| MintAction {
+            recipient,
+            amount: Uint128(share),
+            memo: memo.clone(),
+        })
+        .collect();
+    actions[0].amount = Uint128(share + food_amount.u128() % count);
+    let referral = referral_reward(deps, state, &buyer, sale.referrer, food_amount.u128())?;
+    let receipt = Receipt {
+        paid: sale.paid,
+        asset: sale.asset,
+        food_minted: food_amount,
+        rate: sale.rate,
+        time: env.block.time,
+        promo_code: promo_name,
+    };
+    append_receipt(&mut deps.storage, &buyer, &receipt)?;
+    let mut logs = vec![
+        log("paid", format!("{}{}", receipt.paid, receipt.asset)),
+        log("food_amount", food_amount),
+    ];
+    if let Some((window, _)) = applied_sale {
+        logs.push(log("sale", window.name));
+    }
+    if let Some(name) = receipt.promo_code {
+        logs.push(log("promo_code", name));
+    }
+    if let Some(reward) = referral {
+        logs.push(log("referral_reward", reward.amount));
+        actions.push(reward);
+    }
+    Ok(HandleResponse {
+        messages: vec![mint_food(state, actions)?],
+        log: logs,
+        data: None,
+    })
+}
+
+/// Binds the referrer the first time a buyer names one, then works out the reward of the
+/// buyer's referrer, if there's one
+fn referral_reward<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    state: &State,
+    buyer: &CanonicalAddr,
+    referrer: Option<HumanAddr>,
+    food: u128,
+) -> StdResult<Option<MintAction>> {
+    if let Some(referrer) = referrer {
+        let referrer = deps.api.canonical_address(&referrer)?;
+        match read_referrer(&deps.storage, buyer) {
+            Some(bound) if bound == referrer => {}
+            Some(_) => return Err(StdError::generic_err("A referrer can only be set once")),
+            None => {
+                if &referrer == buyer {
+                    return Err(StdError::generic_err("You can't refer yourself"));
+                }
+                let mut ancestor = read_referrer(&deps.storage, &referrer);
+                while let Some(next) = ancestor {
+                    if &next == buyer {
+                        return Err(StdError::generic_err("Referral loops aren't allowed"));
+                    }
+                    ancestor = read_referrer(&deps.storage, &next);
+                }
+                bind_referrer(&mut deps.storage, buyer, &referrer);
+                let mut totals = load_referral_totals(&deps.storage, &referrer)?;
+                totals.referrals += 1;
+                save_referral_totals(&mut deps.storage, &referrer, &totals)?;
+            }
+        }
+    }
+    let referrer = match read_referrer(&deps.storage, buyer) {
+        Some(referrer) => referrer,
+        None => return Ok(None),
+    };
+    let reward = food.saturating_mul(state.referral_percent as u128) / 100;
+    if reward == 0 {
+        return Ok(None);
+    }
+    let mut totals = load_referral_totals(&deps.storage, &referrer)?;
+    totals.purchases += 1;
+    totals.food_earned = Uint128(totals.food_earned.u128().saturating_add(reward));
+    save_referral_totals(&mut deps.storage, &referrer, &totals)?;
+    Ok(Some(MintAction {
+        recipient: deps.api.human_address(&referrer)?,
+        amount: Uint128(reward),
+        memo: Some("Referral reward".to_string()),
+    }))
+}
+
+/// The active sale with the highest multiplier, and the FOOD it makes of `food`. Sales
+/// the purchase would take the buyer over the cap of are skipped
+fn best_sale<S: Storage>(
+    storage: &S,
+    sales: &[SaleWindow],
+    buyer: &CanonicalAddr,
+    food: u128,
+    time: u64,
+) -> Option<(SaleWindow, u128)> {
+    sales
+        .iter()
+        .filter(|sale| sale.is_active(time))
+        .filter_map(|sale| {
+            let food = sale.apply(food)?;
+            let bought = sale_purchases(storage, sale, buyer);
+            match sale.per_address_cap {
+                Some(cap) if bought.saturating_add(food) > cap.u128() => None,
+                _ => Some((sale, food)),
+            }
+        })
+        .max_by_key(|(sale, _)| sale.rate_multiplier)
+        .map(|(sale, food)| (sale.clone(), food))
+}
+
+/// Checks the code and counts the use, returns the name of the code and its bonus
+fn redeem_promo_code<S: Storage>(
+    storage: &mut S,
+    code: &str,
+    buyer: &CanonicalAddr,
+    time: u64,
+) -> StdResult<(String, PromoBonus)> {
+    let hash = hash_code(code);
+    let mut promo = load_promo(storage, &hash)?
+        .ok_or_else(|| StdError::generic_err("Promo code is not valid"))?;
+    if time >= promo.expires_at {
+        return Err(StdError::generic_err("Promo code has expired"));
+    }
+    if promo.uses >= promo.max_uses {
+        return Err(StdError::generic_err("Promo code has been used up"));
+    }
+    let uses = promo_uses(storage, &hash, buyer);
+    if uses >= promo.per_address_limit {
+        return Err(StdError::generic_err(format!(
+            "Promo code can only be used {} times per address",
+            promo.per_address_limit
+        )));
+    }
+    promo.uses += 1;
+    save_promo(storage, &hash, &promo)?;
+    set_promo_uses(storage, &hash, buyer, uses + 1);
+    Ok((promo.name, promo.bonus))
+}
+
+/// Whether FOOD lists the market as a minter, an error when FOOD doesn't answer
+fn is_minter<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    state: &State,
+) -> StdResult<bool> {
+    let response: MintersResponse = FoodQueryMsg::Minters {}.query(
+        &deps.querier,
+        state.token.code_hash.clone(),
+        state.token.address.clone(),
+    )?;
+    Ok(response.minters.minters.contains(&state.contract_address))
+}
+
+/// A plain mint for a single recipient, so the FOOD history shows an ordinary mint
+fn mint_food(state: &State, mut actions: Vec<MintAction>) -> StdResult<CosmosMsg> {
+    let msg = if actions.len() == 1 {
+        let action = actions.remove(0);
+        FoodHandleMsg::Mint {
+            recipient: action.recipient,
+            amount: action.amount,
+            memo: action.memo,
+            padding: None,
+        }
+    } else {
+        FoodHandleMsg::BatchMint {
+            actions,
+            padding: None,
+        }
+    };
+    msg.to_cosmos_msg(
+        state.token.code_hash.clone(),
+        state.token.address.clone(),
+        None,
+    )
+}
+
+fn deposit(balances: &mut Vec<Coin>, payment: &Coin) {
+    match balances
+        .iter_mut()
+        .find(|balance| balance.denom == payment.denom)
+    {
+        Some(balance) => {
+            balance.amount = Uint128(balance.amount.u128().saturating_add(payment.amount.u128()))
+        }
+        None => balances.push(payment.clone()),
+    }
+}
+
+fn validate_payment_options(options: &[PaymentOption]) -> StdResult<()> {
+    for (i, option) in options.iter().enumerate() {
+        if option.denom.is_empty() || option.rate == 0 {
+            return Err(StdError::generic_err(
+                "Payment options need a denom and a rate above 0",
+            ));
+        }
+        if options[..i].iter().any(|other| other.denom == option.denom) {
+            return Err(StdError::generic_err(format!(
+                "{} is listed more than once",
+                option.denom
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub fn try_set_payment_options<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    options: Vec<PaymentOption>,
+) -> StdResult<HandleResponse> {
+    validate_payment_options(&options)?;
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    config(&mut deps.storage).update(|mut state| {
+        if state.owner != sender {
+            return Err(StdError::unauthorized());
+        }
+        state.payment_options = options;
+        Ok(state)
+    })?;
+    Ok(HandleResponse::default())
+}
+
+/// Balances are kept per denom, so coins of a denom that's no longer accepted can still be withdrawn
+pub fn try_withdraw<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    denom: String,
+    amount: Option<Uint128>,
+    recipient: Option<HumanAddr>,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    let state = config_read(&deps.storage).load()?;
+    if state.owner != sender {
+        return Err(StdError::unauthorized());
+    }
+    // whatever it takes to buy back all the FOOD on the curve stays in the treasury
+    let reserve = match &state.pricing {
+        Pricing::BondingCurve(curve) if curve.sell_back && curve.denom == denom => {
+            let supply = state.curve_supply.u128();
+            curve.sell_refund(supply, supply)?
+        }
+        _ => 0,
+    };
+    let mut balances = treasury_read(&deps.storage).load()?;
+    let balance = balances
+        .iter_mut()
+        .find(|balance| balance.denom == denom)
+        .ok_or_else(|| StdError::generic_err(format!("There's no {} in the treasury", denom)))?;
+    let available = Uint128(balance.amount.u128().saturating_sub(reserve));
+    let amount = amount.unwrap_or(available);
+    if amount.u128() == 0 || amount > available {
+        return Err(StdError::generic_err(format!(
+            "Can withdraw up to {}{}",
+            available, denom
+        )));
+    }
+    balance.amount = Uint128(balance.amount.u128() - amount.u128());
+    balances.retain(|balance| balance.amount.u128() > 0);
+    treasury(&mut deps.storage).save(&balances)?;
+
+    let recipient = recipient.unwrap_or_else(|| env.message.sender.clone());
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: env.contract.address.clone(),
+            to_address: recipient.clone(),
+            amount: vec![Coin { denom, amount }],
+        })],
+        log: vec![log("withdrawn", amount), log("recipient", recipient)],
+        data: None,
+    })
+}
+
+pub fn try_set_payment_tokens<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    tokens: Vec<PaymentToken>,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    let mut state = config_read(&deps.storage).load()?;
+    if state.owner != sender {
+        return Err(StdError::unauthorized());
+    }
+    for (i, token) in tokens.iter().enumerate() {
+        if token.rate == 0 {
+            return Err(StdError::generic_err("Payment tokens need a rate above 0"));
+        }
+        // minted FOOD would come straight back as payment
+        if token.address == state.token.address {
+            return Err(StdError::generic_err("FOOD can't pay for FOOD"));
+        }
+        if tokens[..i]
+            .iter()
+            .any(|other| other.address == token.address)
+        {
+            return Err(StdError::generic_err(format!(
+                "{} is listed more than once",
+                token.address
+            )));
+        }
+    }
+    let messages = tokens
+        .iter()
+        .map(|token| {
+            snip20::register_receive_msg(
+                env.contract_code_hash.clone(),
+                None,
+                BLOCK_SIZE,
+                token.code_hash.clone(),
+                token.address.clone(),
+            )
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    state.payment_tokens = tokens;
+    config(&mut deps.storage).save(&state)?;
+    Ok(HandleResponse {
+        messages,
+        log: vec![],
+        data: None,
+    })
+}
+
+/// Switching pricing keeps the supply on the curve. With sell-back the market registers
+/// with FOOD, so FOOD sent to it can be sold
+pub fn try_set_pricing<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    pricing: Pricing,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    let mut state = config_read(&deps.storage).load()?;
+    if state.owner != sender {
+        return Err(StdError::unauthorized());
+    }
+    let mut messages = vec![];
+    if let Pricing::BondingCurve(curve) = &pricing {
+        curve.validate()?;
+        if curve.sell_back {
+            messages.push(snip20::register_receive_msg(
+                env.contract_code_hash.clone(),
+                None,
+                BLOCK_SIZE,
+                state.token.code_hash.clone(),
+                state.token.address.clone(),
+            )?);
+        }
+    }
+    state.pricing = pricing;
+    config(&mut deps.storage).save(&state)?;
+    Ok(HandleResponse {
+        messages,
+        log: vec![],
+        data: None,
+    })
+}
+
+pub fn try_add_sale<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    sale: SaleWindow,
+) -> StdResult<HandleResponse> {
+    let time = env.block.time;
+    sale.validate(time)?;
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    let mut state = config_read(&deps.storage).load()?;
+    if state.owner != sender {
+        return Err(StdError::unauthorized());
+    }
+    state.sales.retain(|sale| sale.end > time);
+    if state.sales.iter().any(|other| other.name == sale.name) {
+        return Err(StdError::generic_err(
+            "There's already a sale with that name",
+        ));
+    }
+    if state.sales.len() >= MAX_SALES {
+        return Err(StdError::generic_err(format!(
+            "Only {} sales can be scheduled at once",
+            MAX_SALES
+        )));
+    }
+    let name = sale.name.clone();
+    state.sales.push(sale);
+    config(&mut deps.storage).save(&state)?;
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("sale", name)],
+        data: None,
+    })
+}
+
+pub fn try_cancel_sale<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    name: String,
+) -> StdResult<HandleResponse> {
+    let time = env.block.time;
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    let mut state = config_read(&deps.storage).load()?;
+    if state.owner != sender {
+        return Err(StdError::unauthorized());
+    }
+    let index = state
+        .sales
+        .iter()
+        .position(|sale| sale.name == name && sale.end > time)
+        .ok_or_else(|| StdError::generic_err("No such sale"))?;
+    state.sales.remove(index);
+    config(&mut deps.storage).save(&state)?;
+    Ok(HandleResponse::default())
+}
+
+/// FOOD prices need the market registered with FOOD, so it's told about FOOD sent to it
+fn register_for_item<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: &Env,
+    item: &CatalogItem,
+) -> StdResult<Vec<CosmosMsg>> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    let state = config_read(&deps.storage).load()?;
+    if state.owner != sender {
+        return Err(StdError::unauthorized());
+    }
+    item.validate(&state.token.address)?;
+    let mut messages = vec![];
+    if let ItemPrice::Food(_) = item.price {
+        messages.push(snip20::register_receive_msg(
+            env.contract_code_hash.clone(),
+            None,
+            BLOCK_SIZE,
+            state.token.code_hash.clone(),
+            state.token.address.clone(),
+        )?);
+    }
+    Ok(messages)
+}
+
+pub fn try_add_item<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    item: CatalogItem,
+) -> StdResult<HandleResponse> {
+    let messages = register_for_item(deps, env, &item)?;
+    let mut items = catalog_read(&deps.storage).load()?;
+    if items.iter().any(|other| other.sku == item.sku) {
+        return Err(StdError::generic_err(format!(
+            "There's already an item with SKU {}",
+            item.sku
+        )));
+    }
+    if items.len() >= MAX_CATALOG_ITEMS {
+        return Err(StdError::generic_err(format!(
+            "The catalog can hold up to {} items",
+            MAX_CATALOG_ITEMS
+        )));
+    }
+    let sku = item.sku.clone();
+    items.push(item);
+    catalog(&mut deps.storage).save(&items)?;
+    Ok(HandleResponse {
+        messages,
+        log: vec![log("item", sku)],
+        data: None,
+    })
+}
+
+pub fn try_update_item<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    item: CatalogItem,
+) -> StdResult<HandleResponse> {
+    let messages = register_for_item(deps, env, &item)?;
+    let mut items = catalog_read(&deps.storage).load()?;
+    let listed = items
+        .iter_mut()
+        .find(|other| other.sku == item.sku)
+        .ok_or_else(|| no_such_item(&item.sku))?;
+    *listed = item;
+    catalog(&mut deps.storage).save(&items)?;
+    Ok(HandleResponse {
+        messages,
+        log: vec![],
+        data: None,
+    })
+}
+
+pub fn try_set_item_stock<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    sku: String,
+    stock: Option<u32>,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    if config_read(&deps.storage).load()?.owner != sender {
+        return Err(StdError::unauthorized());
+    }
+    catalog(&mut deps.storage).update(|mut items| {
+        let item = items
+            .iter_mut()
+            .find(|item| item.sku == sku)
+            .ok_or_else(|| no_such_item(&sku))?;
+        item.stock = stock;
+        Ok(items)
+    })?;
+    Ok(HandleResponse::default())
+}
+
+pub fn try_remove_item<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    sku: String,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    if config_read(&deps.storage).load()?.owner != sender {
+        return Err(StdError::unauthorized());
+    }
+    catalog(&mut deps.storage).update(|mut items| {
+        let index = items
+            .iter()
+            .position(|item| item.sku == sku)
+            .ok_or_else(|| no_such_item(&sku))?;
+        items.remove(index);
+        Ok(items)
+    })?;
+    Ok(HandleResponse::default())
+}
+
+fn no_such_item(sku: &str) -> StdError {
+    StdError::generic_err(format!("No item with SKU {}", sku))
+}
+
+/// `paid` is what came with the message, it has to match the item's price exactly. uscrt
+/// goes to the treasury and FOOD is burned
+pub fn try_buy_item<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    buyer: HumanAddr,
+    sku: String,
+    quantity: Option<u32>,
+    paid: ItemPrice,
+) -> StdResult<HandleResponse> {
+    let quantity = quantity.unwrap_or(1);
+    if quantity == 0 || quantity > MAX_ITEM_QUANTITY {
+        return Err(StdError::generic_err(format!(
+            "Buy 1 to {} items at once",
+            MAX_ITEM_QUANTITY
+        )));
+    }
+    let state = config_read(&deps.storage).load()?;
+    let mut items = catalog_read(&deps.storage).load()?;
+    let item = items
+        .iter_mut()
+        .find(|item| item.sku == sku)
+        .ok_or_else(|| no_such_item(&sku))?;
+    let total = item
+        .price
+        .amount()
+        .u128()
+        .checked_mul(quantity as u128)
+        .ok_or_else(|| StdError::generic_err("Purchase is too large"))?;
+    match (item.price, paid) {
+        (ItemPrice::Uscrt(_), ItemPrice::Uscrt(amount))
+        | (ItemPrice::Food(_), ItemPrice::Food(amount))
+            if amount.u128() == total => {}
+        (ItemPrice::Uscrt(_), _) => {
+            return Err(StdError::generic_err(format!(
+                "Send exactly {}{} for {} {}",
+                total, TOKEN_DENOM, quantity, item.name
+            )))
+        }
+        (ItemPrice::Food(_), _) => {
+            return Err(StdError::generic_err(format!(
+                "Send exactly {} FOOD for {} {}",
+                total, quantity, item.name
+            )))
+        }
+    }
+    if let Some(stock) = item.stock {
+        if stock < quantity {
+            return Err(StdError::generic_err(format!(
+                "Only {} left in stock",
+                stock
+            )));
+        }
+        item.stock = Some(stock - quantity);
+    }
+    let mut messages = deliver_item(item, &buyer, quantity)?;
+    match paid {
+        ItemPrice::Uscrt(_) => {
+            treasury(&mut deps.storage).update(|mut balances| {
+                deposit(&mut balances, &Coin::new(total, TOKEN_DENOM));
+                Ok(balances)
+            })?;
+        }
+        ItemPrice::Food(amount) => messages.push(snip20::burn_msg(
+            amount,
+            None,
+            BLOCK_SIZE,
+            state.token.code_hash.clone(),
+            state.token.address.clone(),
+        )?),
+    }
+    catalog(&mut deps.storage).save(&items)?;
+    Ok(HandleResponse {
+        messages,
+        log: vec![log("item", sku), log("quantity", quantity)],
+        data: None,
+    })
+}
+
+fn deliver_item(
+    item: &CatalogItem,
+    recipient: &HumanAddr,
+    quantity: u32,
+) -> StdResult<Vec<CosmosMsg>> {
+    match &item.token {
+        ItemToken::Snip20 {
+            address,
+            code_hash,
+            amount,
+            delivery,
+        } => {
+            let amount = amount
+                .u128()
+                .checked_mul(quantity as u128)
+                .map(Uint128)
+                .ok_or_else(|| StdError::generic_err("Purchase is too large"))?;
+            let msg = match delivery {
+                Delivery::Mint => snip20::mint_msg(
+                    recipient.clone(),
+                    amount,
+                    None,
+                    BLOCK_SIZE,
+                    code_hash.clone(),
+                    address.clone(),
+                )?,
+                Delivery::Transfer => snip20::transfer_msg(
+                    recipient.clone(),
+                    amount,
+                    None,
+                    BLOCK_SIZE,
+                    code_hash.clone(),
+                    address.clone(),
+                )?,
+            };
+            Ok(vec![msg])
+        }
+        ItemToken::Snip721 { address, code_hash } => (0..quantity)
+            .map(|_| {
+                let metadata = NftMetadata {
+                    name: Some(item.name.clone()),
+                    description: item.description.clone(),
+                    image: None,
+                };
+                NftHandleMsg::MintNft {
+                    owner: recipient.clone(),
+                    public_metadata: Some(metadata),
+                    memo: None,
+                    padding: None,
+                }
+                .to_cosmos_msg(code_hash.clone(), address.clone(), None)
+            })
+            .collect(),
+    }
+}
+
+/// Balances keep the code hash, so tokens that are no longer accepted can still be withdrawn
+pub fn try_withdraw_token<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    address: HumanAddr,
+    amount: Option<Uint128>,
+    recipient: Option<HumanAddr>,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    if config_read(&deps.storage).load()?.owner != sender {
+        return Err(StdError::unauthorized());
+    }
+    let mut balances = token_treasury_read(&deps.storage).load()?;
+    let balance = balances
+        .iter_mut()
+        .find(|balance| balance.address == address)
+        .ok_or_else(|| StdError::generic_err(format!("There's no {} in the treasury", address)))?;
+    let amount = amount.unwrap_or(balance.amount);
+    if amount.u128() == 0 || amount > balance.amount {
+        return Err(StdError::generic_err(format!(
+            "Can withdraw up to {}{}",
+            balance.amount, address
+        )));
+    }
+    balance.amount = Uint128(balance.amount.u128() - amount.u128());
+    let recipient = recipient.unwrap_or_else(|| env.message.sender.clone());
+    let transfer = snip20::transfer_msg(
+        recipient.clone(),
+        amount,
+        None,
+        BLOCK_SIZE,
+        balance.code_hash.clone(),
+        address,
+    )?;
+    balances.retain(|balance| balance.amount.u128() > 0);
+    token_treasury(&mut deps.storage).save(&balances)?;
+    Ok(HandleResponse {
+        messages: vec![transfer],
+        log: vec![log("withdrawn", amount), log("recipient", recipient)],
+        data: None,
+    })
+}
+
+pub fn try_set_referral_percent<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    percent: u64,
+) -> StdResult<HandleResponse> {
+    if percent > 100 {
+        return Err(StdError::generic_err(
+            "Referral percentage can be up to 100",
+        ));
     }
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    config(&mut deps.storage).update(|mut state| {
+        if state.owner != sender {
+            return Err(StdError::unauthorized());
+        }
+        state.referral_percent = percent;
+        Ok(state)
+    })?;
+    Ok(HandleResponse::default())
+}
+
+pub fn try_set_rewarders<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    rewarders: Vec<HumanAddr>,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    let rewarders = rewarders
+        .iter()
+        .map(|rewarder| deps.api.canonical_address(rewarder))
+        .collect::<StdResult<Vec<_>>>()?;
+    config(&mut deps.storage).update(|mut state| {
+        if state.owner != sender {
+            return Err(StdError::unauthorized());
+        }
+        state.rewarders = rewarders;
+        Ok(state)
+    })?;
+    Ok(HandleResponse::default())
+}
+
+pub fn try_mint_reward<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    recipient: HumanAddr,
+    amount: Uint128,
+    memo: Option<String>,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
     let state = config_read(&deps.storage).load()?;
-    let food_amount = Uint128::from(sent_scrt_funds.u128() * state.exchange_rate as u128);
+    if !state.rewarders.contains(&sender) {
+        return Err(StdError::unauthorized());
+    }
     let mint_message = snip20::mint_msg(
-        sender.clone(),
-        food_amount,
+        recipient.clone(),
+        amount,
         None,
         BLOCK_SIZE,
         state.token.code_hash.clone(),
         state.token.address.clone(),
     )?;
+    let mut logs = vec![
+        log("reward_recipient", recipient),
+        log("reward_amount", amount),
+    ];
+    if let Some(memo) = memo {
+        logs.push(log("memo", memo));
+    }
     Ok(HandleResponse {
         messages: vec![mint_message],
-        log: vec![],
+        log: logs,
         data: None,
     })
 }
 
+/// The code comes in an encrypted transaction, only its hash is stored
+pub fn try_add_promo_code<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    code: String,
+    promo: PromoCode,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    if config_read(&deps.storage).load()?.owner != sender {
+        return Err(StdError::unauthorized());
+    }
+    if code.is_empty() {
+        return Err(StdError::generic_err("Promo code can't be empty"));
+    }
+    if promo.name.is_empty() || promo.name.len() > MAX_PROMO_NAME_LENGTH {
+        return Err(StdError::generic_err(format!(
+            "Promo code name has to be 1-{} bytes long",
+            MAX_PROMO_NAME_LENGTH
+        )));
+    }
+    let no_bonus = match promo.bonus {
+        PromoBonus::Percent(percent) => percent == 0,
+        PromoBonus::Fixed(amount) => amount.u128() == 0,
+    };
+    if no_bonus {
+        return Err(StdError::generic_err("Promo code needs a bonus above 0"));
+    }
+    if promo.max_uses == 0 || promo.per_address_limit == 0 {
+        return Err(StdError::generic_err(
+            "Promo code needs a usage cap and a per-address limit above 0",
+        ));
+    }
+    if promo.expires_at <= env.block.time {
+        return Err(StdError::generic_err("Promo code would already be expired"));
+    }
+    if promo_hash(&deps.storage, &promo.name).is_some() {
+        return Err(StdError::generic_err(format!(
+            "There's already a promo code named {}",
+            promo.name
+        )));
+    }
+    let hash = hash_code(&code);
+    if load_promo(&deps.storage, &hash)?.is_some() {
+        return Err(StdError::generic_err(
+            "That promo code is already registered",
+        ));
+    }
+    save_promo(&mut deps.storage, &hash, &promo)?;
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("promo_code", promo.name)],
+        data: None,
+    })
+}
+
+pub fn try_remove_promo_code<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    name: String,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    if config_read(&deps.storage).load()?.owner != sender {
+        return Err(StdError::unauthorized());
+    }
+    let hash = promo_hash(&deps.storage, &name)
+        .ok_or_else(|| StdError::generic_err(format!("No promo code named {}", name)))?;
+    remove_promo(&mut deps.storage, &name, &hash);
+    Ok(HandleResponse::default())
+}
+
+pub fn try_set_key<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    key: String,
+) -> StdResult<HandleResponse> {
+    let vk = ViewingKey(key);
+
+    let message_sender = deps.api.canonical_address(&env.message.sender)?;
+    write_viewing_key(&mut deps.storage, &message_sender, &vk);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::SetViewingKey { status: Success })?),
+    })
+}
+
+pub fn try_create_key<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    entropy: String,
+) -> StdResult<HandleResponse> {
+    let prng_seed = config_read(&deps.storage).load()?.prng_seed;
+
+    let key = ViewingKey::new(&env, &prng_seed, (&entropy).as_ref());
+
+    let message_sender = deps.api.canonical_address(&env.message.sender)?;
+    write_viewing_key(&mut deps.storage, &message_sender, &key);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::CreateViewingKey { key })?),
+    })
+}
+
+fn try_revoke_permit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    permit_name: String,
+) -> StdResult<HandleResponse> {
+    RevokedPermits::revoke_permit(
+        &mut deps.storage,
+        PREFIX_REVOKED_PERMITS,
+        &env.message.sender,
+        &permit_name,
+    );
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::RevokePermit { status: Success })?),
+    })
+}
+
 pub fn query<S: Storage, A: Api, Q: Querier>(
-    _deps: &Extern<S, A, Q>,
+    deps: &Extern<S, A, Q>,
     msg: QueryMsg,
 ) -> StdResult<Binary> {
     match msg {
-        // QueryMsg::GetFoodBalance {} => to_binary(&query_food_balance(deps)?),
+        QueryMsg::PaymentOptions {} => {
+            let state = config_read(&deps.storage).load()?;
+            to_binary(&QueryAnswer::PaymentOptions {
+                options: state.payment_options,
+                tokens: state.payment_tokens,
+            })
+        }
+        QueryMsg::Treasury {} => to_binary(&QueryAnswer::Treasury {
+            balances: treasury_read(&deps.storage).load()?,
+            tokens: token_treasury_read(&deps.storage).load()?,
+        }),
+        QueryMsg::PromoCode { name } => query_promo_code(deps, name),
+        QueryMsg::Pricing {} => {
+            let state = config_read(&deps.storage).load()?;
+            to_binary(&QueryAnswer::Pricing {
+                pricing: state.pricing,
+                curve_supply: state.curve_supply,
+            })
+        }
+        QueryMsg::Health {} => query_health(deps),
+        QueryMsg::Catalog { page, page_size } => {
+            let items = catalog_read(&deps.storage).load()?;
+            let total = items.len() as u64;
+            let page_size = page_size.min(MAX_CATALOG_ITEMS as u32);
+            let items = items
+                .into_iter()
+                .skip(page_start(page.unwrap_or(0), page_size))
+                .take(page_size as _)
+                .collect();
+            to_binary(&QueryAnswer::Catalog { items, total })
+        }
+        QueryMsg::ActiveSales { time } => {
+            let state = config_read(&deps.storage).load()?;
+            let sales = state
+                .sales
+                .into_iter()
+                .filter(|sale| sale.is_active(time))
+                .collect();
+            to_binary(&QueryAnswer::ActiveSales { sales })
+        }
+        QueryMsg::UpcomingSales { time } => {
+            let state = config_read(&deps.storage).load()?;
+            let mut sales: Vec<SaleWindow> = state
+                .sales
+                .into_iter()
+                .filter(|sale| sale.start > time)
+                .collect();
+            sales.sort_by_key(|sale| sale.start);
+            to_binary(&QueryAnswer::UpcomingSales { sales })
+        }
+        QueryMsg::BuyQuote { payment } => {
+            let state = config_read(&deps.storage).load()?;
+            let curve = bonding_curve(&state)?;
+            let (food, cost) = curve.quote_buy(state.curve_supply.u128(), payment.u128());
+            to_binary(&QueryAnswer::BuyQuote {
+                food: Uint128(food),
+                cost: Uint128(cost),
+            })
+        }
+        QueryMsg::SellQuote { amount } => {
+            let state = config_read(&deps.storage).load()?;
+            let curve = bonding_curve(&state)?;
+            if !curve.sell_back {
+                return Err(StdError::generic_err("FOOD can't be sold back"));
+            }
+            let refund = curve.sell_refund(state.curve_supply.u128(), amount.u128())?;
+            to_binary(&QueryAnswer::SellQuote {
+                refund: Uint128(refund),
+            })
+        }
+        QueryMsg::WithPermit { permit, query } => permit_queries(deps, permit, query),
+        QueryMsg::PurchaseHistory { .. } | QueryMsg::ReferralTotals { .. } => {
+            viewing_keys_queries(deps, &msg)
+        }
+    }
+}
+
+fn query_health<S: Storage, A: Api, Q: Querier>(deps: &Extern<S, A, Q>) -> StdResult<Binary> {
+    let state = config_read(&deps.storage).load()?;
+    let unreachable = |query: &str, error: StdError| HealthProblem::FoodUnreachable {
+        query: query.to_string(),
+        error: error.to_string(),
+    };
+    let mut problems = vec![];
+    match is_minter(deps, &state) {
+        Ok(true) => {}
+        Ok(false) => problems.push(HealthProblem::NotAMinter),
+        Err(err) => problems.push(unreachable("minters", err)),
+    }
+    let status: StdResult<ContractStatusResponse> = FoodQueryMsg::ContractStatus {}.query(
+        &deps.querier,
+        state.token.code_hash.clone(),
+        state.token.address.clone(),
+    );
+    match status {
+        Ok(response) if response.contract_status.status != "normal_run" => {
+            problems.push(HealthProblem::FoodStopped {
+                status: response.contract_status.status,
+            })
+        }
+        Ok(_) => {}
+        Err(err) => problems.push(unreachable("contract_status", err)),
+    }
+    let config: StdResult<TokenConfigResponse> = FoodQueryMsg::TokenConfig {}.query(
+        &deps.querier,
+        state.token.code_hash.clone(),
+        state.token.address.clone(),
+    );
+    match config {
+        Ok(response) if !response.token_config.mint_enabled => {
+            problems.push(HealthProblem::MintingDisabled)
+        }
+        Ok(_) => {}
+        Err(err) => problems.push(unreachable("token_config", err)),
+    }
+    to_binary(&QueryAnswer::Health {
+        healthy: problems.is_empty(),
+        problems,
+    })
+}
+
+fn bonding_curve(state: &State) -> StdResult<&BondingCurve> {
+    match &state.pricing {
+        Pricing::BondingCurve(curve) => Ok(curve),
+        Pricing::Fixed => Err(StdError::generic_err(
+            "FOOD isn't priced on a bonding curve",
+        )),
     }
 }
 
+fn query_promo_code<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    name: String,
+) -> StdResult<Binary> {
+    let promo = promo_hash(&deps.storage, &name)
+        .map(|hash| load_promo(&deps.storage, &hash))
+        .transpose()?
+        .flatten()
+        .ok_or_else(|| StdError::generic_err(format!("No promo code named {}", name)))?;
+    to_binary(&QueryAnswer::PromoCode {
+        name: promo.name,
+        bonus: promo.bonus,
+        remaining_uses: promo.max_uses - promo.uses,
+        per_address_limit: promo.per_address_limit,
+        expires_at: promo.expires_at,
+    })
+}
+
+fn permit_queries<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    permit: Permit,
+    query: QueryWithPermit,
+) -> StdResult<Binary> {
+    let market_address = config_read(&deps.storage).load()?.contract_address;
+    let account = validate(deps, PREFIX_REVOKED_PERMITS, &permit, market_address)?;
+
+    match query {
+        QueryWithPermit::PurchaseHistory { page, page_size } => {
+            if !permit.check_permission(&Permission::History) {
+                return Err(StdError::generic_err(format!(
+                    "No permission to query history, got permissions {:?}",
+                    permit.params.permissions
+                )));
+            }
+            query_purchase_history(deps, &account, page.unwrap_or(0), page_size)
+        }
+    }
+}
+
+fn viewing_keys_queries<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: &QueryMsg,
+) -> StdResult<Binary> {
+    let (addresses, key) = msg.get_validation_params();
+
+    for address in addresses {
+        let canonical_addr = deps.api.canonical_address(address)?;
+
+        let expected_key = read_viewing_key(&deps.storage, &canonical_addr);
+
+        if expected_key.is_none() {
+            // Checking the key will take significant time. We don't want to exit immediately if it isn't set
+            // in a way which will allow to time the command and determine if a viewing key doesn't exist
+            key.check_viewing_key(&[0u8; VIEWING_KEY_SIZE]);
+        } else if key.check_viewing_key(expected_key.unwrap().as_slice()) {
+            return match msg {
+                QueryMsg::PurchaseHistory {
+                    address,
+                    page,
+                    page_size,
+                    ..
+                } => query_purchase_history(deps, address, page.unwrap_or(0), *page_size),
+                QueryMsg::ReferralTotals { address, .. } => query_referral_totals(deps, address),
+                _ => panic!("This query type does not require authentication"),
+            };
+        }
+    }
+
+    to_binary(&QueryAnswer::ViewingKeyError {
+        msg: "Wrong viewing key for this address or viewing key not set".to_string(),
+    })
+}
+
+fn query_purchase_history<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    buyer: &HumanAddr,
+    page: u32,
+    page_size: u32,
+) -> StdResult<Binary> {
+    let buyer = deps.api.canonical_address(buyer)?;
+    let (receipts, total) = get_receipts(&deps.storage, &buyer, page, page_size)?;
+    to_binary(&QueryAnswer::PurchaseHistory { receipts, total })
+}
+
+fn query_referral_totals<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    referrer: &HumanAddr,
+) -> StdResult<Binary> {
+    let referrer = deps.api.canonical_address(referrer)?;
+    let totals = load_referral_totals(&deps.storage, &referrer)?;
+    to_binary(&QueryAnswer::ReferralTotals {
+        referrals: totals.referrals,
+        purchases: totals.purchases,
+        food_earned: totals.food_earned,
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    // use super::*;
-    // use cosmwasm_std::testing::{mock_dependencies, mock_env};
-    // use cosmwasm_std::{coins, from_binary, StdError};
-
-    // #[test]
-    // fn proper_initialization() {
-    //     let mut deps = mock_dependencies(20, &[]);
-
-    //     let msg = InitMsg { count: 17 };
-    //     let env = mock_env("creator", &coins(1000, "earth"));
-
-    //     // we can just call .unwrap() to assert this was a success
-    //     let res = init(&mut deps, env, msg).unwrap();
-    //     assert_eq!(0, res.messages.len());
-
-    //     // it worked, let's query the state
-    //     let res = query(&deps, QueryMsg::GetCount {}).unwrap();
-    //     let value: CountResponse = from_binary(&res).unwrap();
-    //     assert_eq!(17, value.count);
-    // }
+    use super::*;
+    use crate::curve::CurveShape;
+    use crate::msg::{ContractStatus, Minters, TokenConfig};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{
+        coins, from_binary, from_slice, Empty, QuerierResult, QueryRequest, WasmQuery,
+    };
+
+    const ATOM: &str = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+    fn init_helper() -> Extern<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies(20, &[]);
+        let msg = InitMsg {
+            token_code_hash: "food_hash".to_string(),
+            token_address: "food".to_string(),
+            exchange_rate: None,
+            prng_seed: Binary::from("seed".as_bytes()),
+        };
+        init(&mut deps, mock_env("owner", &[]), msg).unwrap();
+        deps
+    }
+
+    fn buy(
+        deps: &mut Extern<MockStorage, MockApi, MockQuerier>,
+        funds: &[Coin],
+    ) -> StdResult<HandleResponse> {
+        let msg = HandleMsg::BuyFood {
+            recipient: None,
+            memo: None,
+            promo_code: None,
+            referrer: None,
+        };
+        handle(deps, mock_env("buyer", funds), msg)
+    }
+
+    fn food_minted(res: &HandleResponse) -> String {
+        res.log
+            .iter()
+            .find(|log| log.key == "food_amount")
+            .map(|log| log.value.clone())
+            .unwrap()
+    }
+
+    fn food_mint(recipient: &str, amount: u128, memo: Option<&str>) -> CosmosMsg {
+        FoodHandleMsg::Mint {
+            recipient: HumanAddr::from(recipient),
+            amount: Uint128(amount),
+            memo: memo.map(String::from),
+            padding: None,
+        }
+        .to_cosmos_msg("food_hash".to_string(), HumanAddr::from("food"), None)
+        .unwrap()
+    }
+
+    fn treasury_balances(deps: &Extern<MockStorage, MockApi, MockQuerier>) -> Vec<Coin> {
+        match from_binary(&query(deps, QueryMsg::Treasury {}).unwrap()).unwrap() {
+            QueryAnswer::Treasury { balances, .. } => balances,
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    fn extract_error_msg<T>(result: StdResult<T>) -> String {
+        match result {
+            Err(StdError::GenericErr { msg, .. }) => msg,
+            Err(err) => panic!("Unexpected error: {:?}", err),
+            Ok(_) => panic!("Expected an error"),
+        }
+    }
+
+    #[test]
+    fn buy_food_with_any_listed_denom() {
+        let mut deps = init_helper();
+        let res = buy(&mut deps, &coins(3, "uscrt")).unwrap();
+        assert_eq!(food_minted(&res), "300");
+        let res = buy(&mut deps, &coins(3, ATOM));
+        assert_eq!(
+            extract_error_msg(res),
+            format!("{} is not accepted as payment", ATOM)
+        );
+
+        let options = vec![
+            PaymentOption {
+                denom: "uscrt".to_string(),
+                rate: 100,
+                min_purchase: None,
+            },
+            PaymentOption {
+                denom: ATOM.to_string(),
+                rate: 250,
+                min_purchase: Some(Uint128(10)),
+            },
+        ];
+        let msg = HandleMsg::SetPaymentOptions {
+            options: options.clone(),
+        };
+        let res = handle(&mut deps, mock_env("buyer", &[]), msg.clone());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+        match from_binary(&query(&deps, QueryMsg::PaymentOptions {}).unwrap()).unwrap() {
+            QueryAnswer::PaymentOptions {
+                options: listed, ..
+            } => assert_eq!(listed, options),
+            _ => panic!("Unexpected query answer"),
+        }
+
+        let res = buy(&mut deps, &coins(9, ATOM));
+        assert_eq!(
+            extract_error_msg(res),
+            format!("The minimum purchase is 10{}", ATOM)
+        );
+        let res = buy(&mut deps, &[Coin::new(10, ATOM), Coin::new(1, "uscrt")]);
+        assert_eq!(
+            extract_error_msg(res),
+            "Send exactly one coin of an accepted denom to buy FOOD"
+        );
+        let res = buy(&mut deps, &coins(10, ATOM)).unwrap();
+        assert_eq!(food_minted(&res), "2500");
+        assert_eq!(
+            treasury_balances(&deps),
+            vec![Coin::new(3, "uscrt"), Coin::new(10, ATOM)]
+        );
+    }
+
+    #[test]
+    fn owner_withdraws_per_denom() {
+        let mut deps = init_helper();
+        buy(&mut deps, &coins(30, "uscrt")).unwrap();
+        let withdraw = |denom: &str, amount: Option<u128>| HandleMsg::Withdraw {
+            denom: denom.to_string(),
+            amount: amount.map(Uint128),
+            recipient: None,
+        };
+        let res = handle(&mut deps, mock_env("buyer", &[]), withdraw("uscrt", None));
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = handle(&mut deps, mock_env("owner", &[]), withdraw(ATOM, None));
+        assert_eq!(
+            extract_error_msg(res),
+            format!("There's no {} in the treasury", ATOM)
+        );
+        let res = handle(
+            &mut deps,
+            mock_env("owner", &[]),
+            withdraw("uscrt", Some(31)),
+        );
+        assert_eq!(extract_error_msg(res), "Can withdraw up to 30uscrt");
+
+        let res = handle(
+            &mut deps,
+            mock_env("owner", &[]),
+            withdraw("uscrt", Some(10)),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from("cosmos2contract"),
+                to_address: HumanAddr::from("owner"),
+                amount: coins(10, "uscrt"),
+            })]
+        );
+        assert_eq!(treasury_balances(&deps), coins(20, "uscrt"));
+        handle(&mut deps, mock_env("owner", &[]), withdraw("uscrt", None)).unwrap();
+        assert!(treasury_balances(&deps).is_empty());
+    }
+
+    fn receive_from(
+        deps: &mut Extern<MockStorage, MockApi, MockQuerier>,
+        token: &str,
+        amount: u128,
+        msg: Option<ReceiveMsg>,
+    ) -> StdResult<HandleResponse> {
+        let msg = HandleMsg::Receive {
+            sender: HumanAddr::from("buyer"),
+            from: HumanAddr::from("buyer"),
+            amount: Uint128(amount),
+            msg: msg.map(|msg| to_binary(&msg).unwrap()),
+        };
+        handle(deps, mock_env(token, &[]), msg)
+    }
+
+    #[test]
+    fn buy_food_with_snip20_tokens() {
+        let mut deps = init_helper();
+        let sscrt = PaymentToken {
+            address: HumanAddr::from("sscrt"),
+            code_hash: "sscrt_hash".to_string(),
+            rate: 100,
+            min_purchase: Some(Uint128(5)),
+        };
+        let food = PaymentToken {
+            address: HumanAddr::from("food"),
+            ..sscrt.clone()
+        };
+        let set_tokens = |tokens| HandleMsg::SetPaymentTokens { tokens };
+        let res = handle(&mut deps, mock_env("owner", &[]), set_tokens(vec![food]));
+        assert_eq!(extract_error_msg(res), "FOOD can't pay for FOOD");
+        let env = mock_env("owner", &[]);
+        let code_hash = env.contract_code_hash.clone();
+        let res = handle(&mut deps, env, set_tokens(vec![sscrt])).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![snip20::register_receive_msg(
+                code_hash,
+                None,
+                BLOCK_SIZE,
+                "sscrt_hash".to_string(),
+                HumanAddr::from("sscrt"),
+            )
+            .unwrap()]
+        );
+
+        let res = receive_from(&mut deps, "stranger_token", 10, None);
+        assert_eq!(
+            extract_error_msg(res),
+            "stranger_token is not accepted as payment"
+        );
+        let res = receive_from(&mut deps, "sscrt", 4, None);
+        assert_eq!(extract_error_msg(res), "The minimum purchase is 5sscrt");
+        let msg = ReceiveMsg::BuyFood {
+            recipient: None,
+            memo: None,
+            promo_code: None,
+            referrer: None,
+        };
+        let res = receive_from(&mut deps, "sscrt", 7, Some(msg)).unwrap();
+        assert_eq!(res.messages, vec![food_mint("buyer", 700, None)]);
+
+        // the treasury keeps tokens that are no longer accepted
+        handle(&mut deps, mock_env("owner", &[]), set_tokens(vec![])).unwrap();
+        let withdraw = HandleMsg::WithdrawToken {
+            address: HumanAddr::from("sscrt"),
+            amount: None,
+            recipient: Some(HumanAddr::from("vault")),
+        };
+        let res = handle(&mut deps, mock_env("buyer", &[]), withdraw.clone());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = handle(&mut deps, mock_env("owner", &[]), withdraw.clone()).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![snip20::transfer_msg(
+                HumanAddr::from("vault"),
+                Uint128(7),
+                None,
+                BLOCK_SIZE,
+                "sscrt_hash".to_string(),
+                HumanAddr::from("sscrt"),
+            )
+            .unwrap()]
+        );
+        let res = handle(&mut deps, mock_env("owner", &[]), withdraw);
+        assert_eq!(extract_error_msg(res), "There's no sscrt in the treasury");
+    }
+
+    #[test]
+    fn buy_food_as_a_gift() {
+        let mut deps = init_helper();
+        let msg = HandleMsg::BuyFood {
+            recipient: Some(HumanAddr::from("kid")),
+            memo: Some("Happy birthday".to_string()),
+        };
+        let res = handle(&mut deps, mock_env("parent", &coins(2, "uscrt")), msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![food_mint("kid", 200, Some("Happy birthday"))]
+        );
+
+        let gift = |recipients: &[&str]| HandleMsg::GiftFood {
+            recipients: recipients
+                .iter()
+                .map(|&member| HumanAddr::from(member))
+                .collect(),
+            memo: Some("Guild rations".to_string()),
+            promo_code: None,
+            referrer: None,
+        };
+        let res = handle(&mut deps, mock_env("guild", &coins(10, "uscrt")), gift(&[]));
+        assert_eq!(extract_error_msg(res), "FOOD can go to 1 to 50 recipients");
+        let res = handle(
+            &mut deps,
+            mock_env("guild", &coins(10, "uscrt")),
+            gift(&["a", "b", "c"]),
+        )
+        .unwrap();
+        let action = |recipient: &str, amount| MintAction {
+            recipient: HumanAddr::from(recipient),
+            amount: Uint128(amount),
+            memo: Some("Guild rations".to_string()),
+        };
+        let batch = FoodHandleMsg::BatchMint {
+            actions: vec![action("a", 334), action("b", 333), action("c", 333)],
+            padding: None,
+        };
+        assert_eq!(
+            res.messages,
+            vec![batch
+                .to_cosmos_msg("food_hash".to_string(), HumanAddr::from("food"), None)
+                .unwrap()]
+        );
+
+        // the same works with a token payment
+        let sscrt = PaymentToken {
+            address: HumanAddr::from("sscrt"),
+            code_hash: "sscrt_hash".to_string(),
+            rate: 1,
+            min_purchase: None,
+        };
+        let msg = HandleMsg::SetPaymentTokens {
+            tokens: vec![sscrt],
+        };
+        handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+        let msg = ReceiveMsg::GiftFood {
+            recipients: vec![HumanAddr::from("a"), HumanAddr::from("b")],
+            memo: None,
+            promo_code: None,
+        };
+        let res = receive_from(&mut deps, "sscrt", 1, Some(msg));
+        assert_eq!(
+            extract_error_msg(res),
+            "Purchase is too small to split between 2 recipients"
+        );
+    }
+
+    fn purchase_history(
+        deps: &Extern<MockStorage, MockApi, MockQuerier>,
+        address: &str,
+        key: &str,
+    ) -> QueryAnswer {
+        let msg = QueryMsg::PurchaseHistory {
+            address: HumanAddr::from(address),
+            key: key.to_string(),
+            page: None,
+            page_size: 10,
+        };
+        from_binary(&query(deps, msg).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn buyers_keep_private_receipts() {
+        let mut deps = init_helper();
+        let msg = HandleMsg::SetPaymentTokens {
+            tokens: vec![PaymentToken {
+                address: HumanAddr::from("sscrt"),
+                code_hash: "sscrt_hash".to_string(),
+                rate: 90,
+                min_purchase: None,
+            }],
+        };
+        handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+        let msg = HandleMsg::BuyFood {
+            recipient: Some(HumanAddr::from("kid")),
+            memo: None,
+            promo_code: None,
+            referrer: None,
+        };
+        let env = mock_env("buyer", &coins(2, "uscrt"));
+        let time = env.block.time;
+        handle(&mut deps, env, msg).unwrap();
+        receive_from(&mut deps, "sscrt", 5, None).unwrap();
+
+        let msg = HandleMsg::SetViewingKey {
+            key: "key".to_string(),
+            padding: None,
+        };
+        handle(&mut deps, mock_env("buyer", &[]), msg).unwrap();
+        match purchase_history(&deps, "buyer", "key") {
+            QueryAnswer::PurchaseHistory { receipts, total } => {
+                assert_eq!(total, 2);
+                assert_eq!(
+                    receipts,
+                    vec![
+                        Receipt {
+                            paid: Uint128(5),
+                            asset: "sscrt".to_string(),
+                            food_minted: Uint128(450),
+                            rate: Some(90),
+                            time,
+                            promo_code: None,
+                        },
+                        Receipt {
+                            paid: Uint128(2),
+                            asset: "uscrt".to_string(),
+                            food_minted: Uint128(200),
+                            rate: Some(100),
+                            time,
+                            promo_code: None,
+                        },
+                    ]
+                );
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+        // the gift shows up for whoever paid for it, and nobody else
+        assert!(matches!(
+            purchase_history(&deps, "buyer", "wrong_key"),
+            QueryAnswer::ViewingKeyError { .. }
+        ));
+        assert!(matches!(
+            purchase_history(&deps, "kid", "key"),
+            QueryAnswer::ViewingKeyError { .. }
+        ));
+        // pages too far out are empty instead of overflowing
+        let msg = QueryMsg::PurchaseHistory {
+            address: HumanAddr::from("buyer"),
+            key: "key".to_string(),
+            page: Some(u32::MAX),
+            page_size: u32::MAX,
+        };
+        match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+            QueryAnswer::PurchaseHistory { receipts, total } => {
+                assert!(receipts.is_empty());
+                assert_eq!(total, 2);
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    fn buy_with_promo(
+        deps: &mut Extern<MockStorage, MockApi, MockQuerier>,
+        buyer: &str,
+        code: &str,
+    ) -> StdResult<HandleResponse> {
+        let msg = HandleMsg::BuyFood {
+            recipient: None,
+            memo: None,
+            promo_code: Some(code.to_string()),
+            referrer: None,
+        };
+        handle(deps, mock_env(buyer, &coins(10, "uscrt")), msg)
+    }
+
+    fn promo_remaining_uses(deps: &Extern<MockStorage, MockApi, MockQuerier>, name: &str) -> u32 {
+        let msg = QueryMsg::PromoCode {
+            name: name.to_string(),
+        };
+        match from_binary(&query(deps, msg).unwrap()).unwrap() {
+            QueryAnswer::PromoCode { remaining_uses, .. } => remaining_uses,
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    #[test]
+    fn promo_codes_add_a_bonus() {
+        let mut deps = init_helper();
+        let time = mock_env("owner", &[]).block.time;
+        let add = |code: &str, name: &str, bonus| HandleMsg::AddPromoCode {
+            code: code.to_string(),
+            name: name.to_string(),
+            bonus,
+            max_uses: 3,
+            per_address_limit: 2,
+            expires_at: time + 100,
+        };
+        let msg = add("LAUNCH", "launch", PromoBonus::Percent(20));
+        let res = handle(&mut deps, mock_env("buyer", &[]), msg.clone());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+        let msg = add("LAUNCH", "again", PromoBonus::Fixed(Uint128(50)));
+        let res = handle(&mut deps, mock_env("owner", &[]), msg);
+        assert_eq!(
+            extract_error_msg(res),
+            "That promo code is already registered"
+        );
+        let msg = add("FIFTY", "fifty", PromoBonus::Fixed(Uint128(50)));
+        handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+
+        // 10uscrt buy 1000 FOOD, plus the bonus
+        let res = buy_with_promo(&mut deps, "alice", "LAUNCH").unwrap();
+        assert_eq!(food_minted(&res), "1200");
+        let res = buy_with_promo(&mut deps, "alice", "FIFTY").unwrap();
+        assert_eq!(food_minted(&res), "1050");
+        let res = buy_with_promo(&mut deps, "alice", "launch");
+        assert_eq!(extract_error_msg(res), "Promo code is not valid");
+
+        buy_with_promo(&mut deps, "alice", "LAUNCH").unwrap();
+        let res = buy_with_promo(&mut deps, "alice", "LAUNCH");
+        assert_eq!(
+            extract_error_msg(res),
+            "Promo code can only be used 2 times per address"
+        );
+        assert_eq!(promo_remaining_uses(&deps, "launch"), 1);
+        buy_with_promo(&mut deps, "bob", "LAUNCH").unwrap();
+        let res = buy_with_promo(&mut deps, "carol", "LAUNCH");
+        assert_eq!(extract_error_msg(res), "Promo code has been used up");
+        assert_eq!(promo_remaining_uses(&deps, "launch"), 0);
+
+        let mut env = mock_env("carol", &coins(10, "uscrt"));
+        env.block.time = time + 100;
+        let msg = HandleMsg::BuyFood {
+            recipient: None,
+            memo: None,
+            promo_code: Some("FIFTY".to_string()),
+            referrer: None,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert_eq!(extract_error_msg(res), "Promo code has expired");
+
+        // the answer names the code without giving it away
+        let msg = QueryMsg::PromoCode {
+            name: "fifty".to_string(),
+        };
+        let answer = String::from_utf8(query(&deps, msg).unwrap().to_vec()).unwrap();
+        assert!(!answer.contains("FIFTY"));
+
+        // a code added again doesn't remember who used it before
+        let msg = HandleMsg::RemovePromoCode {
+            name: "launch".to_string(),
+        };
+        handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+        let msg = add("LAUNCH", "launch", PromoBonus::Percent(20));
+        handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+        let res = buy_with_promo(&mut deps, "alice", "LAUNCH").unwrap();
+        assert_eq!(food_minted(&res), "1200");
+        assert_eq!(promo_remaining_uses(&deps, "launch"), 2);
+    }
+
+    fn set_curve(
+        deps: &mut Extern<MockStorage, MockApi, MockQuerier>,
+        shape: CurveShape,
+        sell_back: bool,
+    ) -> HandleResponse {
+        let msg = HandleMsg::SetPricing {
+            pricing: Pricing::BondingCurve(BondingCurve {
+                denom: "uscrt".to_string(),
+                shape,
+                sell_back,
+            }),
+        };
+        handle(deps, mock_env("owner", &[]), msg).unwrap()
+    }
+
+    fn buy_quote(deps: &Extern<MockStorage, MockApi, MockQuerier>, payment: u128) -> (u128, u128) {
+        let msg = QueryMsg::BuyQuote {
+            payment: Uint128(payment),
+        };
+        match from_binary(&query(deps, msg).unwrap()).unwrap() {
+            QueryAnswer::BuyQuote { food, cost } => (food.u128(), cost.u128()),
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    fn refund_to(recipient: &str, amount: u128) -> CosmosMsg {
+        CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from("cosmos2contract"),
+            to_address: HumanAddr::from(recipient),
+            amount: coins(amount, "uscrt"),
+        })
+    }
+
+    #[test]
+    fn bonding_curve_quotes_match_purchases() {
+        let mut deps = init_helper();
+        let linear = CurveShape::Linear {
+            base_price: Uint128(10_000),
+            slope: Uint128(10_000),
+        };
+        let env = mock_env("owner", &[]);
+        let code_hash = env.contract_code_hash.clone();
+        let res = set_curve(&mut deps, linear, true);
+        assert_eq!(
+            res.messages,
+            vec![snip20::register_receive_msg(
+                code_hash,
+                None,
+                BLOCK_SIZE,
+                "food_hash".to_string(),
+                HumanAddr::from("food"),
+            )
+            .unwrap()]
+        );
+
+        assert_eq!(buy_quote(&deps, 1000), (95445, 1000));
+        let res = buy(&mut deps, &coins(1000, "uscrt")).unwrap();
+        assert_eq!(res.messages, vec![food_mint("buyer", 95445, None)]);
+        // the same payment buys less further up the curve
+        assert_eq!(buy_quote(&deps, 1000), (87770, 1000));
+        let res = buy(&mut deps, &coins(1000, ATOM));
+        assert_eq!(
+            extract_error_msg(res),
+            "FOOD is priced on a bonding curve, pay in uscrt"
+        );
+
+        // 999uscrt are kept to buy all of it back
+        let msg = HandleMsg::Withdraw {
+            denom: "uscrt".to_string(),
+            amount: Some(Uint128(2)),
+            recipient: None,
+        };
+        let res = handle(&mut deps, mock_env("owner", &[]), msg);
+        assert_eq!(extract_error_msg(res), "Can withdraw up to 1uscrt");
+
+        let msg = QueryMsg::SellQuote {
+            amount: Uint128(40_000),
+        };
+        let quote: QueryAnswer = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(
+            quote,
+            QueryAnswer::SellQuote {
+                refund: Uint128(430)
+            }
+        );
+        let res = receive_from(&mut deps, "food", 40_000, None).unwrap();
+        let burn = snip20::burn_msg(
+            Uint128(40_000),
+            None,
+            BLOCK_SIZE,
+            "food_hash".to_string(),
+            HumanAddr::from("food"),
+        )
+        .unwrap();
+        assert_eq!(res.messages, vec![burn, refund_to("buyer", 430)]);
+        let res = receive_from(&mut deps, "food", 60_000, None);
+        assert_eq!(extract_error_msg(res), "Only 55445 FOOD can be sold back");
+
+        // at 2.5uscrt a unit, 11uscrt buy 4 units and 1uscrt comes back
+        let flat = CurveShape::Linear {
+            base_price: Uint128(2_500_000),
+            slope: Uint128(0),
+        };
+        set_curve(&mut deps, flat, false);
+        assert_eq!(buy_quote(&deps, 11), (4, 10));
+        let res = buy(&mut deps, &coins(11, "uscrt")).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![food_mint("buyer", 4, None), refund_to("buyer", 1)]
+        );
+        // less than a FOOD per uscrt doesn't round down to a rate of 0, curve receipts have none
+        let msg = HandleMsg::SetViewingKey {
+            key: "key".to_string(),
+            padding: None,
+        };
+        handle(&mut deps, mock_env("buyer", &[]), msg).unwrap();
+        match purchase_history(&deps, "buyer", "key") {
+            QueryAnswer::PurchaseHistory { receipts, .. } => {
+                let receipt = &receipts[0];
+                assert_eq!(
+                    (receipt.paid, receipt.food_minted, receipt.rate),
+                    (Uint128(10), Uint128(4), None)
+                );
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+        let res = receive_from(&mut deps, "food", 4, None);
+        assert_eq!(extract_error_msg(res), "FOOD can't be sold back");
+    }
+
+    #[test]
+    fn buying_then_selling_back_never_profits() {
+        let curves = [
+            BondingCurve {
+                denom: "uscrt".to_string(),
+                shape: CurveShape::Linear {
+                    base_price: Uint128(10_000),
+                    slope: Uint128(10_000),
+                },
+                sell_back: true,
+            },
+            BondingCurve {
+                denom: "uscrt".to_string(),
+                shape: CurveShape::Linear {
+                    base_price: Uint128(3_333_333),
+                    slope: Uint128(7),
+                },
+                sell_back: true,
+            },
+            BondingCurve {
+                denom: "uscrt".to_string(),
+                shape: CurveShape::Exponential {
+                    base_price: Uint128(10_000),
+                    doubling_supply: Uint128(50_000),
+                },
+                sell_back: true,
+            },
+            BondingCurve {
+                denom: "uscrt".to_string(),
+                shape: CurveShape::Exponential {
+                    base_price: Uint128(1),
+                    doubling_supply: Uint128(30_000),
+                },
+                sell_back: true,
+            },
+        ];
+        // plain LCG, so every run checks the same cases
+        let mut seed: u64 = 42;
+        let mut next = |bound: u64| {
+            seed = seed
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (seed >> 33) % bound
+        };
+        for curve in curves.iter() {
+            for _ in 0..500 {
+                let supply = next(1_000_000) as u128;
+                let payment = next(100_000) as u128 + 1;
+                let (food, cost) = curve.quote_buy(supply, payment);
+                assert!(cost <= payment);
+                assert_eq!(curve.buy_cost(supply, food).unwrap(), cost);
+                // the quote is the most the payment buys
+                assert!(curve
+                    .buy_cost(supply, food + 1)
+                    .map_or(true, |cost| cost > payment));
+                let refund = curve.sell_refund(supply + food, food).unwrap();
+                assert!(
+                    refund <= cost,
+                    "{:?} refunds {} for {}",
+                    curve,
+                    refund,
+                    cost
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn only_food_bought_on_the_curve_sells_back() {
+        let mut deps = init_helper();
+        let linear = CurveShape::Linear {
+            base_price: Uint128(10_000),
+            slope: Uint128(10_000),
+        };
+        set_curve(&mut deps, linear, true);
+        let msg = HandleMsg::AddPromoCode {
+            code: "LAUNCH".to_string(),
+            name: "launch".to_string(),
+            bonus: PromoBonus::Percent(20),
+            max_uses: 10,
+            per_address_limit: 1,
+            expires_at: mock_env("owner", &[]).block.time + 100,
+        };
+        handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+        let msg = HandleMsg::BuyFood {
+            recipient: None,
+            memo: None,
+            promo_code: Some("LAUNCH".to_string()),
+            referrer: None,
+        };
+        let res = handle(&mut deps, mock_env("buyer", &coins(1000, "uscrt")), msg).unwrap();
+        assert_eq!(res.messages, vec![food_mint("buyer", 95445 + 19089, None)]);
+
+        // the bonus was minted off the curve, selling it would pay out other buyers' money
+        let res = receive_from(&mut deps, "food", 95445 + 19089, None);
+        assert_eq!(extract_error_msg(res), "Only 95445 FOOD can be sold back");
+        let msg = QueryMsg::SellQuote {
+            amount: Uint128(95445),
+        };
+        let refund = match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+            QueryAnswer::SellQuote { refund } => refund.u128(),
+            _ => panic!("Unexpected query answer"),
+        };
+        assert!(refund <= 1000);
+        let res = receive_from(&mut deps, "food", 95445, None).unwrap();
+        assert_eq!(res.messages[1], refund_to("buyer", refund));
+        let res = receive_from(&mut deps, "food", 1, None);
+        assert_eq!(extract_error_msg(res), "Only 0 FOOD can be sold back");
+
+        // FOOD that changed hands can't be sold back by its new holder either
+        let res = buy(&mut deps, &coins(1000, "uscrt")).unwrap();
+        assert_eq!(res.messages, vec![food_mint("buyer", 95445, None)]);
+        let msg = HandleMsg::Receive {
+            sender: HumanAddr::from("friend"),
+            from: HumanAddr::from("friend"),
+            amount: Uint128(100),
+            msg: None,
+        };
+        let res = handle(&mut deps, mock_env("food", &[]), msg);
+        assert_eq!(extract_error_msg(res), "Only 0 FOOD can be sold back");
+    }
+
+    fn sale_applied(res: &HandleResponse) -> Option<String> {
+        res.log
+            .iter()
+            .find(|log| log.key == "sale")
+            .map(|log| log.value.clone())
+    }
+
+    fn sale_window(name: &str, start: u64, end: u64, rate_multiplier: u64) -> SaleWindow {
+        SaleWindow {
+            name: name.to_string(),
+            start,
+            end,
+            rate_multiplier,
+            per_address_cap: None,
+        }
+    }
+
+    #[test]
+    fn best_sale_on_applies() {
+        let mut deps = init_helper();
+        let time = mock_env("owner", &[]).block.time;
+        let flash = SaleWindow {
+            per_address_cap: Some(Uint128(300)),
+            ..sale_window("flash", time, time + 60, 200)
+        };
+        let sales = vec![
+            sale_window("happy hour", time, time + 3600, 150),
+            flash,
+            sale_window("weekend", time + 1000, time + 2000, 300),
+        ];
+        for sale in sales.iter() {
+            let msg = HandleMsg::AddSale { sale: sale.clone() };
+            handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+        }
+        let msg = HandleMsg::AddSale {
+            sale: sale_window("happy hour", time + 10, time + 20, 150),
+        };
+        let res = handle(&mut deps, mock_env("owner", &[]), msg.clone());
+        assert_eq!(
+            extract_error_msg(res),
+            "There's already a sale with that name"
+        );
+        let res = handle(&mut deps, mock_env("buyer", &[]), msg);
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+
+        // 400 FOOD would take the buyer over the flash sale's cap
+        let res = buy(&mut deps, &coins(2, "uscrt")).unwrap();
+        assert_eq!(food_minted(&res), "300");
+        assert_eq!(sale_applied(&res), Some("happy hour".to_string()));
+        let res = buy(&mut deps, &coins(1, "uscrt")).unwrap();
+        assert_eq!(food_minted(&res), "200");
+        assert_eq!(sale_applied(&res), Some("flash".to_string()));
+        let res = buy(&mut deps, &coins(1, "uscrt")).unwrap();
+        assert_eq!(food_minted(&res), "150");
+        let msg = HandleMsg::BuyFood {
+            recipient: None,
+            memo: None,
+            promo_code: None,
+            referrer: None,
+        };
+        let res = handle(&mut deps, mock_env("bob", &coins(1, "uscrt")), msg.clone()).unwrap();
+        assert_eq!(food_minted(&res), "200");
+
+        let active = query(&deps, QueryMsg::ActiveSales { time }).unwrap();
+        assert_eq!(
+            from_binary::<QueryAnswer>(&active).unwrap(),
+            QueryAnswer::ActiveSales {
+                sales: sales[..2].to_vec()
+            }
+        );
+        let upcoming = query(&deps, QueryMsg::UpcomingSales { time }).unwrap();
+        assert_eq!(
+            from_binary::<QueryAnswer>(&upcoming).unwrap(),
+            QueryAnswer::UpcomingSales {
+                sales: sales[2..].to_vec()
+            }
+        );
+
+        let cancel = HandleMsg::CancelSale {
+            name: "flash".to_string(),
+        };
+        handle(&mut deps, mock_env("owner", &[]), cancel).unwrap();
+        let res = handle(&mut deps, mock_env("bob", &coins(1, "uscrt")), msg.clone()).unwrap();
+        assert_eq!(sale_applied(&res), Some("happy hour".to_string()));
+        let mut env = mock_env("bob", &coins(1, "uscrt"));
+        env.block.time = time + 3600;
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert_eq!(food_minted(&res), "100");
+        assert_eq!(sale_applied(&res), None);
+    }
+
+    fn buy_item(
+        deps: &mut Extern<MockStorage, MockApi, MockQuerier>,
+        sku: &str,
+        quantity: Option<u32>,
+        funds: &[Coin],
+    ) -> StdResult<HandleResponse> {
+        let msg = HandleMsg::BuyItem {
+            sku: sku.to_string(),
+            quantity,
+        };
+        handle(deps, mock_env("buyer", funds), msg)
+    }
+
+    #[test]
+    fn catalog_items_are_delivered_to_buyers() {
+        let mut deps = init_helper();
+        let medicine = CatalogItem {
+            sku: "medicine".to_string(),
+            name: "Medicine".to_string(),
+            description: None,
+            price: ItemPrice::Uscrt(Uint128(50)),
+            token: ItemToken::Snip20 {
+                address: HumanAddr::from("medicine"),
+                code_hash: "medicine_hash".to_string(),
+                amount: Uint128(1),
+                delivery: Delivery::Mint,
+            },
+            stock: Some(3),
+        };
+        let hat = CatalogItem {
+            sku: "hat".to_string(),
+            name: "Hat".to_string(),
+            description: Some("A tiny top hat".to_string()),
+            price: ItemPrice::Food(Uint128(200)),
+            token: ItemToken::Snip721 {
+                address: HumanAddr::from("cosmetics"),
+                code_hash: "cosmetics_hash".to_string(),
+            },
+            stock: None,
+        };
+        let add = |item: &CatalogItem| HandleMsg::AddItem { item: item.clone() };
+        let res = handle(&mut deps, mock_env("buyer", &[]), add(&medicine));
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = handle(&mut deps, mock_env("owner", &[]), add(&medicine)).unwrap();
+        assert!(res.messages.is_empty());
+        let res = handle(&mut deps, mock_env("owner", &[]), add(&medicine));
+        assert_eq!(
+            extract_error_msg(res),
+            "There's already an item with SKU medicine"
+        );
+        let env = mock_env("owner", &[]);
+        let code_hash = env.contract_code_hash.clone();
+        let res = handle(&mut deps, env, add(&hat)).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![snip20::register_receive_msg(
+                code_hash,
+                None,
+                BLOCK_SIZE,
+                "food_hash".to_string(),
+                HumanAddr::from("food"),
+            )
+            .unwrap()]
+        );
+
+        let res = buy_item(&mut deps, "medicine", None, &coins(40, "uscrt"));
+        assert_eq!(
+            extract_error_msg(res),
+            "Send exactly 50uscrt for 1 Medicine"
+        );
+        let res = buy_item(&mut deps, "medicine", Some(2), &coins(100, "uscrt")).unwrap();
+        let mint = snip20::mint_msg(
+            HumanAddr::from("buyer"),
+            Uint128(2),
+            None,
+            BLOCK_SIZE,
+            "medicine_hash".to_string(),
+            HumanAddr::from("medicine"),
+        )
+        .unwrap();
+        assert_eq!(res.messages, vec![mint]);
+        assert_eq!(treasury_balances(&deps), coins(100, "uscrt"));
+        let res = buy_item(&mut deps, "medicine", Some(2), &coins(100, "uscrt"));
+        assert_eq!(extract_error_msg(res), "Only 1 left in stock");
+
+        let res = buy_item(&mut deps, "hat", None, &coins(200, "uscrt"));
+        assert_eq!(extract_error_msg(res), "Send exactly 200 FOOD for 1 Hat");
+        let msg = ReceiveMsg::BuyItem {
+            sku: "hat".to_string(),
+            quantity: None,
+        };
+        let res = receive_from(&mut deps, "food", 200, Some(msg)).unwrap();
+        let nft = NftHandleMsg::MintNft {
+            owner: HumanAddr::from("buyer"),
+            public_metadata: Some(NftMetadata {
+                name: Some("Hat".to_string()),
+                description: Some("A tiny top hat".to_string()),
+                image: None,
+            }),
+            memo: None,
+            padding: None,
+        }
+        .to_cosmos_msg(
+            "cosmetics_hash".to_string(),
+            HumanAddr::from("cosmetics"),
+            None,
+        )
+        .unwrap();
+        let burn = snip20::burn_msg(
+            Uint128(200),
+            None,
+            BLOCK_SIZE,
+            "food_hash".to_string(),
+            HumanAddr::from("food"),
+        )
+        .unwrap();
+        assert_eq!(res.messages, vec![nft, burn]);
+
+        // restocked, and from now on sent out of the market's own medicine
+        let msg = HandleMsg::UpdateItem {
+            item: CatalogItem {
+                token: ItemToken::Snip20 {
+                    address: HumanAddr::from("medicine"),
+                    code_hash: "medicine_hash".to_string(),
+                    amount: Uint128(1),
+                    delivery: Delivery::Transfer,
+                },
+                stock: None,
+                ..medicine
+            },
+        };
+        handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+        let res = buy_item(&mut deps, "medicine", Some(2), &coins(100, "uscrt")).unwrap();
+        let transfer = snip20::transfer_msg(
+            HumanAddr::from("buyer"),
+            Uint128(2),
+            None,
+            BLOCK_SIZE,
+            "medicine_hash".to_string(),
+            HumanAddr::from("medicine"),
+        )
+        .unwrap();
+        assert_eq!(res.messages, vec![transfer]);
+
+        let msg = HandleMsg::RemoveItem {
+            sku: "medicine".to_string(),
+        };
+        handle(&mut deps, mock_env("owner", &[]), msg).unwrap();
+        let msg = QueryMsg::Catalog {
+            page: None,
+            page_size: 10,
+        };
+        let answer: QueryAnswer = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(
+            answer,
+            QueryAnswer::Catalog {
+                items: vec![hat],
+                total: 1,
+            }
+        );
+        // pages too far out are empty instead of overflowing
+        let msg = QueryMsg::Catalog {
+            page: Some(u32::MAX),
+            page_size: u32::MAX,
+        };
+        let answer: QueryAnswer = from_binary(&query(&deps, msg).unwrap()).unwrap();
+        assert_eq!(
+            answer,
+            QueryAnswer::Catalog {
+                items: vec![],
+                total: 1,
+            }
+        );
+    }
+
+    /// Answers FOOD's queries like a SNIP-20 contract would
+    struct FoodQuerier {
+        minters: Vec<HumanAddr>,
+        status: String,
+        mint_enabled: bool,
+    }
+
+    impl Querier for FoodQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let msg = match from_slice(bin_request).unwrap() {
+                QueryRequest::<Empty>::Wasm(WasmQuery::Smart { msg, .. }) => msg,
+                _ => panic!("Unexpected query"),
+            };
+            let answer = match from_binary(&msg).unwrap() {
+                FoodQueryMsg::Minters {} => to_binary(&MintersResponse {
+                    minters: Minters {
+                        minters: self.minters.clone(),
+                    },
+                }),
+                FoodQueryMsg::ContractStatus {} => to_binary(&ContractStatusResponse {
+                    contract_status: ContractStatus {
+                        status: self.status.clone(),
+                    },
+                }),
+                FoodQueryMsg::TokenConfig {} => to_binary(&TokenConfigResponse {
+                    token_config: TokenConfig {
+                        mint_enabled: self.mint_enabled,
+                    },
+                }),
+            };
+            Ok(answer)
+        }
+    }
+
+    fn init_with_food(
+        minters: &[&str],
+        status: &str,
+        mint_enabled: bool,
+    ) -> Extern<MockStorage, MockApi, FoodQuerier> {
+        let mut deps = Extern {
+            storage: MockStorage::default(),
+            api: MockApi::new(20),
+            querier: FoodQuerier {
+                minters: minters
+                    .iter()
+                    .map(|&minter| HumanAddr::from(minter))
+                    .collect(),
+                status: status.to_string(),
+                mint_enabled,
+            },
+        };
+        let msg = InitMsg {
+            token_code_hash: "food_hash".to_string(),
+            token_address: "food".to_string(),
+            exchange_rate: None,
+            prng_seed: Binary::from("seed".as_bytes()),
+        };
+        init(&mut deps, mock_env("owner", &[]), msg).unwrap();
+        deps
+    }
+
+    fn health<Q: Querier>(deps: &Extern<MockStorage, MockApi, Q>) -> QueryAnswer {
+        from_binary(&query(deps, QueryMsg::Health {}).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn health_check_asks_food() {
+        let buy_food = HandleMsg::BuyFood {
+            recipient: None,
+            memo: None,
+            promo_code: None,
+            referrer: None,
+        };
+        let mut deps = init_with_food(&["pet"], "normal_run", false);
+        assert_eq!(
+            health(&deps),
+            QueryAnswer::Health {
+                healthy: false,
+                problems: vec![HealthProblem::NotAMinter, HealthProblem::MintingDisabled],
+            }
+        );
+        let res = handle(
+            &mut deps,
+            mock_env("buyer", &coins(1, "uscrt")),
+            buy_food.clone(),
+        );
+        assert_eq!(
+            extract_error_msg(res),
+            "The market isn't a FOOD minter, the owner has to add it first"
+        );
+
+        let mut deps = init_with_food(&["pet", "cosmos2contract"], "stop_all", true);
+        assert_eq!(
+            health(&deps),
+            QueryAnswer::Health {
+                healthy: false,
+                problems: vec![HealthProblem::FoodStopped {
+                    status: "stop_all".to_string()
+                }],
+            }
+        );
+        handle(&mut deps, mock_env("buyer", &coins(1, "uscrt")), buy_food).unwrap();
+
+        let deps = init_with_food(&["cosmos2contract"], "normal_run", true);
+        assert_eq!(
+            health(&deps),
+            QueryAnswer::Health {
+                healthy: true,
+                problems: vec![],
+            }
+        );
+
+        // no FOOD to ask at all
+        match health(&init_helper()) {
+            QueryAnswer::Health { healthy, problems } => {
+                assert!(!healthy);
+                let queries: Vec<_> = problems
+                    .into_iter()
+                    .map(|problem| match problem {
+                        HealthProblem::FoodUnreachable { query, .. } => query,
+                        _ => panic!("Unexpected problem {:?}", problem),
+                    })
+                    .collect();
+                assert_eq!(queries, vec!["minters", "contract_status", "token_config"]);
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+    fn buy_referred(
+        deps: &mut Extern<MockStorage, MockApi, MockQuerier>,
+        buyer: &str,
+        referrer: Option<&str>,
+    ) -> StdResult<HandleResponse> {
+        let msg = HandleMsg::BuyFood {
+            recipient: None,
+            memo: None,
+            promo_code: None,
+            referrer: referrer.map(HumanAddr::from),
+        };
+        handle(deps, mock_env(buyer, &coins(10, "uscrt")), msg)
+    }
+
+    fn referral_mint(buyer: &str, food: u128, referrer: &str, reward: u128) -> CosmosMsg {
+        let batch = FoodHandleMsg::BatchMint {
+            actions: vec![
+                MintAction {
+                    recipient: HumanAddr::from(buyer),
+                    amount: Uint128(food),
+                    memo: None,
+                },
+                MintAction {
+                    recipient: HumanAddr::from(referrer),
+                    amount: Uint128(reward),
+                    memo: Some("Referral reward".to_string()),
+                },
+            ],
+            padding: None,
+        };
+        batch
+            .to_cosmos_msg("food_hash".to_string(), HumanAddr::from("food"), None)
+            .unwrap()
+    }
+
+    #[test]
+    fn referrers_earn_a_share_of_purchases() {
+        let mut deps = init_helper();
+        let res = buy_referred(&mut deps, "alice", Some("alice"));
+        assert_eq!(extract_error_msg(res), "You can't refer yourself");
+        let res = buy_referred(&mut deps, "alice", Some("ref")).unwrap();
+        assert_eq!(res.messages, vec![referral_mint("alice", 1000, "ref", 50)]);
+        // the binding stays for every purchase after
+        let res = buy_referred(&mut deps, "alice", None).unwrap();
+        assert_eq!(res.messages, vec![referral_mint("alice", 1000, "ref", 50)]);
+        let res = buy_referred(&mut deps, "alice", Some("other"));
+        assert_eq!(extract_error_msg(res), "A referrer can only be set once");
+        buy_referred(&mut deps, "alice", Some("ref")).unwrap();
+        let res = buy_referred(&mut deps, "ref", Some("alice"));
+        assert_eq!(extract_error_msg(res), "Referral loops aren't allowed");
+        let res = buy_referred(&mut deps, "ref", None).unwrap();
+        assert_eq!(res.messages, vec![food_mint("ref", 1000, None)]);
+
+        let set_percent = |percent| HandleMsg::SetReferralPercent { percent };
+        let res = handle(&mut deps, mock_env("alice", &[]), set_percent(10));
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = handle(&mut deps, mock_env("owner", &[]), set_percent(101));
+        assert_eq!(
+            extract_error_msg(res),
+            "Referral percentage can be up to 100"
+        );
+        handle(&mut deps, mock_env("owner", &[]), set_percent(10)).unwrap();
+        let res = buy_referred(&mut deps, "alice", None).unwrap();
+        assert_eq!(res.messages, vec![referral_mint("alice", 1000, "ref", 100)]);
+
+        let msg = HandleMsg::SetViewingKey {
+            key: "key".to_string(),
+            padding: None,
+        };
+        handle(&mut deps, mock_env("ref", &[]), msg).unwrap();
+        let totals = |key: &str| {
+            let msg = QueryMsg::ReferralTotals {
+                address: HumanAddr::from("ref"),
+                key: key.to_string(),
+            };
+            from_binary::<QueryAnswer>(&query(&deps, msg).unwrap()).unwrap()
+        };
+        assert_eq!(
+            totals("key"),
+            QueryAnswer::ReferralTotals {
+                referrals: 1,
+                purchases: 4,
+                food_earned: Uint128(250),
+            }
+        );
+        assert!(matches!(
+            totals("wrong_key"),
+            QueryAnswer::ViewingKeyError { .. }
+        ));
+    }
 }
@@ -0,0 +1,160 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdError, StdResult, Uint128};
+
+/// Prices are in millionths of the denom per FOOD unit
+pub const PRICE_PRECISION: u128 = 1_000_000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Pricing {
+    /// FOOD at the rates of the payment options and tokens
+    Fixed,
+    BondingCurve(BondingCurve),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CurveShape {
+    /// The price rises by `slope` millionths for every million FOOD units minted
+    Linear { base_price: Uint128, slope: Uint128 },
+    /// The price doubles every `doubling_supply` FOOD units, rising linearly in between
+    Exponential {
+        base_price: Uint128,
+        doubling_supply: Uint128,
+    },
+}
+
+/// The price of FOOD follows the supply minted on the curve. Costs are the area under the
+/// curve, worked out exactly as a fraction and rounded up for buyers and down for sellers,
+/// so a buy followed by a sell never makes a profit
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BondingCurve {
+    /// Native denom the curve is priced in
+    pub denom: String,
+    pub shape: CurveShape,
+    /// FOOD can be sent back to the market for a refund down the curve, by the addresses it was
+    /// bought on the curve for. Bonuses, rewards and FOOD bought at fixed rates can't
+    pub sell_back: bool,
+}
+
+impl BondingCurve {
+    pub fn validate(&self) -> StdResult<()> {
+        if self.denom.is_empty() {
+            return Err(StdError::generic_err("Bonding curve needs a denom"));
+        }
+        let valid = match self.shape {
+            CurveShape::Linear { base_price, .. } => base_price.u128() > 0,
+            CurveShape::Exponential {
+                base_price,
+                doubling_supply,
+            } => {
+                base_price.u128() > 0
+                    && doubling_supply.u128() > 0
+                    && doubling_supply.u128() <= u128::MAX / (2 * PRICE_PRECISION)
+            }
+        };
+        if !valid {
+            return Err(StdError::generic_err(
+                "Bonding curve needs a base price and a doubling supply above 0",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Area under the curve from 0 to `supply`, times `denominator()`
+    fn area(&self, supply: u128) -> Option<u128> {
+        match self.shape {
+            CurveShape::Linear { base_price, slope } => {
+                let flat = base_price
+                    .u128()
+                    .checked_mul(2 * PRICE_PRECISION)?
+                    .checked_mul(supply)?;
+                let rising = slope.u128().checked_mul(supply)?.checked_mul(supply)?;
+                flat.checked_add(rising)
+            }
+            CurveShape::Exponential {
+                base_price,
+                doubling_supply,
+            } => {
+                let step = doubling_supply.u128();
+                let doublings = supply / step;
+                let rest = supply % step;
+                if doublings >= 127 {
+                    return None;
+                }
+                let factor = 1u128 << doublings;
+                // each whole step costs one and a half times its starting price
+                let whole_steps = step
+                    .checked_mul(step)?
+                    .checked_mul(3)?
+                    .checked_mul(factor - 1)?;
+                let last_step = step
+                    .checked_mul(2)?
+                    .checked_mul(rest)?
+                    .checked_add(rest.checked_mul(rest)?)?
+                    .checked_mul(factor)?;
+                base_price
+                    .u128()
+                    .checked_mul(whole_steps.checked_add(last_step)?)
+            }
+        }
+    }
+
+    fn denominator(&self) -> u128 {
+        match self.shape {
+            CurveShape::Linear { .. } => 2 * PRICE_PRECISION * PRICE_PRECISION,
+            CurveShape::Exponential {
+                doubling_supply, ..
+            } => 2 * doubling_supply.u128() * PRICE_PRECISION,
+        }
+    }
+
+    /// The area only grows with the supply, so `from` up to `to` can't underflow
+    fn area_between(&self, from: u128, to: u128) -> StdResult<u128> {
+        match (self.area(from), self.area(to)) {
+            (Some(from), Some(to)) => Ok(to - from),
+            _ => Err(StdError::generic_err(
+                "FOOD supply is out of the curve's range",
+            )),
+        }
+    }
+
+    /// What minting `amount` FOOD on top of `supply` costs
+    pub fn buy_cost(&self, supply: u128, amount: u128) -> StdResult<u128> {
+        let to = supply
+            .checked_add(amount)
+            .ok_or_else(|| StdError::generic_err("FOOD supply is out of the curve's range"))?;
+        let area = self.area_between(supply, to)?;
+        let denominator = self.denominator();
+        Ok(area / denominator + (area % denominator > 0) as u128)
+    }
+
+    /// What selling `amount` FOOD back from `supply` refunds
+    pub fn sell_refund(&self, supply: u128, amount: u128) -> StdResult<u128> {
+        let from = supply.checked_sub(amount).ok_or_else(|| {
+            StdError::generic_err(format!("Only {} FOOD can be sold back", supply))
+        })?;
+        Ok(self.area_between(from, supply)? / self.denominator())
+    }
+
+    /// Most FOOD `payment` buys on top of `supply`, and what it costs
+    pub fn quote_buy(&self, supply: u128, payment: u128) -> (u128, u128) {
+        let base_price = match self.shape {
+            CurveShape::Linear { base_price, .. } => base_price.u128(),
+            CurveShape::Exponential { base_price, .. } => base_price.u128(),
+        };
+        // the price never drops below the base price
+        let mut low = 0;
+        let mut high = payment.saturating_mul(PRICE_PRECISION) / base_price + 1;
+        while low < high {
+            let amount = low + (high - low + 1) / 2;
+            match self.buy_cost(supply, amount) {
+                Ok(cost) if cost <= payment => low = amount,
+                _ => high = amount - 1,
+            }
+        }
+        (low, self.buy_cost(supply, low).unwrap_or(0))
+    }
+}
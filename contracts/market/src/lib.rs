@@ -1,5 +1,11 @@
+pub mod catalog;
 pub mod contract;
+pub mod curve;
 pub mod msg;
+pub mod promo;
+pub mod receipts;
+pub mod referrals;
+pub mod sales;
 pub mod state;
 
 #[cfg(target_arch = "wasm32")]
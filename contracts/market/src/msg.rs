@@ -1,24 +1,412 @@
+use cosmwasm_std::{Binary, Coin, HumanAddr, Uint128};
 use schemars::JsonSchema;
+use secret_toolkit::permit::Permit;
 use serde::{Deserialize, Serialize};
 
+use secret_toolkit::utils::{HandleCallback, Query};
+
+use crate::catalog::CatalogItem;
+use crate::curve::Pricing;
+use crate::promo::PromoBonus;
+use crate::receipts::Receipt;
+use crate::sales::SaleWindow;
+use shared::viewing_key::ViewingKey;
+
 
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InitMsg {
     pub token_code_hash: String,
     pub token_address: String,
+    /// FOOD per uscrt, the only payment option until the owner sets others
     pub exchange_rate: Option<u64>,
+    pub prng_seed: Binary,
+}
+
+/// Native denom the market accepts, e.g. uscrt or an IBC asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PaymentOption {
+    pub denom: String,
+    /// FOOD minted per unit of the denom
+    pub rate: u64,
+    pub min_purchase: Option<Uint128>,
+}
+
+/// SNIP-20 token the market accepts through `Send`, e.g. sSCRT
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PaymentToken {
+    pub address: HumanAddr,
+    pub code_hash: String,
+    /// FOOD minted per unit of the token
+    pub rate: u64,
+    pub min_purchase: Option<Uint128>,
+}
+
+/// SNIP-20 tokens held by the market
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenBalance {
+    pub address: HumanAddr,
+    pub code_hash: String,
+    pub amount: Uint128,
 }
 
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum HandleMsg {
-    BuyFood {},
+    /// Pays with exactly one coin of an accepted denom. The FOOD goes to the buyer unless
+    /// it's a gift, and the memo shows up in the recipient's FOOD transaction history.
+    /// A promo code adds its bonus to the FOOD bought. The first referrer a buyer names is
+    /// theirs for good, and gets a share of everything they buy from then on
+    BuyFood {
+        recipient: Option<HumanAddr>,
+        memo: Option<String>,
+        promo_code: Option<String>,
+        referrer: Option<HumanAddr>,
+    },
+    /// Like `BuyFood`, with the FOOD split evenly between the recipients in one batch mint
+    GiftFood {
+        recipients: Vec<HumanAddr>,
+        memo: Option<String>,
+        promo_code: Option<String>,
+    },
+    /// Owner only, replaces the table of accepted denoms
+    SetPaymentOptions { options: Vec<PaymentOption> },
+    /// Owner only, `None` withdraws the whole balance of the denom, to the owner by default
+    Withdraw {
+        denom: String,
+        amount: Option<Uint128>,
+        recipient: Option<HumanAddr>,
+    },
+    /// Owner only, replaces the accepted SNIP-20 tokens and registers the market with them
+    SetPaymentTokens { tokens: Vec<PaymentToken> },
+    /// Owner only
+    SetReferralPercent { percent: u64 },
+    /// Owner only, switches between the fixed rates and a bonding curve
+    SetPricing { pricing: Pricing },
+    /// Owner only, purchases in the window get the best multiplier of the sales on at the time
+    AddSale { sale: SaleWindow },
+    /// Owner only
+    CancelSale { name: String },
+    /// Owner only, items priced in FOOD register the market with FOOD
+    AddItem { item: CatalogItem },
+    /// Owner only, replaces the item with the same SKU
+    UpdateItem { item: CatalogItem },
+    /// Owner only, `None` lifts the stock limit
+    SetItemStock { sku: String, stock: Option<u32> },
+    /// Owner only
+    RemoveItem { sku: String },
+    /// Pays for items priced in uscrt, one at a time by default
+    BuyItem { sku: String, quantity: Option<u32> },
+    /// Owner only, like `Withdraw` for SNIP-20 tokens
+    WithdrawToken {
+        address: HumanAddr,
+        amount: Option<Uint128>,
+        recipient: Option<HumanAddr>,
+    },
+    /// `Send` of an accepted SNIP-20 token, `from` gets the FOOD. FOOD itself is sold back
+    /// when the bonding curve allows it
+    Receive {
+        sender: HumanAddr,
+        from: HumanAddr,
+        amount: Uint128,
+        msg: Option<Binary>,
+    },
+    /// Owner only, replaces the list of contracts (e.g. pets) allowed to mint rewards
+    SetRewarders { rewarders: Vec<HumanAddr> },
+    /// Mints FOOD for free, used by pets to pay out achievement bonuses
+    MintReward {
+        recipient: HumanAddr,
+        amount: Uint128,
+        memo: Option<String>,
+    },
+    /// Owner only, only the hash of the code is stored and `name` is what the public sees
+    AddPromoCode {
+        code: String,
+        name: String,
+        bonus: PromoBonus,
+        max_uses: u32,
+        per_address_limit: u32,
+        expires_at: u64,
+    },
+    /// Owner only
+    RemovePromoCode { name: String },
+    CreateViewingKey {
+        entropy: String,
+        padding: Option<String>,
+    },
+    SetViewingKey {
+        key: String,
+        padding: Option<String>,
+    },
+    RevokePermit {
+        permit_name: String,
+        padding: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleAnswer {
+    CreateViewingKey { key: ViewingKey },
+    SetViewingKey { status: ResponseStatus },
+    RevokePermit { status: ResponseStatus },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    Success,
+    Failure,
+}
+
+/// Payload of a payment token's `Send`, buying FOOD is the default
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    BuyFood {
+        recipient: Option<HumanAddr>,
+        memo: Option<String>,
+        promo_code: Option<String>,
+        referrer: Option<HumanAddr>,
+    },
+    GiftFood {
+        recipients: Vec<HumanAddr>,
+        memo: Option<String>,
+        promo_code: Option<String>,
+    },
+    /// Pays for items priced in FOOD
+    BuyItem { sku: String, quantity: Option<u32> },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
+    PaymentOptions {},
+    Treasury {},
+    Pricing {},
+    /// Checks with FOOD that the market can mint
+    Health {},
+    /// Pages hold at most `MAX_CATALOG_ITEMS` items, as many as the catalog can
+    Catalog {
+        page: Option<u32>,
+        page_size: u32,
+    },
+    ActiveSales {
+        time: u64,
+    },
+    UpcomingSales {
+        time: u64,
+    },
+    /// FOOD a payment in the curve's denom buys right now, and what it costs of the payment
+    BuyQuote {
+        payment: Uint128,
+    },
+    SellQuote {
+        amount: Uint128,
+    },
+    /// Looks a promo code up by its public name
+    PromoCode {
+        name: String,
+    },
+    /// The buyer's receipts, latest first
+    PurchaseHistory {
+        address: HumanAddr,
+        key: String,
+        page: Option<u32>,
+        page_size: u32,
+    },
+    /// What the address earned by referring buyers
+    ReferralTotals {
+        address: HumanAddr,
+        key: String,
+    },
+    WithPermit {
+        permit: Permit,
+        query: QueryWithPermit,
+    },
+}
+
+impl QueryMsg {
+    pub fn get_validation_params(&self) -> (Vec<&HumanAddr>, ViewingKey) {
+        match self {
+            Self::PurchaseHistory { address, key, .. } => (vec![address], ViewingKey(key.clone())),
+            Self::ReferralTotals { address, key } => (vec![address], ViewingKey(key.clone())),
+            _ => panic!("This query type does not require authentication"),
+        }
+    }
+}
+
+/// Queries allowed with a permit that has the `history` permission
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryWithPermit {
+    PurchaseHistory { page: Option<u32>, page_size: u32 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryAnswer {
+    PaymentOptions {
+        options: Vec<PaymentOption>,
+        tokens: Vec<PaymentToken>,
+    },
+    Treasury {
+        balances: Vec<Coin>,
+        tokens: Vec<TokenBalance>,
+    },
+    Pricing {
+        pricing: Pricing,
+        curve_supply: Uint128,
+    },
+    BuyQuote {
+        food: Uint128,
+        cost: Uint128,
+    },
+    Catalog {
+        items: Vec<CatalogItem>,
+        total: u64,
+    },
+    Health {
+        healthy: bool,
+        problems: Vec<HealthProblem>,
+    },
+    ActiveSales {
+        sales: Vec<SaleWindow>,
+    },
+    UpcomingSales {
+        sales: Vec<SaleWindow>,
+    },
+    SellQuote {
+        refund: Uint128,
+    },
+    PromoCode {
+        name: String,
+        bonus: PromoBonus,
+        remaining_uses: u32,
+        per_address_limit: u32,
+        expires_at: u64,
+    },
+    PurchaseHistory {
+        receipts: Vec<Receipt>,
+        total: u64,
+    },
+    ReferralTotals {
+        referrals: u32,
+        purchases: u32,
+        food_earned: Uint128,
+    },
+    ViewingKeyError {
+        msg: String,
+    },
+}
+
+/// FOOD messages the market sends, `snip20::mint_msg` has no memo
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FoodHandleMsg {
+    Mint {
+        recipient: HumanAddr,
+        amount: Uint128,
+        memo: Option<String>,
+        padding: Option<String>,
+    },
+    BatchMint {
+        actions: Vec<MintAction>,
+        padding: Option<String>,
+    },
+}
+
+impl HandleCallback for FoodHandleMsg {
+    const BLOCK_SIZE: usize = 256;
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintAction {
+    pub recipient: HumanAddr,
+    pub amount: Uint128,
+    pub memo: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthProblem {
+    /// FOOD didn't answer the query
+    FoodUnreachable {
+        query: String,
+        error: String,
+    },
+    NotAMinter,
+    MintingDisabled,
+    FoodStopped {
+        status: String,
+    },
+}
+
+/// FOOD queries the market makes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FoodQueryMsg {
+    Minters {},
+    ContractStatus {},
+    TokenConfig {},
+}
+
+impl Query for FoodQueryMsg {
+    const BLOCK_SIZE: usize = 256;
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintersResponse {
+    pub minters: Minters,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Minters {
+    pub minters: Vec<HumanAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContractStatusResponse {
+    pub contract_status: ContractStatus,
+}
+
+/// `normal_run` while FOOD works as usual
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContractStatus {
+    pub status: String,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenConfigResponse {
+    pub token_config: TokenConfig,
+}
+
+/// The part of FOOD's token config the market cares about
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenConfig {
+    pub mint_enabled: bool,
+}
+
+/// SNIP-721 messages the market sends for catalog items
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NftHandleMsg {
+    MintNft {
+        owner: HumanAddr,
+        public_metadata: Option<NftMetadata>,
+        memo: Option<String>,
+        padding: Option<String>,
+    },
+}
+
+impl HandleCallback for NftHandleMsg {
+    const BLOCK_SIZE: usize = 256;
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NftMetadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+}
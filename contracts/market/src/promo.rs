@@ -0,0 +1,108 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CanonicalAddr, ReadonlyStorage, StdResult, Storage, Uint128};
+use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
+
+use secret_toolkit::storage::{TypedStore, TypedStoreMut};
+
+use shared::rand::sha_256;
+
+const PREFIX_PROMO_CODES: &[u8] = b"promo_codes";
+const PREFIX_PROMO_NAMES: &[u8] = b"promo_names";
+const PREFIX_PROMO_USES: &[u8] = b"promo_uses";
+const PREFIX_PROMO_GENERATIONS: &[u8] = b"promo_generations";
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PromoBonus {
+    /// Extra FOOD in percent of what the payment buys
+    Percent(u64),
+    /// Extra FOOD per purchase
+    Fixed(Uint128),
+}
+
+impl PromoBonus {
+    pub fn extra_food(self, food: u128) -> u128 {
+        match self {
+            PromoBonus::Percent(percent) => food.saturating_mul(percent as u128) / 100,
+            PromoBonus::Fixed(amount) => amount.u128(),
+        }
+    }
+}
+
+/// Stored under the hash of the code, so the code itself is never kept
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct PromoCode {
+    /// Public label of the campaign, shown in queries and receipts instead of the code
+    pub name: String,
+    pub bonus: PromoBonus,
+    pub max_uses: u32,
+    pub uses: u32,
+    pub per_address_limit: u32,
+    pub expires_at: u64,
+}
+
+pub fn hash_code(code: &str) -> [u8; 32] {
+    sha_256(code.as_bytes())
+}
+
+pub fn save_promo<S: Storage>(storage: &mut S, hash: &[u8], promo: &PromoCode) -> StdResult<()> {
+    PrefixedStorage::new(PREFIX_PROMO_NAMES, storage).set(promo.name.as_bytes(), hash);
+    let mut store = PrefixedStorage::new(PREFIX_PROMO_CODES, storage);
+    let mut store = TypedStoreMut::attach(&mut store);
+    store.store(hash, promo)
+}
+
+pub fn load_promo<S: ReadonlyStorage>(storage: &S, hash: &[u8]) -> StdResult<Option<PromoCode>> {
+    let store = ReadonlyPrefixedStorage::new(PREFIX_PROMO_CODES, storage);
+    let store = TypedStore::attach(&store);
+    store.may_load(hash)
+}
+
+/// Hash of the code behind a campaign name
+pub fn promo_hash<S: ReadonlyStorage>(storage: &S, name: &str) -> Option<Vec<u8>> {
+    ReadonlyPrefixedStorage::new(PREFIX_PROMO_NAMES, storage).get(name.as_bytes())
+}
+
+/// The per-address counters can't be listed to clear them, so the code moves on to a new
+/// generation of counters instead, and starts from zero if it's added again
+pub fn remove_promo<S: Storage>(storage: &mut S, name: &str, hash: &[u8]) {
+    PrefixedStorage::new(PREFIX_PROMO_NAMES, storage).remove(name.as_bytes());
+    PrefixedStorage::new(PREFIX_PROMO_CODES, storage).remove(hash);
+    let generation = promo_generation(storage, hash) + 1;
+    PrefixedStorage::new(PREFIX_PROMO_GENERATIONS, storage).set(hash, &generation.to_be_bytes());
+}
+
+/// Times the code was removed
+fn promo_generation<S: ReadonlyStorage>(storage: &S, hash: &[u8]) -> u32 {
+    ReadonlyPrefixedStorage::new(PREFIX_PROMO_GENERATIONS, storage)
+        .get(hash)
+        .map_or(0, |generation| {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(&generation);
+            u32::from_be_bytes(bytes)
+        })
+}
+
+/// Namespace of the counters of the code's current generation, hashes all have the same length
+fn uses_namespace<S: ReadonlyStorage>(storage: &S, hash: &[u8]) -> Vec<u8> {
+    [hash, &promo_generation(storage, hash).to_be_bytes()].concat()
+}
+
+pub fn promo_uses<S: ReadonlyStorage>(storage: &S, hash: &[u8], buyer: &CanonicalAddr) -> u32 {
+    let namespace = uses_namespace(storage, hash);
+    ReadonlyPrefixedStorage::multilevel(&[PREFIX_PROMO_USES, &namespace], storage)
+        .get(buyer.as_slice())
+        .map_or(0, |uses| {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(&uses);
+            u32::from_be_bytes(bytes)
+        })
+}
+
+pub fn set_promo_uses<S: Storage>(storage: &mut S, hash: &[u8], buyer: &CanonicalAddr, uses: u32) {
+    let namespace = uses_namespace(storage, hash);
+    PrefixedStorage::multilevel(&[PREFIX_PROMO_USES, &namespace], storage)
+        .set(buyer.as_slice(), &uses.to_be_bytes());
+}
@@ -0,0 +1,61 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CanonicalAddr, ReadonlyStorage, StdResult, Storage, Uint128};
+use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
+
+use secret_toolkit::storage::{AppendStore, AppendStoreMut};
+
+use shared::paging::page_start;
+
+const PREFIX_RECEIPTS: &[u8] = b"receipts";
+
+/// Record of a purchase, kept for the buyer even when the FOOD went to someone else
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct Receipt {
+    pub paid: Uint128,
+    /// Native denom or address of the payment token
+    pub asset: String,
+    pub food_minted: Uint128,
+    /// FOOD per unit of the asset, `None` on a bonding curve, where the price moves with every
+    /// unit. `paid` and `food_minted` give the average
+    pub rate: Option<u64>,
+    pub time: u64,
+    pub promo_code: Option<String>,
+}
+
+pub fn append_receipt<S: Storage>(
+    store: &mut S,
+    buyer: &CanonicalAddr,
+    receipt: &Receipt,
+) -> StdResult<()> {
+    let mut store = PrefixedStorage::multilevel(&[PREFIX_RECEIPTS, buyer.as_slice()], store);
+    let mut store = AppendStoreMut::attach_or_create(&mut store)?;
+    store.push(receipt)
+}
+
+/// Returns a page of the buyer's receipts, latest first, and the total count
+pub fn get_receipts<S: ReadonlyStorage>(
+    storage: &S,
+    buyer: &CanonicalAddr,
+    page: u32,
+    page_size: u32,
+) -> StdResult<(Vec<Receipt>, u64)> {
+    let store = ReadonlyPrefixedStorage::multilevel(&[PREFIX_RECEIPTS, buyer.as_slice()], storage);
+
+    // A buyer who never bought anything has no store yet
+    let store = AppendStore::<Receipt, _, _>::attach(&store);
+    let store = if let Some(result) = store {
+        result?
+    } else {
+        return Ok((vec![], 0));
+    };
+
+    let receipts: StdResult<Vec<Receipt>> = store
+        .iter()
+        .rev()
+        .skip(page_start(page, page_size))
+        .take(page_size as _)
+        .collect();
+    receipts.map(|receipts| (receipts, store.len() as u64))
+}
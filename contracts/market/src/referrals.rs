@@ -0,0 +1,52 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Binary, CanonicalAddr, ReadonlyStorage, StdResult, Storage, Uint128};
+use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
+
+use secret_toolkit::storage::{TypedStore, TypedStoreMut};
+
+const PREFIX_REFERRERS: &[u8] = b"referrers";
+const PREFIX_REFERRAL_TOTALS: &[u8] = b"referral_totals";
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq)]
+pub struct ReferralTotals {
+    /// Buyers who bound this address as their referrer
+    pub referrals: u32,
+    /// Purchases of those buyers that earned a reward
+    pub purchases: u32,
+    pub food_earned: Uint128,
+}
+
+/// The binding is permanent, there's no way to change or remove it
+pub fn bind_referrer<S: Storage>(storage: &mut S, buyer: &CanonicalAddr, referrer: &CanonicalAddr) {
+    PrefixedStorage::new(PREFIX_REFERRERS, storage).set(buyer.as_slice(), referrer.as_slice());
+}
+
+pub fn read_referrer<S: ReadonlyStorage>(
+    storage: &S,
+    buyer: &CanonicalAddr,
+) -> Option<CanonicalAddr> {
+    ReadonlyPrefixedStorage::new(PREFIX_REFERRERS, storage)
+        .get(buyer.as_slice())
+        .map(|referrer| CanonicalAddr(Binary(referrer)))
+}
+
+pub fn save_referral_totals<S: Storage>(
+    storage: &mut S,
+    referrer: &CanonicalAddr,
+    totals: &ReferralTotals,
+) -> StdResult<()> {
+    let mut store = PrefixedStorage::new(PREFIX_REFERRAL_TOTALS, storage);
+    let mut store = TypedStoreMut::attach(&mut store);
+    store.store(referrer.as_slice(), totals)
+}
+
+pub fn load_referral_totals<S: ReadonlyStorage>(
+    storage: &S,
+    referrer: &CanonicalAddr,
+) -> StdResult<ReferralTotals> {
+    let store = ReadonlyPrefixedStorage::new(PREFIX_REFERRAL_TOTALS, storage);
+    let store = TypedStore::attach(&store);
+    Ok(store.may_load(referrer.as_slice())?.unwrap_or_default())
+}
@@ -0,0 +1,91 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CanonicalAddr, ReadonlyStorage, StdError, StdResult, Storage, Uint128};
+use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
+
+pub const MAX_SALES: usize = 20;
+/// Highest multiplier, in percent, a sale can set
+pub const MAX_SALE_MULTIPLIER: u64 = 1000;
+const MAX_SALE_NAME_LENGTH: usize = 30;
+const PREFIX_SALE_PURCHASES: &[u8] = b"sale_purchases";
+
+/// Flash sale or happy hour, FOOD bought in the window is multiplied by `rate_multiplier`
+/// percent
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct SaleWindow {
+    pub name: String,
+    pub start: u64,
+    pub end: u64,
+    pub rate_multiplier: u64,
+    /// Most FOOD an address can buy at the sale rate
+    pub per_address_cap: Option<Uint128>,
+}
+
+impl SaleWindow {
+    pub fn is_active(&self, current_time: u64) -> bool {
+        self.start <= current_time && current_time < self.end
+    }
+
+    pub fn validate(&self, current_time: u64) -> StdResult<()> {
+        if self.name.is_empty() || self.name.len() > MAX_SALE_NAME_LENGTH {
+            return Err(StdError::generic_err(format!(
+                "Sale name has to be 1-{} bytes long",
+                MAX_SALE_NAME_LENGTH
+            )));
+        }
+        if self.end <= self.start || self.end <= current_time {
+            return Err(StdError::generic_err(
+                "Sale has to end after it starts, in the future",
+            ));
+        }
+        if self.rate_multiplier <= 100 || self.rate_multiplier > MAX_SALE_MULTIPLIER {
+            return Err(StdError::generic_err(format!(
+                "Sale multiplier has to be over 100% and up to {}%",
+                MAX_SALE_MULTIPLIER
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, food: u128) -> Option<u128> {
+        Some(food.checked_mul(self.rate_multiplier as u128)? / 100)
+    }
+
+    /// A sale name can come back later, the start tells the two apart. The name's length goes
+    /// first, so one sale's name and start can't spell out another's
+    fn key(&self) -> Vec<u8> {
+        // `validate` keeps names short enough for one byte
+        [
+            &[self.name.len() as u8][..],
+            self.name.as_bytes(),
+            &self.start.to_be_bytes(),
+        ]
+        .concat()
+    }
+}
+
+/// FOOD the buyer got at the sale rate so far
+pub fn sale_purchases<S: ReadonlyStorage>(
+    storage: &S,
+    sale: &SaleWindow,
+    buyer: &CanonicalAddr,
+) -> u128 {
+    ReadonlyPrefixedStorage::multilevel(&[PREFIX_SALE_PURCHASES, &sale.key()], storage)
+        .get(buyer.as_slice())
+        .map_or(0, |amount| {
+            let mut bytes = [0u8; 16];
+            bytes.copy_from_slice(&amount);
+            u128::from_be_bytes(bytes)
+        })
+}
+
+pub fn set_sale_purchases<S: Storage>(
+    storage: &mut S,
+    sale: &SaleWindow,
+    buyer: &CanonicalAddr,
+    amount: u128,
+) {
+    PrefixedStorage::multilevel(&[PREFIX_SALE_PURCHASES, &sale.key()], storage)
+        .set(buyer.as_slice(), &amount.to_be_bytes());
+}
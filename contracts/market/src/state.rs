@@ -1,11 +1,23 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{CanonicalAddr, Storage, HumanAddr};
-use cosmwasm_storage::{singleton, singleton_read, ReadonlySingleton, Singleton};
+use cosmwasm_std::{CanonicalAddr, Coin, HumanAddr, ReadonlyStorage, Storage, Uint128};
+use cosmwasm_storage::{
+    singleton, singleton_read, PrefixedStorage, ReadonlyPrefixedStorage, ReadonlySingleton,
+    Singleton,
+};
 
+use crate::curve::Pricing;
+use crate::msg::{PaymentOption, PaymentToken, TokenBalance};
+use crate::sales::SaleWindow;
+use shared::viewing_key::ViewingKey;
 
 pub static CONFIG_KEY: &[u8] = b"config";
+pub static TREASURY_KEY: &[u8] = b"treasury";
+pub static TOKEN_TREASURY_KEY: &[u8] = b"token_treasury";
+pub const PREFIX_VIEW_KEY: &[u8] = b"viewingkey";
+pub const PREFIX_REVOKED_PERMITS: &str = "revoked_permits";
+pub const PREFIX_CURVE_BALANCES: &[u8] = b"curve_balances";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct TokenInfo {
@@ -16,8 +28,23 @@ pub struct TokenInfo {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
     pub owner: CanonicalAddr,
+    /// Permits are signed for this address, and queries don't know it otherwise
+    pub contract_address: HumanAddr,
+    pub prng_seed: Vec<u8>,
     pub token: TokenInfo,
-    pub exchange_rate: u64
+    /// Native coins the market takes for FOOD, each at its own rate
+    pub payment_options: Vec<PaymentOption>,
+    /// SNIP-20 tokens the market takes for FOOD
+    pub payment_tokens: Vec<PaymentToken>,
+    /// A bonding curve replaces the rates of the payment options and tokens
+    pub pricing: Pricing,
+    /// FOOD bought on the curve and not sold back
+    pub curve_supply: Uint128,
+    /// Sales that haven't ended yet, as of the last change
+    pub sales: Vec<SaleWindow>,
+    /// FOOD a referrer gets, in percent of the FOOD their referrals buy
+    pub referral_percent: u64,
+    pub rewarders: Vec<CanonicalAddr>,
 }
 
 pub fn config<S: Storage>(storage: &mut S) -> Singleton<S, State> {
@@ -27,3 +54,48 @@ pub fn config<S: Storage>(storage: &mut S) -> Singleton<S, State> {
 pub fn config_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, State> {
     singleton_read(storage, CONFIG_KEY)
 }
+
+/// Payments the owner hasn't withdrawn yet, one coin per denom
+pub fn treasury<S: Storage>(storage: &mut S) -> Singleton<S, Vec<Coin>> {
+    singleton(storage, TREASURY_KEY)
+}
+
+pub fn treasury_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Vec<Coin>> {
+    singleton_read(storage, TREASURY_KEY)
+}
+
+/// FOOD minted to the address on the curve and not sold back yet, the most it can sell back.
+/// Bonuses, rewards and FOOD bought at fixed rates never paid into the curve
+pub fn curve_balance<S: ReadonlyStorage>(storage: &S, address: &CanonicalAddr) -> u128 {
+    ReadonlyPrefixedStorage::new(PREFIX_CURVE_BALANCES, storage)
+        .get(address.as_slice())
+        .map_or(0, |amount| {
+            let mut bytes = [0u8; 16];
+            bytes.copy_from_slice(&amount);
+            u128::from_be_bytes(bytes)
+        })
+}
+
+pub fn set_curve_balance<S: Storage>(storage: &mut S, address: &CanonicalAddr, amount: u128) {
+    PrefixedStorage::new(PREFIX_CURVE_BALANCES, storage)
+        .set(address.as_slice(), &amount.to_be_bytes());
+}
+
+/// Like the treasury, for SNIP-20 payments
+pub fn token_treasury<S: Storage>(storage: &mut S) -> Singleton<S, Vec<TokenBalance>> {
+    singleton(storage, TOKEN_TREASURY_KEY)
+}
+
+pub fn token_treasury_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Vec<TokenBalance>> {
+    singleton_read(storage, TOKEN_TREASURY_KEY)
+}
+
+pub fn write_viewing_key<S: Storage>(store: &mut S, owner: &CanonicalAddr, key: &ViewingKey) {
+    let mut key_store = PrefixedStorage::new(PREFIX_VIEW_KEY, store);
+    key_store.set(owner.as_slice(), &key.to_hashed());
+}
+
+pub fn read_viewing_key<S: ReadonlyStorage>(store: &S, owner: &CanonicalAddr) -> Option<Vec<u8>> {
+    let key_store = ReadonlyPrefixedStorage::new(PREFIX_VIEW_KEY, store);
+    key_store.get(owner.as_slice())
+}
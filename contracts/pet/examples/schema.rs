@@ -2,7 +2,7 @@ use std::env::current_dir;
 use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
-use pet::{msg::{InitMsg, HandleMsg, QueryMsg}, state::State};
+use pet::{msg::{InitMsg, HandleMsg, QueryMsg, QueryAnswer}, state::State};
 
 
 fn main() {
@@ -14,5 +14,6 @@ fn main() {
     export_schema(&schema_for!(InitMsg), &out_dir);
     export_schema(&schema_for!(HandleMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(QueryAnswer), &out_dir);
     export_schema(&schema_for!(State), &out_dir);
 }
@@ -0,0 +1,79 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::common::SECONDS_PER_DAY;
+use crate::state::State;
+
+const HUNDRED_MEALS: u64 = 100;
+const LONG_STREAK: u32 = 50;
+
+/// Achievements are kept in `State::achievements` as a bitset, indexed by the variant's position
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Achievement {
+    FirstMeal,
+    HundredMeals,
+    SurvivedWeek,
+    SurvivedMonth,
+    LongStreak,
+    Revived,
+}
+
+pub const ALL_ACHIEVEMENTS: [Achievement; 6] = [
+    Achievement::FirstMeal,
+    Achievement::HundredMeals,
+    Achievement::SurvivedWeek,
+    Achievement::SurvivedMonth,
+    Achievement::LongStreak,
+    Achievement::Revived,
+];
+
+impl Achievement {
+    pub fn bit(self) -> u32 {
+        1 << (self as u32)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Achievement::FirstMeal => "first_meal",
+            Achievement::HundredMeals => "hundred_meals",
+            Achievement::SurvivedWeek => "survived_week",
+            Achievement::SurvivedMonth => "survived_month",
+            Achievement::LongStreak => "long_streak",
+            Achievement::Revived => "revived",
+        }
+    }
+
+    fn is_earned(self, state: &State, current_time: u64) -> bool {
+        let lifetime = current_time.saturating_sub(state.alive_since);
+        match self {
+            Achievement::FirstMeal => state.feed_count >= 1,
+            Achievement::HundredMeals => state.feed_count >= HUNDRED_MEALS,
+            Achievement::SurvivedWeek => lifetime >= 7 * SECONDS_PER_DAY,
+            Achievement::SurvivedMonth => lifetime >= 30 * SECONDS_PER_DAY,
+            Achievement::LongStreak => state.best_streak >= LONG_STREAK,
+            Achievement::Revived => state.revive_count >= 1,
+        }
+    }
+}
+
+/// Marks every achievement the pet has earned so far, and returns the ones that are new
+pub fn unlock_achievements(state: &mut State, current_time: u64) -> Vec<Achievement> {
+    let mut unlocked = vec![];
+    for achievement in ALL_ACHIEVEMENTS {
+        if state.achievements & achievement.bit() == 0 && achievement.is_earned(state, current_time)
+        {
+            state.achievements |= achievement.bit();
+            unlocked.push(achievement);
+        }
+    }
+    unlocked
+}
+
+pub fn achievements_from_bits(bits: u32) -> Vec<Achievement> {
+    ALL_ACHIEVEMENTS
+        .iter()
+        .copied()
+        .filter(|achievement| bits & achievement.bit() != 0)
+        .collect()
+}
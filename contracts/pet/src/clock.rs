@@ -0,0 +1,56 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Rate at which the pet's clock runs normally, in percent of real time
+pub const NORMAL_RATE: u64 = 100;
+
+/// Period of real time in which the pet's clock runs at `rate` percent
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct RateWindow {
+    pub start: u64,
+    pub end: u64,
+    pub rate: u64,
+}
+
+impl RateWindow {
+    fn covers(&self, start: u64, end: u64) -> bool {
+        self.start <= start && end <= self.end
+    }
+}
+
+/// Seconds that passed for the pet between `from` and `to`. Rates of overlapping windows
+/// multiply, so a window at 0% stops the clock whatever else is going on
+pub fn pet_seconds(from: u64, to: u64, windows: &[RateWindow]) -> u64 {
+    if to <= from {
+        return 0;
+    }
+    let mut points = vec![from, to];
+    for window in windows {
+        for point in [window.start, window.end] {
+            if from < point && point < to {
+                points.push(point);
+            }
+        }
+    }
+    points.sort_unstable();
+    points.dedup();
+
+    let seconds: u128 = points
+        .windows(2)
+        .map(|segment| {
+            let (start, end) = (segment[0], segment[1]);
+            let rate = windows
+                .iter()
+                .filter(|window| window.covers(start, end))
+                .fold(NORMAL_RATE as u128, |rate, window| {
+                    rate * window.rate as u128 / NORMAL_RATE as u128
+                });
+            (end - start) as u128 * rate / NORMAL_RATE as u128
+        })
+        .sum();
+    if seconds > u64::MAX as u128 {
+        u64::MAX
+    } else {
+        seconds as u64
+    }
+}
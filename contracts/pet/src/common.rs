@@ -1,3 +1,57 @@
+use cosmwasm_std::{StdError, StdResult};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-    // pub struct Hours(u8);
-    // pub struct Minutes(u8);
+pub const SECONDS_PER_MINUTE: u64 = 60;
+pub const SECONDS_PER_HOUR: u64 = 60 * SECONDS_PER_MINUTE;
+pub const SECONDS_PER_DAY: u64 = 24 * SECONDS_PER_HOUR;
+
+/// Length of a time period, in the unit that is most convenient for the sender
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Duration {
+    Seconds(u64),
+    Minutes(u64),
+    Hours(u64),
+    Days(u64),
+}
+
+impl Duration {
+    pub fn to_seconds(self) -> StdResult<Seconds> {
+        let (amount, unit) = match self {
+            Duration::Seconds(seconds) => (seconds, 1),
+            Duration::Minutes(minutes) => (minutes, SECONDS_PER_MINUTE),
+            Duration::Hours(hours) => (hours, SECONDS_PER_HOUR),
+            Duration::Days(days) => (days, SECONDS_PER_DAY),
+        };
+        amount
+            .checked_mul(unit)
+            .map(Seconds)
+            .ok_or_else(|| StdError::generic_err("Duration is too long"))
+    }
+}
+
+/// Time period in seconds, which is how durations are kept in storage
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, JsonSchema,
+)]
+pub struct Seconds(pub u64);
+
+impl Seconds {
+    pub fn u64(self) -> u64 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Seconds) -> StdResult<Seconds> {
+        self.0
+            .checked_add(other.0)
+            .map(Seconds)
+            .ok_or_else(|| StdError::generic_err("Time period overflow"))
+    }
+}
+
+/// Moves a point in time (seconds since epoch) forward, failing instead of overflowing
+pub fn time_after(time: u64, period: Seconds) -> StdResult<u64> {
+    time.checked_add(period.0)
+        .ok_or_else(|| StdError::generic_err("Time overflow"))
+}
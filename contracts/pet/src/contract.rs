@@ -1,39 +1,151 @@
 use secret_toolkit::snip20;
-use std::ops::Add;
-use std::time::Duration;
+use secret_toolkit::utils::HandleCallback;
 
 use cosmwasm_std::{
-    log, to_binary, Api, Binary, Env, Extern, HandleResponse, HumanAddr, InitResponse, Querier,
-    StdError, StdResult, Storage, Uint128,
+    from_binary, log, to_binary, Api, Binary, CosmosMsg, Env, Extern, HandleResponse, HumanAddr,
+    InitResponse, LogAttribute, Querier, StdError, StdResult, Storage, Uint128,
 };
 
-use crate::msg::{HandleMsg, InitMsg, Minutes, QueryMsg};
-use crate::state::{pet, pet_read, State, TokenInfo};
+use crate::achievements::{achievements_from_bits, unlock_achievements, Achievement};
+use crate::clock::{pet_seconds, RateWindow};
+use crate::common::{time_after, Duration, Seconds, SECONDS_PER_DAY, SECONDS_PER_HOUR};
+use crate::graveyard::{append_memorial, get_hall_of_fame, get_memorials, CauseOfDeath, Memorial};
+use crate::history::{append_event, get_events, EventKind};
+use crate::msg::{
+    AchievementBonus, CaretakerInfo, CureCost, Direction, FactoryHandleMsg, FeedingMode,
+    HandleAnswer, HandleMsg, InitMsg, MarketHandleMsg, MedicineToken, Permission, PlayInfo,
+    QueryAnswer, QueryMsg, ReceiveMsg, ResponseStatus::Success,
+};
+use crate::profile::{validate_avatar, validate_bio, Profile, ProfileField};
+use crate::rules::{ranked_difficulty, Difficulty};
+use crate::state::{
+    caretakers, caretakers_read, friends, friends_read, pet, pet_read, read_viewing_key,
+    world_events, world_events_read, write_viewing_key, Adoption, Caretaker, FactoryInfo, Illness,
+    PendingPlay, State, TokenInfo, Vacation,
+};
+use crate::stats::{Stats, MAX_POINTS};
+use crate::world::{load_world_events, rate_window};
+use shared::name::validate_name;
+use shared::rand::{sha_256, Prng};
+use shared::viewing_key::{ViewingKey, VIEWING_KEY_SIZE};
+use shared::world::{cancel_world_event, schedule_world_event, world_cost, WorldEvent};
 
 const BLOCK_SIZE: usize = 256;
-const DEFAULT_SATIATED_TIME: Minutes = 180;
-const DEFAULT_STARVING_TIME: Minutes = 60;
-const TOKENS_PER_FEEDING: u16 = 100;
+const MIN_INTERVAL: Seconds = Seconds(60);
+const MAX_INTERVAL: Seconds = Seconds(30 * SECONDS_PER_DAY);
+const TOKENS_PER_REVIVAL: u16 = 500;
+const VACATION_TOKENS_PER_HOUR: u16 = 10;
+const VACATION_ALLOWANCE: Seconds = Seconds(14 * SECONDS_PER_DAY);
+const VACATION_YEAR: u64 = 365 * SECONDS_PER_DAY;
+const TOKENS_PER_MEDICINE: u16 = 200;
+/// Percent of the normal rate at which a sick pet's clock runs
+const SICK_RATE: u64 = 200;
+const LONG_HUNGER_SICKNESS_RISK: u64 = 10;
+const DIRTY_SICKNESS_RISK: u64 = 10;
+/// Cleanliness points every meal costs
+const FEEDING_MESS: i64 = 15;
+const BIRTH_WEIGHT: u64 = 1000;
+const MIN_WEIGHT: u64 = 500;
+const TOKENS_PER_PLAY: u16 = 10;
+/// Blocks after the commit in which a game can be revealed, after that the stake can be reclaimed
+const PLAY_REVEAL_BLOCKS: u64 = 100;
+const PLAY_WON_HAPPINESS: i64 = 20;
+const PLAY_LOST_HAPPINESS: i64 = 5;
+/// Grams lost playing, losing means chasing the pet for longer
+const PLAY_WON_WEIGHT_LOSS: u64 = 10;
+const PLAY_LOST_WEIGHT_LOSS: u64 = 20;
+const OBESE_WEIGHT: u64 = 1500;
+const OVERFEEDING_WEIGHT_GAIN: u64 = 100;
+const OVERFEEDING_HEALTH_LOSS: u64 = 10;
+const MEAL_HEALTH: u64 = 5;
+const OBESE_SICKNESS_RISK: u64 = 10;
+/// Every this many points of lost health add a percent of sickness risk
+const HEALTH_POINTS_PER_RISK: u64 = 5;
+const TOKENS_PER_RENAME: u16 = 50;
+const ADOPTION_FEE: u16 = 300;
+const ADOPTION_DEADLINE: Seconds = Seconds(7 * SECONDS_PER_DAY);
+/// Happiness points both pets get from a visit
+const VISIT_HAPPINESS: i64 = 10;
+const VISIT_COOLDOWN: Seconds = Seconds(SECONDS_PER_HOUR);
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     msg: InitMsg,
 ) -> StdResult<InitResponse> {
+    validate_name(&msg.name)?;
+    let preset = msg.difficulty.unwrap_or(Difficulty::Normal);
+    let (preset_satiated, preset_starving, preset_rules) =
+        match (preset.intervals(), preset.rules()) {
+            (Some((satiated, starving)), Some(rules)) => (satiated, starving, rules),
+            _ => {
+                return Err(StdError::generic_err(
+                    "Pick a preset difficulty, overriding its rules makes it custom",
+                ))
+            }
+        };
+    let satiated_interval = match msg.satiated_interval {
+        Some(interval) => interval.to_seconds()?,
+        None => preset_satiated,
+    };
+    let starving_interval = match msg.starving_interval {
+        Some(interval) => interval.to_seconds()?,
+        None => preset_starving,
+    };
+    validate_intervals(satiated_interval, starving_interval)?;
+    let rules = preset_rules.with_overrides(&msg.overrides.unwrap_or_default())?;
+    let difficulty = ranked_difficulty(preset, satiated_interval, starving_interval, &rules);
+
+    let owner = msg.owner.unwrap_or_else(|| env.message.sender.clone());
+    let admin = msg.admin.unwrap_or_else(|| env.message.sender.clone());
     let state = State {
+        profile: Profile::new(msg.name),
         last_feed_time: env.block.time,
-        satiated_interval: msg.satiated_interval.unwrap_or(DEFAULT_SATIATED_TIME),
-        starving_interval: msg.starving_interval.unwrap_or(DEFAULT_STARVING_TIME),
-        owner: deps.api.canonical_address(&env.message.sender)?,
+        satiated_interval,
+        starving_interval,
+        difficulty,
+        rules,
+        owner: deps.api.canonical_address(&owner)?,
+        admin: deps.api.canonical_address(&admin)?,
         token_info: TokenInfo {
             address: HumanAddr(msg.token_address.clone()),
             code_hash: msg.token_code_hash.clone(),
         },
+        factory: msg.factory.as_ref().map(|factory| FactoryInfo {
+            code_hash: factory.code_hash.clone(),
+            address: factory.address.clone(),
+            pet_id: factory.pet_id,
+        }),
+        alive_since: env.block.time,
+        feed_count: 0,
+        current_streak: 0,
+        best_streak: 0,
+        revive_count: 0,
+        achievements: 0,
+        achievement_bonus: None,
+        prng_seed: sha_256(&msg.prng_seed.0).to_vec(),
+        vacations: vec![],
+        vacation_year_start: env.block.time,
+        vacation_used: Seconds::default(),
+        illnesses: vec![],
+        roll_count: 0,
+        medicine: None,
+        stats: Stats::new(env.block.time),
+        weight: BIRTH_WEIGHT,
+        play: None,
+        health: MAX_POINTS,
+        feeding_mode: FeedingMode::Strict,
+        food_eaten: Uint128(0),
+        buried: false,
+        adoptions: vec![],
+        last_visit: None,
+        world_events: vec![],
     };
 
     pet(&mut deps.storage).save(&state)?;
+    caretakers(&mut deps.storage).save(&vec![])?;
+    friends(&mut deps.storage).save(&vec![])?;
 
-    println!("Pet was born and fed, thanks to {}", env.message.sender);
     let pet_contract_hash = &env.contract_code_hash;
     let callback = snip20::register_receive_msg(
         pet_contract_hash.clone(),
@@ -42,9 +154,19 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
         msg.token_code_hash.clone(),
         HumanAddr(msg.token_address.clone()),
     )?;
+    let mut messages = vec![callback];
+
+    // let the factory know where the pet lives
+    if let Some(factory) = msg.factory {
+        let register = FactoryHandleMsg::RegisterPet {
+            pet_id: factory.pet_id,
+            password: factory.password,
+        };
+        messages.push(register.to_cosmos_msg(factory.code_hash, factory.address, None)?);
+    }
 
     Ok(InitResponse {
-        messages: vec![callback],
+        messages,
         log: vec![],
     })
 }
@@ -56,153 +178,3929 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<HandleResponse> {
     match msg {
         HandleMsg::Receive {
-            sender,
+            from, amount, msg, ..
+        } => try_receive(deps, &env, from, amount, msg),
+        HandleMsg::UpdateIntervals {
+            satiated_interval,
+            starving_interval,
+        } => try_update_intervals(deps, &env, satiated_interval, starving_interval),
+        HandleMsg::SetAchievementBonus { bonus } => try_set_achievement_bonus(deps, &env, bonus),
+        HandleMsg::AddCaretaker {
+            address,
+            expires_at,
+            permissions,
+        } => try_add_caretaker(deps, &env, address, expires_at, permissions),
+        HandleMsg::RemoveCaretaker { address } => try_remove_caretaker(deps, &env, address),
+        HandleMsg::AddFriend { pet } => try_add_friend(deps, &env, pet),
+        HandleMsg::RemoveFriend { pet } => try_remove_friend(deps, &env, pet),
+        HandleMsg::Visit { pet, code_hash } => try_visit(deps, &env, pet, code_hash),
+        HandleMsg::ReceiveVisit { guest } => try_receive_visit(deps, &env, guest),
+        HandleMsg::EndVacation {} => try_end_vacation(deps, &env),
+        HandleMsg::Clean {} => try_clean(deps, &env),
+        HandleMsg::RevealPlay { guess, salt } => try_reveal_play(deps, &env, guess, salt),
+        HandleMsg::ReclaimPlay {} => try_reclaim_play(deps, &env),
+        HandleMsg::SetFeedingMode { mode } => try_set_feeding_mode(deps, &env, mode),
+        HandleMsg::SetProfile {
+            bio,
+            avatar,
+            public,
+        } => try_set_profile(deps, &env, bio, avatar, public),
+        HandleMsg::Abandon {} => try_abandon(deps, &env),
+        HandleMsg::Tick {} => try_tick(deps, &env),
+        HandleMsg::SetMedicineToken { medicine } => try_set_medicine_token(deps, &env, medicine),
+        HandleMsg::ScheduleWorldEvent { event } => try_schedule_world_event(deps, &env, event),
+        HandleMsg::CancelWorldEvent { name } => try_cancel_world_event(deps, &env, name),
+        HandleMsg::CreateViewingKey { entropy, .. } => try_create_key(deps, env, entropy),
+        HandleMsg::SetViewingKey { key, .. } => try_set_key(deps, env, key),
+    }
+}
+
+/// `from` is the owner of the tokens, so that's who has to be allowed to take care of the pet
+pub fn try_receive<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    from: HumanAddr,
+    amount: Uint128,
+    msg: Option<Binary>,
+) -> StdResult<HandleResponse> {
+    let mut state = pet_read(&deps.storage).load()?;
+    let medicine = state
+        .medicine
+        .clone()
+        .filter(|medicine| medicine.address == env.message.sender);
+    if env.message.sender != state.token_info.address && medicine.is_none() {
+        return Err(StdError::generic_err(
+            "Only FOOD or medicine tokens can be sent to the pet",
+        ));
+    }
+    let msg = match msg {
+        Some(msg) => from_binary(&msg)?,
+        None => ReceiveMsg::Feed {},
+    };
+    if medicine.is_some() && msg != (ReceiveMsg::Medicine {}) {
+        return Err(StdError::generic_err(
+            "Medicine tokens can only be used to cure the pet",
+        ));
+    }
+    let permission = match msg {
+        ReceiveMsg::Feed {} => Some(Permission::Feed),
+        ReceiveMsg::Revive {} => Some(Permission::Revive),
+        ReceiveMsg::StartVacation { .. } => None,
+        ReceiveMsg::Medicine {} => Some(Permission::Medicine),
+        ReceiveMsg::Play { .. } => Some(Permission::Play),
+        ReceiveMsg::Rename { .. } => None,
+        // anyone can adopt a pet from the pool, or give it food
+        ReceiveMsg::Adopt {} => return try_adopt(deps, env, from, amount),
+        ReceiveMsg::GiftFood {} => return try_feed(deps, env, from, amount, true),
+    };
+    authorize(deps, &state, &from, permission, env.block.time)?;
+    match msg {
+        ReceiveMsg::Feed {} => try_feed(deps, env, from, amount, false),
+        ReceiveMsg::Revive {} => try_revive(deps, env, from, amount),
+        ReceiveMsg::StartVacation { duration } => {
+            try_start_vacation(deps, env, from, amount, duration)
+        }
+        ReceiveMsg::Medicine {} => {
+            state.world_events = load_world_events(&deps.storage, &deps.querier, &state.factory)?;
+            let cost = cure_costs(&state, env.block.time)
+                .into_iter()
+                .find(|cost| cost.token == env.message.sender)
+                .ok_or_else(|| {
+                    StdError::generic_err("Only FOOD or medicine tokens can be sent to the pet")
+                })?;
+            try_medicine(deps, env, from, amount, cost)
+        }
+        ReceiveMsg::Play { commitment } => try_play(deps, env, from, amount, commitment),
+        ReceiveMsg::Rename { name } => try_rename(deps, env, from, amount, name),
+        ReceiveMsg::Adopt {} | ReceiveMsg::GiftFood {} => {
+            unreachable!("adoption and gifts don't need authorization")
+        }
+    }
+}
+
+/// Gifts of food from strangers are only eaten by a hungry pet, whatever the feeding mode
+pub fn try_feed<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    from: HumanAddr,
+    amount: Uint128,
+    gift: bool,
+) -> StdResult<HandleResponse> {
+    let time = env.block.time;
+    let mut state = load_state(deps)?;
+    if is_dead(&state, time)? {
+        // too late for the meal, but the pet gets its memorial if nobody noticed it died yet
+        let mut res = bury(deps, &mut state, time)?;
+        pet(&mut deps.storage).save(&state)?;
+        res.messages.push(snip20::transfer_msg(
             from,
             amount,
-            ..
-        } => try_feed(&mut deps.storage, &env, sender, amount),
+            None,
+            BLOCK_SIZE,
+            state.token_info.code_hash.clone(),
+            state.token_info.address.clone(),
+        )?);
+        return Ok(res);
+    }
+    if on_vacation(&state, time).is_some() {
+        return Err(StdError::generic_err("Pet is on vacation"));
+    }
+    if !is_hungry(&state, time)? {
+        return match state.feeding_mode {
+            FeedingMode::Soft if !gift => try_overfeed(deps, env, from, amount, state),
+            _ => Err(StdError::generic_err("Pet is not hungry")),
+        };
+    }
+    if amount < world_cost(state.rules.feeding_cost, &state.world_events, time) {
+        return Err(StdError::generic_err(
+            "You need more tokens to feed the pet",
+        ));
+    }
+    let risk = sickness_risk(&state, time)?;
+    let fell_sick = roll_sickness(&mut state, env, risk);
+    touch_stats(&mut state, time);
+    state.stats.add_cleanliness(-FEEDING_MESS);
+    state.health = (state.health + MEAL_HEALTH).min(MAX_POINTS);
+    state.food_eaten = add_food(state.food_eaten, amount);
+    // the pet is hungry but alive, so this meal extends the streak
+    state.last_feed_time = time;
+    forget_past_windows(&mut state, time);
+    state.feed_count += 1;
+    state.current_streak += 1;
+    state.best_streak = state.best_streak.max(state.current_streak);
+    let unlocked = unlock_achievements(&mut state, time);
+    pet(&mut deps.storage).save(&state)?;
+    let kind = if gift {
+        EventKind::GiftedFood
+    } else {
+        EventKind::Fed
+    };
+    append_event(&mut deps.storage, time, kind, Some(from))?;
+    if fell_sick {
+        append_event(&mut deps.storage, time, EventKind::FellSick, None)?;
+    }
+
+    let mut messages = vec![burn_msg(&state.token_info, amount)?];
+    messages.extend(achievement_rewards(&deps.api, &state, &unlocked)?);
+    let mut logs = vec![
+        log("current_time", time),
+        log("streak", state.current_streak),
+    ];
+    if fell_sick {
+        logs.push(log("fell_sick", time));
+    }
+    logs.extend(achievement_logs(&unlocked));
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// A satiated pet eats anyway in the soft feeding mode. It doesn't get any less hungry,
+/// but it puts on weight and loses health
+fn try_overfeed<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    from: HumanAddr,
+    amount: Uint128,
+    mut state: State,
+) -> StdResult<HandleResponse> {
+    let time = env.block.time;
+    if amount < world_cost(state.rules.feeding_cost, &state.world_events, time) {
+        return Err(StdError::generic_err(
+            "You need more tokens to feed the pet",
+        ));
+    }
+    let risk = sickness_risk(&state, time)?;
+    let fell_sick = roll_sickness(&mut state, env, risk);
+    touch_stats(&mut state, time);
+    state.stats.add_cleanliness(-FEEDING_MESS);
+    state.weight = state.weight.saturating_add(OVERFEEDING_WEIGHT_GAIN);
+    state.health = state.health.saturating_sub(OVERFEEDING_HEALTH_LOSS);
+    state.food_eaten = add_food(state.food_eaten, amount);
+    pet(&mut deps.storage).save(&state)?;
+    append_event(&mut deps.storage, time, EventKind::Overfed, Some(from))?;
+    if fell_sick {
+        append_event(&mut deps.storage, time, EventKind::FellSick, None)?;
+    }
+
+    let mut logs = vec![log("overfed", time), log("weight", state.weight)];
+    if fell_sick {
+        logs.push(log("fell_sick", time));
+    }
+    Ok(HandleResponse {
+        messages: vec![burn_msg(&state.token_info, amount)?],
+        log: logs,
+        data: None,
+    })
+}
+
+pub fn try_set_feeding_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    mode: FeedingMode,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    pet(&mut deps.storage).update(|mut state| {
+        if state.owner != sender {
+            return Err(StdError::unauthorized());
+        }
+        state.feeding_mode = mode;
+        Ok(state)
+    })?;
+    Ok(HandleResponse::default())
+}
+
+pub fn try_set_profile<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    bio: Option<String>,
+    avatar: Option<String>,
+    public: Vec<ProfileField>,
+) -> StdResult<HandleResponse> {
+    if let Some(bio) = &bio {
+        validate_bio(bio)?;
     }
+    if let Some(avatar) = &avatar {
+        validate_avatar(avatar)?;
+    }
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    pet(&mut deps.storage).update(|mut state| {
+        if state.owner != sender {
+            return Err(StdError::unauthorized());
+        }
+        state.profile.bio = bio;
+        state.profile.avatar = avatar;
+        state.profile.public = public;
+        Ok(state)
+    })?;
+    Ok(HandleResponse::default())
 }
 
-pub fn try_feed<S: Storage>(
-    storage: &mut S,
+/// The factory's index is kept in sync, memorials keep the name the pet died with
+pub fn try_rename<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
     env: &Env,
-    sender: HumanAddr,
+    from: HumanAddr,
     amount: Uint128,
+    name: String,
 ) -> StdResult<HandleResponse> {
     let time = env.block.time;
-    let state = &pet_read(storage).load()?;
-    if is_dead(state, time) {
+    let mut state = load_state(deps)?;
+    if is_dead(&state, time)? {
         return Err(StdError::generic_err("Pet is dead :("));
     }
-    if !is_hungry(state, time) {
-        return Err(StdError::generic_err("Pet is not hungry"));
+    validate_name(&name)?;
+    let price = world_cost(
+        Uint128(TOKENS_PER_RENAME as u128),
+        &state.world_events,
+        time,
+    );
+    if amount < price {
+        return Err(StdError::generic_err(format!(
+            "You need {} tokens to rename the pet",
+            price
+        )));
+    }
+    let risk = sickness_risk(&state, time)?;
+    let fell_sick = roll_sickness(&mut state, env, risk);
+    state.profile.name = name.clone();
+    pet(&mut deps.storage).save(&state)?;
+    append_event(
+        &mut deps.storage,
+        time,
+        EventKind::Renamed { name: name.clone() },
+        Some(from),
+    )?;
+    if fell_sick {
+        append_event(&mut deps.storage, time, EventKind::FellSick, None)?;
+    }
+
+    let mut messages = vec![burn_msg(&state.token_info, amount)?];
+    messages.extend(notify_factory(&state, |pet_id| {
+        FactoryHandleMsg::RenamePet {
+            pet_id,
+            name: name.clone(),
+        }
+    })?);
+    let mut logs = vec![log("name", name)];
+    if fell_sick {
+        logs.push(log("fell_sick", time));
+    }
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Brings a dead pet back with a full stomach, the streak starts over
+pub fn try_revive<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    from: HumanAddr,
+    amount: Uint128,
+) -> StdResult<HandleResponse> {
+    let time = env.block.time;
+    let mut state = load_state(deps)?;
+    if !state.rules.revive_allowed {
+        return Err(StdError::generic_err("This pet can't be revived"));
+    }
+    if !is_dead(&state, time)? {
+        return Err(StdError::generic_err("Pet is still alive"));
     }
-    if amount < Uint128(TOKENS_PER_FEEDING as u128) {
+    let price = world_cost(
+        Uint128(TOKENS_PER_REVIVAL as u128),
+        &state.world_events,
+        time,
+    );
+    if amount < price {
         return Err(StdError::generic_err(
-            "You need more tokens to feed the pet",
+            "You need more tokens to revive the pet",
         ));
     }
-    let _ = pet(storage).update(|mut state| {
-        state.last_feed_time = time;
-        Ok(state)
+    let burial = bury(deps, &mut state, time)?;
+    // whatever the pet had, it's gone now
+    cure(&mut state, time);
+    state.last_feed_time = time;
+    forget_past_windows(&mut state, time);
+    state.stats = Stats::new(time);
+    state.weight = BIRTH_WEIGHT;
+    state.health = MAX_POINTS;
+    state.food_eaten = Uint128(0);
+    state.buried = false;
+    state.alive_since = time;
+    state.current_streak = 0;
+    state.revive_count += 1;
+    let unlocked = unlock_achievements(&mut state, time);
+    pet(&mut deps.storage).save(&state)?;
+    append_event(&mut deps.storage, time, EventKind::Revived, Some(from))?;
+
+    let mut messages = burial.messages;
+    messages.push(burn_msg(&state.token_info, amount)?);
+    messages.extend(achievement_rewards(&deps.api, &state, &unlocked)?);
+    let mut logs = burial.log;
+    logs.push(log("revived_at", time));
+    logs.extend(achievement_logs(&unlocked));
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Lets keepers record a death without having to feed the pet
+pub fn try_tick<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+) -> StdResult<HandleResponse> {
+    let time = env.block.time;
+    let mut state = load_state(deps)?;
+    let mut messages = vec![];
+    let mut logs = vec![];
+    if let Some(adoption) =
+        waiting_adoption(&mut state).filter(|adoption| adoption.deadline <= time)
+    {
+        // nobody wanted the pet, the shelter run by the admin takes it
+        adoption.ended_at = Some(adoption.deadline);
+        let deadline = adoption.deadline;
+        state.owner = state.admin.clone();
+        append_event(&mut deps.storage, deadline, EventKind::Sheltered, None)?;
+        let shelter = deps.api.human_address(&state.admin)?;
+        messages.extend(notify_factory(&state, |pet_id| {
+            FactoryHandleMsg::ChangeOwner {
+                pet_id,
+                owner: shelter,
+            }
+        })?);
+        logs.push(log("sheltered_at", deadline));
+    }
+    if is_dead(&state, time)? {
+        let burial = bury(deps, &mut state, time)?;
+        messages.extend(burial.messages);
+        logs.extend(burial.log);
+    }
+    pet(&mut deps.storage).save(&state)?;
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+pub fn try_abandon<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+) -> StdResult<HandleResponse> {
+    check_if_owner(deps, env)?;
+    let time = env.block.time;
+    let mut state = load_state(deps)?;
+    if is_dead(&state, time)? {
+        return Err(StdError::generic_err("Pet is dead :("));
+    }
+    let deadline = time_after(time, ADOPTION_DEADLINE)?;
+    state.adoptions.push(Adoption {
+        listed_at: time,
+        deadline,
+        ended_at: None,
+    });
+    // nobody can sign as the contract, so the pet has no owner until it's adopted
+    state.owner = deps.api.canonical_address(&env.contract.address)?;
+    pet(&mut deps.storage).save(&state)?;
+    caretakers(&mut deps.storage).save(&vec![])?;
+    append_event(
+        &mut deps.storage,
+        time,
+        EventKind::Abandoned,
+        Some(env.message.sender.clone()),
+    )?;
+
+    let messages = notify_factory(&state, |pet_id| FactoryHandleMsg::PutUpForAdoption {
+        pet_id,
+        born_at: state.alive_since,
+        deadline,
     })?;
-    let burn_msg = snip20::burn_msg(
-        amount,
-        None,
-        BLOCK_SIZE,
-        state.token_info.code_hash.clone(),
-        state.token_info.address.clone(),
+    Ok(HandleResponse {
+        messages: messages.into_iter().collect(),
+        log: vec![log("adoption_deadline", deadline)],
+        data: None,
+    })
+}
+
+pub fn try_adopt<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    from: HumanAddr,
+    amount: Uint128,
+) -> StdResult<HandleResponse> {
+    let time = env.block.time;
+    let mut state = load_state(deps)?;
+    let adoption = match waiting_adoption(&mut state) {
+        Some(adoption) if time < adoption.deadline => adoption,
+        _ => return Err(StdError::generic_err("Pet is not up for adoption")),
+    };
+    let price = world_cost(Uint128(ADOPTION_FEE as u128), &state.world_events, time);
+    if amount < price {
+        return Err(StdError::generic_err(format!(
+            "You need {} tokens to adopt the pet",
+            price
+        )));
+    }
+    // the clock starts again, it's the new owner's job to keep the pet fed
+    adoption.ended_at = Some(time);
+    state.owner = deps.api.canonical_address(&from)?;
+    pet(&mut deps.storage).save(&state)?;
+    append_event(
+        &mut deps.storage,
+        time,
+        EventKind::Adopted,
+        Some(from.clone()),
     )?;
+
+    let mut messages = vec![burn_msg(&state.token_info, amount)?];
+    messages.extend(notify_factory(&state, |pet_id| {
+        FactoryHandleMsg::ChangeOwner {
+            pet_id,
+            owner: from.clone(),
+        }
+    })?);
     Ok(HandleResponse {
-        messages: vec![burn_msg],
-        log: vec![log("current_time", time), log("is_hungry", time)],
+        messages,
+        log: vec![log("adopted_by", from)],
         data: None,
     })
 }
 
-fn to_seconds(interval: Minutes) -> u64 {
-    (interval * 60) as u64
+/// The stay in the adoption pool that hasn't ended yet, if any
+fn waiting_adoption(state: &mut State) -> Option<&mut Adoption> {
+    state
+        .adoptions
+        .iter_mut()
+        .find(|adoption| adoption.ended_at.is_none())
 }
 
-fn is_dead(state: &State, current_time: u64) -> bool {
-    state.last_feed_time + to_seconds(state.satiated_interval) + to_seconds(state.starving_interval)
-        < current_time
+/// The pet's state with its factory's world events, which the pet doesn't keep a copy of
+/// The pet with its world events, only needed where costs or decay are worked out
+fn load_state<S: Storage, A: Api, Q: Querier>(deps: &Extern<S, A, Q>) -> StdResult<State> {
+    let mut state = pet_read(&deps.storage).load()?;
+    state.world_events = load_world_events(&deps.storage, &deps.querier, &state.factory)?;
+    Ok(state)
 }
 
-fn is_hungry(state: &State, current_time: u64) -> bool {
-    state.last_feed_time + to_seconds(state.satiated_interval) < current_time
+/// Message for the factory the pet was created by, if any
+fn notify_factory<F>(state: &State, msg: F) -> StdResult<Option<CosmosMsg>>
+where
+    F: FnOnce(u64) -> FactoryHandleMsg,
+{
+    match &state.factory {
+        Some(factory) => Ok(Some(msg(factory.pet_id).to_cosmos_msg(
+            factory.code_hash.clone(),
+            factory.address.clone(),
+            None,
+        )?)),
+        None => Ok(None),
+    }
 }
 
-pub fn query<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
-    msg: QueryMsg,
-) -> StdResult<Binary> {
-    // match msg {
-    //     QueryMsg::CanEat {} => to_binary(&can_eat(deps)?),
-    //     QueryMsg::IsHungry {} => to_binary( &is_hungry(deps)?)
-    // }
-    Ok(to_binary("test")?)
+/// Writes the memorial of the current life, once, and lets the factory know.
+/// The pet has to be dead at `current_time`, the caller saves the state
+fn bury<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    state: &mut State,
+    current_time: u64,
+) -> StdResult<HandleResponse> {
+    if state.buried {
+        return Ok(HandleResponse::default());
+    }
+    let died_at = death_time(state, current_time)?;
+    let sick = state
+        .illnesses
+        .iter()
+        .any(|illness| illness.start < died_at && illness.end.map_or(true, |end| end >= died_at));
+    let cause = if sick {
+        CauseOfDeath::Sickness
+    } else {
+        CauseOfDeath::Starvation
+    };
+    let memorial = Memorial {
+        name: state.profile.name.clone(),
+        owner: deps.api.human_address(&state.owner)?,
+        born_at: state.alive_since,
+        died_at,
+        lifespan: died_at - state.alive_since,
+        food_eaten: state.food_eaten,
+        cause,
+        difficulty: state.difficulty,
+    };
+    append_memorial(&mut deps.storage, &memorial)?;
+    append_event(&mut deps.storage, died_at, EventKind::Died { cause }, None)?;
+    state.buried = true;
+
+    let messages = notify_factory(state, |pet_id| FactoryHandleMsg::BuryPet {
+        pet_id,
+        memorial,
+    })?;
+    Ok(HandleResponse {
+        messages: messages.into_iter().collect(),
+        log: vec![log("died_at", died_at), log("cause", cause.name())],
+        data: None,
+    })
 }
 
-// fn can_eat<S:Storage, A:Api, Q:Querier>(deps: &Extern<S, A, Q>) -> StdResult<bool> {
-//     Ok(true)
-// }
+/// First second at which the pet was dead, it has to be dead at `current_time`
+fn death_time(state: &State, current_time: u64) -> StdResult<u64> {
+    // hunger only ever grows, so the moment it became fatal can be searched for
+    let (mut alive, mut dead) = (state.last_feed_time, current_time);
+    while dead - alive > 1 {
+        let middle = alive + (dead - alive) / 2;
+        if is_dead(state, middle)? {
+            dead = middle;
+        } else {
+            alive = middle;
+        }
+    }
+    Ok(dead)
+}
 
-// fn is_hungry<S:Storage, A:Api, Q:Querier>(deps: &Extern<S, A, Q>) -> StdResult<bool> {
-//     let state = pet_read(&deps.storage).load()?;
-//     // deps.
-//     // state.last_feed_time
-//     Ok(true)
-// }
+fn add_food(eaten: Uint128, amount: Uint128) -> Uint128 {
+    Uint128(eaten.u128().saturating_add(amount.u128()))
+}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env};
-    use cosmwasm_std::{coins, from_binary, StdError};
-
-    // #[test]
-    // fn proper_initialization() {
-    //     let mut deps = mock_dependencies(20, &[]);
-
-    //     let msg = InitMsg { count: 17 };
-    //     let env = mock_env("creator", &coins(1000, "earth"));
-
-    //     // we can just call .unwrap() to assert this was a success
-    //     let res = init(&mut deps, env, msg).unwrap();
-    //     assert_eq!(0, res.messages.len());
-
-    //     // it worked, let's query the state
-    //     let res = query(&deps, QueryMsg::GetCount {}).unwrap();
-    //     let value: CountResponse = from_binary(&res).unwrap();
-    //     assert_eq!(17, value.count);
-    // }
-
-    // #[test]
-    // fn increment() {
-    //     let mut deps = mock_dependencies(20, &coins(2, "token"));
-
-    //     let msg = InitMsg { count: 17 };
-    //     let env = mock_env("creator", &coins(2, "token"));
-    //     let _res = init(&mut deps, env, msg).unwrap();
-
-    //     // anyone can increment
-    //     let env = mock_env("anyone", &coins(2, "token"));
-    //     let msg = HandleMsg::Increment {};
-    //     let _res = handle(&mut deps, env, msg).unwrap();
-
-    //     // should increase counter by 1
-    //     let res = query(&deps, QueryMsg::GetCount {}).unwrap();
-    //     let value: CountResponse = from_binary(&res).unwrap();
-    //     assert_eq!(18, value.count);
-    // }
-
-    // #[test]
-    // fn reset() {
-    //     let mut deps = mock_dependencies(20, &coins(2, "token"));
-
-    //     let msg = InitMsg { count: 17 };
-    //     let env = mock_env("creator", &coins(2, "token"));
-    //     let _res = init(&mut deps, env, msg).unwrap();
-
-    //     // not anyone can reset
-    //     let unauth_env = mock_env("anyone", &coins(2, "token"));
-    //     let msg = HandleMsg::Reset { count: 5 };
-    //     let res = handle(&mut deps, unauth_env, msg);
-    //     match res {
-    //         Err(StdError::Unauthorized { .. }) => {}
-    //         _ => panic!("Must return unauthorized error"),
-    //     }
-
-    //     // only the original creator can reset the counter
-    //     let auth_env = mock_env("creator", &coins(2, "token"));
-    //     let msg = HandleMsg::Reset { count: 5 };
-    //     let _res = handle(&mut deps, auth_env, msg).unwrap();
-
-    //     // should now be 5
-    //     let res = query(&deps, QueryMsg::GetCount {}).unwrap();
-    //     let value: CountResponse = from_binary(&res).unwrap();
-    //     assert_eq!(5, value.count);
-    // }
+/// Stops the pet's clock, so it gets neither hungry nor starves while the owner is away
+pub fn try_start_vacation<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    from: HumanAddr,
+    amount: Uint128,
+    duration: Duration,
+) -> StdResult<HandleResponse> {
+    let time = env.block.time;
+    let mut state = load_state(deps)?;
+    if is_dead(&state, time)? {
+        return Err(StdError::generic_err("Pet is dead :("));
+    }
+    if on_vacation(&state, time).is_some() {
+        return Err(StdError::generic_err("Pet is already on vacation"));
+    }
+    let duration = duration.to_seconds()?;
+    if duration.u64() == 0 {
+        return Err(StdError::generic_err("Vacation has to be longer than zero"));
+    }
+    let hours = (duration.u64() + SECONDS_PER_HOUR - 1) / SECONDS_PER_HOUR;
+    let price = world_cost(
+        Uint128(hours as u128 * VACATION_TOKENS_PER_HOUR as u128),
+        &state.world_events,
+        time,
+    );
+    if amount < price {
+        return Err(StdError::generic_err(format!(
+            "You need {} tokens for this vacation",
+            price
+        )));
+    }
+    start_vacation_year(&mut state, time);
+    let used = state.vacation_used.checked_add(duration)?;
+    if used > VACATION_ALLOWANCE {
+        return Err(StdError::generic_err(format!(
+            "Vacation allowance exceeded, {} seconds left this year",
+            VACATION_ALLOWANCE.u64() - state.vacation_used.u64()
+        )));
+    }
+    let end = time_after(time, duration)?;
+    state.vacation_used = used;
+    state.vacations.push(Vacation { start: time, end });
+    pet(&mut deps.storage).save(&state)?;
+    append_event(
+        &mut deps.storage,
+        time,
+        EventKind::VacationStarted { until: end },
+        Some(from),
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![burn_msg(&state.token_info, amount)?],
+        log: vec![log("vacation_until", end)],
+        data: None,
+    })
+}
+
+pub fn try_end_vacation<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+) -> StdResult<HandleResponse> {
+    check_if_owner(deps, env)?;
+    let time = env.block.time;
+    let mut state = pet_read(&deps.storage).load()?;
+    let vacation = match state
+        .vacations
+        .iter_mut()
+        .find(|vacation| vacation.is_active(time))
+    {
+        Some(vacation) => vacation,
+        None => return Err(StdError::generic_err("Pet is not on vacation")),
+    };
+    let unused = vacation.end - time;
+    vacation.end = time;
+    state.vacation_used = Seconds(state.vacation_used.u64().saturating_sub(unused));
+    pet(&mut deps.storage).save(&state)?;
+    append_event(
+        &mut deps.storage,
+        time,
+        EventKind::VacationEnded,
+        Some(env.message.sender.clone()),
+    )?;
+    Ok(HandleResponse::default())
+}
+
+/// Cleans up after the pet, restoring its cleanliness
+pub fn try_clean<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+) -> StdResult<HandleResponse> {
+    let time = env.block.time;
+    let mut state = load_state(deps)?;
+    authorize(
+        deps,
+        &state,
+        &env.message.sender,
+        Some(Permission::Clean),
+        time,
+    )?;
+    if is_dead(&state, time)? {
+        return Err(StdError::generic_err("Pet is dead :("));
+    }
+    if on_vacation(&state, time).is_some() {
+        return Err(StdError::generic_err("Pet is on vacation"));
+    }
+    let risk = sickness_risk(&state, time)?;
+    let fell_sick = roll_sickness(&mut state, env, risk);
+    touch_stats(&mut state, time);
+    state.stats.add_cleanliness(MAX_POINTS as i64);
+    pet(&mut deps.storage).save(&state)?;
+    append_event(
+        &mut deps.storage,
+        time,
+        EventKind::Cleaned,
+        Some(env.message.sender.clone()),
+    )?;
+    let mut logs = vec![log("cleaned_at", time)];
+    if fell_sick {
+        append_event(&mut deps.storage, time, EventKind::FellSick, None)?;
+        logs.push(log("fell_sick", time));
+    }
+    Ok(HandleResponse {
+        messages: vec![],
+        log: logs,
+        data: None,
+    })
+}
+
+/// Commits to a guess, the pet's move is only decided by the block it's revealed in
+pub fn try_play<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    from: HumanAddr,
+    amount: Uint128,
+    commitment: Binary,
+) -> StdResult<HandleResponse> {
+    let time = env.block.time;
+    let mut state = load_state(deps)?;
+    if is_dead(&state, time)? {
+        return Err(StdError::generic_err("Pet is dead :("));
+    }
+    if on_vacation(&state, time).is_some() {
+        return Err(StdError::generic_err("Pet is on vacation"));
+    }
+    if state.play.is_some() {
+        return Err(StdError::generic_err("A game is already in progress"));
+    }
+    let price = world_cost(Uint128(TOKENS_PER_PLAY as u128), &state.world_events, time);
+    if amount < price {
+        return Err(StdError::generic_err(format!(
+            "You need {} tokens to play",
+            price
+        )));
+    }
+    if commitment.len() != 32 {
+        return Err(StdError::generic_err("Commitment has to be a sha256 hash"));
+    }
+    state.play = Some(PendingPlay {
+        player: deps.api.canonical_address(&from)?,
+        commitment: commitment.0,
+        stake: amount,
+        height: env.block.height,
+    });
+    pet(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("reveal_until", env.block.height + PLAY_REVEAL_BLOCKS)],
+        data: None,
+    })
+}
+
+pub fn try_reveal_play<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    guess: Direction,
+    salt: Binary,
+) -> StdResult<HandleResponse> {
+    let time = env.block.time;
+    let mut state = load_state(deps)?;
+    let play = match state.play.take() {
+        Some(play) => play,
+        None => return Err(StdError::generic_err("There is no game to reveal")),
+    };
+    if play.player != deps.api.canonical_address(&env.message.sender)? {
+        return Err(StdError::unauthorized());
+    }
+    if env.block.height <= play.height {
+        return Err(StdError::generic_err(
+            "The game has to be revealed in a later block",
+        ));
+    }
+    if env.block.height > play.height + PLAY_REVEAL_BLOCKS {
+        return Err(StdError::generic_err(
+            "The game expired, reclaim the stake instead",
+        ));
+    }
+    let mut preimage = guess.name().as_bytes().to_vec();
+    preimage.extend_from_slice(salt.as_slice());
+    if sha_256(&preimage)[..] != play.commitment[..] {
+        return Err(StdError::generic_err("Guess doesn't match the commitment"));
+    }
+    if is_dead(&state, time)? {
+        return Err(StdError::generic_err("Pet is dead :("));
+    }
+    if on_vacation(&state, time).is_some() {
+        return Err(StdError::generic_err("Pet is on vacation"));
+    }
+
+    let pet_move = pet_move(&state, env, &play);
+    let won = pet_move == guess;
+    let risk = sickness_risk(&state, time)?;
+    let fell_sick = roll_sickness(&mut state, env, risk);
+    touch_stats(&mut state, time);
+    let (happiness, weight_loss) = if won {
+        (PLAY_WON_HAPPINESS, PLAY_WON_WEIGHT_LOSS)
+    } else {
+        (PLAY_LOST_HAPPINESS, PLAY_LOST_WEIGHT_LOSS)
+    };
+    state.stats.add_happiness(happiness);
+    state.weight = state.weight.saturating_sub(weight_loss).max(MIN_WEIGHT);
+    pet(&mut deps.storage).save(&state)?;
+    append_event(
+        &mut deps.storage,
+        time,
+        EventKind::Played { won },
+        Some(env.message.sender.clone()),
+    )?;
+    let mut logs = vec![log("pet_move", pet_move.name()), log("won", won)];
+    if fell_sick {
+        append_event(&mut deps.storage, time, EventKind::FellSick, None)?;
+        logs.push(log("fell_sick", time));
+    }
+
+    Ok(HandleResponse {
+        messages: vec![burn_msg(&state.token_info, play.stake)?],
+        log: logs,
+        data: None,
+    })
+}
+
+/// The pet's move is seeded with the pet's secret seed and the reveal block,
+/// so neither the player nor anyone watching the commit can predict it
+fn pet_move(state: &State, env: &Env, play: &PendingPlay) -> Direction {
+    let mut entropy = Vec::with_capacity(16 + play.commitment.len());
+    entropy.extend_from_slice(&env.block.height.to_be_bytes());
+    entropy.extend_from_slice(&env.block.time.to_be_bytes());
+    entropy.extend_from_slice(&play.commitment);
+    let mut rng = Prng::new(&state.prng_seed, &entropy);
+    if rng.rand_bytes()[0] % 2 == 0 {
+        Direction::Left
+    } else {
+        Direction::Right
+    }
+}
+
+pub fn try_reclaim_play<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+) -> StdResult<HandleResponse> {
+    let mut state = pet_read(&deps.storage).load()?;
+    let play = match state.play.take() {
+        Some(play) => play,
+        None => return Err(StdError::generic_err("There is no game to reclaim")),
+    };
+    if play.player != deps.api.canonical_address(&env.message.sender)? {
+        return Err(StdError::unauthorized());
+    }
+    if env.block.height <= play.height + PLAY_REVEAL_BLOCKS {
+        return Err(StdError::generic_err("The game can still be revealed"));
+    }
+    pet(&mut deps.storage).save(&state)?;
+
+    let refund = snip20::transfer_msg(
+        env.message.sender.clone(),
+        play.stake,
+        None,
+        BLOCK_SIZE,
+        state.token_info.code_hash.clone(),
+        state.token_info.address.clone(),
+    )?;
+    Ok(HandleResponse {
+        messages: vec![refund],
+        log: vec![],
+        data: None,
+    })
+}
+
+/// The allowance renews a year after the first vacation of the previous period
+fn start_vacation_year(state: &mut State, current_time: u64) {
+    if current_time.saturating_sub(state.vacation_year_start) >= VACATION_YEAR {
+        state.vacation_year_start = current_time;
+        state.vacation_used = Seconds::default();
+    }
+}
+
+fn on_vacation(state: &State, current_time: u64) -> Option<Vacation> {
+    state
+        .vacations
+        .iter()
+        .copied()
+        .find(|vacation| vacation.is_active(current_time))
+}
+
+pub fn try_medicine<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    from: HumanAddr,
+    amount: Uint128,
+    cost: CureCost,
+) -> StdResult<HandleResponse> {
+    let time = env.block.time;
+    let mut state = load_state(deps)?;
+    if is_dead(&state, time)? {
+        return Err(StdError::generic_err("Pet is dead :("));
+    }
+    if sick_since(&state).is_none() {
+        return Err(StdError::generic_err("Pet is not sick"));
+    }
+    if amount < cost.amount {
+        return Err(StdError::generic_err(
+            "You need more tokens to cure the pet",
+        ));
+    }
+    cure(&mut state, time);
+    pet(&mut deps.storage).save(&state)?;
+    append_event(&mut deps.storage, time, EventKind::Cured, Some(from))?;
+    let token = TokenInfo {
+        code_hash: cost.code_hash,
+        address: cost.token,
+    };
+
+    Ok(HandleResponse {
+        messages: vec![burn_msg(&token, amount)?],
+        log: vec![log("cured_at", time)],
+        data: None,
+    })
+}
+
+pub fn try_set_medicine_token<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    medicine: Option<MedicineToken>,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    let mut state = pet_read(&deps.storage).load()?;
+    if state.admin != sender {
+        return Err(StdError::unauthorized());
+    }
+    let mut messages = vec![];
+    if let Some(medicine) = &medicine {
+        if medicine.address == state.token_info.address {
+            return Err(StdError::generic_err(
+                "Medicine token has to be different from FOOD",
+            ));
+        }
+        messages.push(snip20::register_receive_msg(
+            env.contract_code_hash.clone(),
+            None,
+            BLOCK_SIZE,
+            medicine.code_hash.clone(),
+            medicine.address.clone(),
+        )?);
+    }
+    state.medicine = medicine;
+    pet(&mut deps.storage).save(&state)?;
+    Ok(HandleResponse {
+        messages,
+        log: vec![],
+        data: None,
+    })
+}
+
+pub fn try_schedule_world_event<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    event: WorldEvent,
+) -> StdResult<HandleResponse> {
+    check_if_own_schedule(deps, env)?;
+    let mut events = world_events_read(&deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    let name = event.name.clone();
+    schedule_world_event(&mut events, event, env.block.time)?;
+    world_events(&mut deps.storage).save(&events)?;
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("world_event", name)],
+        data: None,
+    })
+}
+
+pub fn try_cancel_world_event<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    name: String,
+) -> StdResult<HandleResponse> {
+    check_if_own_schedule(deps, env)?;
+    let mut events = world_events_read(&deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    cancel_world_event(&mut events, &name, env.block.time)?;
+    world_events(&mut deps.storage).save(&events)?;
+    Ok(HandleResponse::default())
+}
+
+/// Only the admin of a pet without a factory keeps a schedule, the factory's is for all its pets
+fn check_if_own_schedule<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: &Env,
+) -> StdResult<()> {
+    let state = pet_read(&deps.storage).load()?;
+    if state.admin != deps.api.canonical_address(&env.message.sender)? {
+        return Err(StdError::unauthorized());
+    }
+    if state.factory.is_some() {
+        return Err(StdError::generic_err(
+            "Pets from a factory go by its world events",
+        ));
+    }
+    Ok(())
+}
+
+/// FOOD always cures the pet, the medicine token only once the admin set one
+fn cure_costs(state: &State, current_time: u64) -> Vec<CureCost> {
+    let mut costs = vec![CureCost {
+        token: state.token_info.address.clone(),
+        code_hash: state.token_info.code_hash.clone(),
+        amount: Uint128(TOKENS_PER_MEDICINE as u128),
+    }];
+    if let Some(medicine) = &state.medicine {
+        costs.push(CureCost {
+            token: medicine.address.clone(),
+            code_hash: medicine.code_hash.clone(),
+            amount: medicine.price,
+        });
+    }
+    for cost in costs.iter_mut() {
+        cost.amount = world_cost(cost.amount, &state.world_events, current_time);
+    }
+    costs
+}
+
+fn sick_since(state: &State) -> Option<u64> {
+    state
+        .illnesses
+        .iter()
+        .find(|illness| illness.end.is_none())
+        .map(|illness| illness.start)
+}
+
+fn cure(state: &mut State, current_time: u64) {
+    for illness in state.illnesses.iter_mut() {
+        if illness.end.is_none() {
+            illness.end = Some(current_time);
+        }
+    }
+}
+
+/// Chance, in percent, that the pet falls ill when someone interacts with it
+fn sickness_risk(state: &State, current_time: u64) -> StdResult<u64> {
+    let mut risk = state.rules.sickness_risk;
+    let half_starving = Seconds(state.starving_interval.u64() / 2);
+    if time_since_meal(state, current_time) > state.satiated_interval.checked_add(half_starving)? {
+        risk += LONG_HUNGER_SICKNESS_RISK;
+    }
+    if current_stats(state, current_time).is_dirty() {
+        risk += DIRTY_SICKNESS_RISK;
+    }
+    if is_obese(state) {
+        risk += OBESE_SICKNESS_RISK;
+    }
+    risk += (MAX_POINTS - state.health) / HEALTH_POINTS_PER_RISK;
+    Ok(risk.min(100))
+}
+
+/// Deterministic roll, seeded with the pet's secret seed, the block and a nonce.
+/// Returns whether the pet fell sick
+fn roll_sickness(state: &mut State, env: &Env, risk: u64) -> bool {
+    if sick_since(state).is_some() {
+        return false;
+    }
+    state.roll_count += 1;
+    let mut entropy = Vec::with_capacity(24);
+    entropy.extend_from_slice(&env.block.height.to_be_bytes());
+    entropy.extend_from_slice(&env.block.time.to_be_bytes());
+    entropy.extend_from_slice(&state.roll_count.to_be_bytes());
+    let mut rng = Prng::new(&state.prng_seed, &entropy);
+    let mut roll = [0u8; 8];
+    roll.copy_from_slice(&rng.rand_bytes()[..8]);
+    if u64::from_be_bytes(roll) % 100 < risk {
+        state.illnesses.push(Illness {
+            start: env.block.time,
+            end: None,
+        });
+        return true;
+    }
+    false
+}
+
+/// Stats decay with the pet's clock, so they stop on vacation and speed up with sickness
+fn current_stats(state: &State, current_time: u64) -> Stats {
+    let elapsed = pet_seconds(state.stats.updated_at, current_time, &rate_windows(state));
+    state
+        .stats
+        .decayed(elapsed, current_time, &state.rules.decay)
+}
+
+/// Has to happen before windows are forgotten, they are needed to decay the stats
+fn touch_stats(state: &mut State, current_time: u64) {
+    state.stats = current_stats(state, current_time);
+}
+
+fn is_obese(state: &State) -> bool {
+    state.weight >= OBESE_WEIGHT
+}
+
+/// Windows that ended before the last meal don't affect the pet anymore
+fn forget_past_windows(state: &mut State, current_time: u64) {
+    state
+        .vacations
+        .retain(|vacation| vacation.end > current_time);
+    state
+        .illnesses
+        .retain(|illness| illness.end.map_or(true, |end| end > current_time));
+    state
+        .adoptions
+        .retain(|adoption| adoption.ended_at.is_none() || adoption.end() > current_time);
+}
+
+fn burn_msg(token: &TokenInfo, amount: Uint128) -> StdResult<CosmosMsg> {
+    snip20::burn_msg(
+        amount,
+        None,
+        BLOCK_SIZE,
+        token.code_hash.clone(),
+        token.address.clone(),
+    )
+}
+
+fn achievement_logs(unlocked: &[Achievement]) -> Vec<LogAttribute> {
+    unlocked
+        .iter()
+        .map(|achievement| log("achievement", achievement.name()))
+        .collect()
+}
+
+/// One Market mint per unlocked achievement, if the admin configured a bonus
+fn achievement_rewards<A: Api>(
+    api: &A,
+    state: &State,
+    unlocked: &[Achievement],
+) -> StdResult<Vec<CosmosMsg>> {
+    let bonus = match &state.achievement_bonus {
+        Some(bonus) if !unlocked.is_empty() => bonus,
+        _ => return Ok(vec![]),
+    };
+    let recipient = api.human_address(&state.owner)?;
+    unlocked
+        .iter()
+        .map(|achievement| {
+            MarketHandleMsg::MintReward {
+                recipient: recipient.clone(),
+                amount: bonus.amount,
+                memo: Some(format!("Achievement: {}", achievement.name())),
+            }
+            .to_cosmos_msg(
+                bonus.market_code_hash.clone(),
+                bonus.market_address.clone(),
+                None,
+            )
+        })
+        .collect()
+}
+
+pub fn try_set_achievement_bonus<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    bonus: Option<AchievementBonus>,
+) -> StdResult<HandleResponse> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    pet(&mut deps.storage).update(|mut state| {
+        if state.admin != sender {
+            return Err(StdError::unauthorized());
+        }
+        state.achievement_bonus = bonus;
+        Ok(state)
+    })?;
+    Ok(HandleResponse::default())
+}
+
+pub fn try_update_intervals<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    satiated_interval: Duration,
+    starving_interval: Duration,
+) -> StdResult<HandleResponse> {
+    let satiated_interval = satiated_interval.to_seconds()?;
+    let starving_interval = starving_interval.to_seconds()?;
+    validate_intervals(satiated_interval, starving_interval)?;
+
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    pet(&mut deps.storage).update(|mut state| {
+        if state.owner != sender {
+            return Err(StdError::unauthorized());
+        }
+        state.satiated_interval = satiated_interval;
+        state.starving_interval = starving_interval;
+        // once made easier, the pet stays custom
+        state.difficulty = ranked_difficulty(
+            state.difficulty,
+            satiated_interval,
+            starving_interval,
+            &state.rules,
+        );
+        Ok(state)
+    })?;
+    Ok(HandleResponse::default())
+}
+
+/// The owner can do anything, caretakers only what they were given permission for.
+/// `None` is for actions that only the owner can take
+fn authorize<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    state: &State,
+    address: &HumanAddr,
+    permission: Option<Permission>,
+    current_time: u64,
+) -> StdResult<()> {
+    let address = deps.api.canonical_address(address)?;
+    if address == state.owner {
+        return Ok(());
+    }
+    let permission = match permission {
+        Some(permission) => permission,
+        None => return Err(StdError::unauthorized()),
+    };
+    let caretakers = caretakers_read(&deps.storage).load()?;
+    if caretakers
+        .iter()
+        .any(|caretaker| caretaker.address == address && caretaker.can(permission, current_time))
+    {
+        Ok(())
+    } else {
+        Err(StdError::unauthorized())
+    }
+}
+
+pub fn try_add_caretaker<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    address: HumanAddr,
+    expires_at: u64,
+    permissions: Vec<Permission>,
+) -> StdResult<HandleResponse> {
+    check_if_owner(deps, env)?;
+    if expires_at <= env.block.time {
+        return Err(StdError::generic_err(
+            "Expiration time has to be in the future",
+        ));
+    }
+    if permissions.is_empty() {
+        return Err(StdError::generic_err("At least one permission is required"));
+    }
+    let address = deps.api.canonical_address(&address)?;
+    caretakers(&mut deps.storage).update(|mut caretakers| {
+        caretakers.retain(|caretaker| caretaker.address != address);
+        caretakers.push(Caretaker {
+            address,
+            expires_at,
+            permissions,
+        });
+        Ok(caretakers)
+    })?;
+    Ok(HandleResponse::default())
+}
+
+pub fn try_remove_caretaker<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    address: HumanAddr,
+) -> StdResult<HandleResponse> {
+    check_if_owner(deps, env)?;
+    let address = deps.api.canonical_address(&address)?;
+    caretakers(&mut deps.storage).update(|mut caretakers| {
+        let count = caretakers.len();
+        caretakers.retain(|caretaker| caretaker.address != address);
+        if caretakers.len() == count {
+            return Err(StdError::generic_err("Address is not a caretaker"));
+        }
+        Ok(caretakers)
+    })?;
+    Ok(HandleResponse::default())
+}
+
+pub fn try_add_friend<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    pet: HumanAddr,
+) -> StdResult<HandleResponse> {
+    check_if_owner(deps, env)?;
+    if pet == env.contract.address {
+        return Err(StdError::generic_err("A pet can't be its own friend"));
+    }
+    let pet = deps.api.canonical_address(&pet)?;
+    friends(&mut deps.storage).update(|mut friends| {
+        if !friends.contains(&pet) {
+            friends.push(pet);
+        }
+        Ok(friends)
+    })?;
+    Ok(HandleResponse::default())
+}
+
+pub fn try_remove_friend<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    pet: HumanAddr,
+) -> StdResult<HandleResponse> {
+    check_if_owner(deps, env)?;
+    let pet = deps.api.canonical_address(&pet)?;
+    friends(&mut deps.storage).update(|mut friends| {
+        let count = friends.len();
+        friends.retain(|friend| *friend != pet);
+        if friends.len() == count {
+            return Err(StdError::generic_err("Pet is not a friend"));
+        }
+        Ok(friends)
+    })?;
+    Ok(HandleResponse::default())
+}
+
+/// Visits go through the factory, so the visited pet knows the guest is a pet. It checks that
+/// it's friends with this one, if not, the whole visit fails
+pub fn try_visit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    host: HumanAddr,
+    code_hash: String,
+) -> StdResult<HandleResponse> {
+    check_if_owner(deps, env)?;
+    if host == env.contract.address {
+        return Err(StdError::generic_err("A pet can't visit itself"));
+    }
+    let time = env.block.time;
+    let mut state = load_state(deps)?;
+    let visit = notify_factory(&state, |pet_id| FactoryHandleMsg::VisitPet {
+        pet_id,
+        host: host.clone(),
+        host_code_hash: code_hash,
+    })?
+    .ok_or_else(|| StdError::generic_err("Only pets from a factory can visit"))?;
+    let fell_sick = enjoy_visit(&mut state, env)?;
+    pet(&mut deps.storage).save(&state)?;
+    append_event(
+        &mut deps.storage,
+        time,
+        EventKind::Visited { pet: host.clone() },
+        Some(env.message.sender.clone()),
+    )?;
+    let mut logs = vec![log("visited", host)];
+    if fell_sick {
+        append_event(&mut deps.storage, time, EventKind::FellSick, None)?;
+        logs.push(log("fell_sick", time));
+    }
+
+    Ok(HandleResponse {
+        messages: vec![visit],
+        log: logs,
+        data: None,
+    })
+}
+
+/// Only the factory can vouch for the guest, a friend listed address could be anything
+pub fn try_receive_visit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    guest: HumanAddr,
+) -> StdResult<HandleResponse> {
+    let mut state = load_state(deps)?;
+    match &state.factory {
+        Some(factory) if factory.address == env.message.sender => {}
+        _ => return Err(StdError::unauthorized()),
+    }
+    if !friends_read(&deps.storage)
+        .load()?
+        .contains(&deps.api.canonical_address(&guest)?)
+    {
+        return Err(StdError::generic_err("Only friends can visit the pet"));
+    }
+    let time = env.block.time;
+    let fell_sick = enjoy_visit(&mut state, env)?;
+    pet(&mut deps.storage).save(&state)?;
+    append_event(
+        &mut deps.storage,
+        time,
+        EventKind::VisitedBy { pet: guest.clone() },
+        None,
+    )?;
+    let mut logs = vec![log("visited_by", guest)];
+    if fell_sick {
+        append_event(&mut deps.storage, time, EventKind::FellSick, None)?;
+        logs.push(log("fell_sick", time));
+    }
+    Ok(HandleResponse {
+        messages: vec![],
+        log: logs,
+        data: None,
+    })
+}
+
+/// Checks the pet is up for a visit, on either side of it, and makes it happier.
+/// Returns whether the pet caught something from its friend
+fn enjoy_visit(state: &mut State, env: &Env) -> StdResult<bool> {
+    let current_time = env.block.time;
+    if is_dead(state, current_time)? {
+        return Err(StdError::generic_err("Pet is dead :("));
+    }
+    if on_vacation(state, current_time).is_some() {
+        return Err(StdError::generic_err("Pet is on vacation"));
+    }
+    if let Some(next_visit_at) = next_visit_at(state, current_time) {
+        return Err(StdError::generic_err(format!(
+            "Pet needs some rest after its last visit, try again at {}",
+            next_visit_at
+        )));
+    }
+    let risk = sickness_risk(state, current_time)?;
+    let fell_sick = roll_sickness(state, env, risk);
+    touch_stats(state, current_time);
+    state.stats.add_happiness(VISIT_HAPPINESS);
+    state.last_visit = Some(current_time);
+    Ok(fell_sick)
+}
+
+/// `None` once the cooldown of the last visit is over
+fn next_visit_at(state: &State, current_time: u64) -> Option<u64> {
+    state
+        .last_visit
+        .map(|last_visit| last_visit.saturating_add(VISIT_COOLDOWN.u64()))
+        .filter(|next_visit_at| *next_visit_at > current_time)
+}
+
+fn check_if_owner<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: &Env,
+) -> StdResult<()> {
+    let sender = deps.api.canonical_address(&env.message.sender)?;
+    if pet_read(&deps.storage).load()?.owner != sender {
+        return Err(StdError::unauthorized());
+    }
+    Ok(())
+}
+
+pub fn try_set_key<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    key: String,
+) -> StdResult<HandleResponse> {
+    let vk = ViewingKey(key);
+
+    let message_sender = deps.api.canonical_address(&env.message.sender)?;
+    write_viewing_key(&mut deps.storage, &message_sender, &vk);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::SetViewingKey { status: Success })?),
+    })
+}
+
+pub fn try_create_key<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    entropy: String,
+) -> StdResult<HandleResponse> {
+    let prng_seed = pet_read(&deps.storage).load()?.prng_seed;
+
+    let key = ViewingKey::new(&env, &prng_seed, (&entropy).as_ref());
+
+    let message_sender = deps.api.canonical_address(&env.message.sender)?;
+    write_viewing_key(&mut deps.storage, &message_sender, &key);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&HandleAnswer::CreateViewingKey { key })?),
+    })
+}
+
+/// Intervals have to be within [MIN_INTERVAL, MAX_INTERVAL], and the pet can't starve
+/// for longer than it stays satiated
+fn validate_intervals(satiated_interval: Seconds, starving_interval: Seconds) -> StdResult<()> {
+    if satiated_interval.u64() == 0 || starving_interval.u64() == 0 {
+        return Err(StdError::generic_err("Intervals must be greater than zero"));
+    }
+    for interval in [satiated_interval, starving_interval] {
+        if interval < MIN_INTERVAL || interval > MAX_INTERVAL {
+            return Err(StdError::generic_err(format!(
+                "Intervals must be between {} and {} seconds",
+                MIN_INTERVAL.u64(),
+                MAX_INTERVAL.u64()
+            )));
+        }
+    }
+    if starving_interval > satiated_interval {
+        return Err(StdError::generic_err(
+            "Starving interval can't be longer than the satiated interval",
+        ));
+    }
+    Ok(())
+}
+
+/// Periods in which the pet's clock doesn't run at the normal rate
+fn rate_windows(state: &State) -> Vec<RateWindow> {
+    let vacations = state.vacations.iter().map(|vacation| RateWindow {
+        start: vacation.start,
+        end: vacation.end,
+        rate: 0,
+    });
+    let illnesses = state.illnesses.iter().map(|illness| RateWindow {
+        start: illness.start,
+        end: illness.end.unwrap_or(u64::MAX),
+        rate: SICK_RATE,
+    });
+    let adoptions = state.adoptions.iter().map(|adoption| RateWindow {
+        start: adoption.listed_at,
+        end: adoption.end(),
+        rate: 0,
+    });
+    let world_events = state.world_events.iter().map(rate_window);
+    vacations
+        .chain(illnesses)
+        .chain(adoptions)
+        .chain(world_events)
+        .collect()
+}
+
+/// Time the pet has gone without food, as experienced by the pet
+fn time_since_meal(state: &State, current_time: u64) -> Seconds {
+    Seconds(pet_seconds(
+        state.last_feed_time,
+        current_time,
+        &rate_windows(state),
+    ))
+}
+
+fn is_dead(state: &State, current_time: u64) -> StdResult<bool> {
+    let lifespan = state
+        .satiated_interval
+        .checked_add(state.starving_interval)?;
+    Ok(time_since_meal(state, current_time) > lifespan)
+}
+
+fn is_hungry(state: &State, current_time: u64) -> StdResult<bool> {
+    Ok(time_since_meal(state, current_time) > state.satiated_interval)
+}
+
+fn vacation_allowance(state: &State, current_time: u64) -> Seconds {
+    let mut state = state.clone();
+    start_vacation_year(&mut state, current_time);
+    Seconds(
+        VACATION_ALLOWANCE
+            .u64()
+            .saturating_sub(state.vacation_used.u64()),
+    )
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> StdResult<Binary> {
+    let state = match msg {
+        QueryMsg::IsHungry { .. }
+        | QueryMsg::CanEat { .. }
+        | QueryMsg::Status { .. }
+        | QueryMsg::WorldEvents { .. }
+        | QueryMsg::Vitals { .. }
+        | QueryMsg::Sickness { .. } => load_state(deps)?,
+        _ => pet_read(&deps.storage).load()?,
+    };
+    match msg {
+        QueryMsg::IsHungry { time } => to_binary(&QueryAnswer::IsHungry {
+            is_hungry: is_hungry(&state, time)?,
+        }),
+        QueryMsg::CanEat { time } => to_binary(&QueryAnswer::CanEat {
+            can_eat: is_hungry(&state, time)? && !is_dead(&state, time)?,
+        }),
+        QueryMsg::Status { time } => query_status(&state, time),
+        QueryMsg::Profile {} => query_profile(&state),
+        QueryMsg::Friends { time } => to_binary(&QueryAnswer::Friends {
+            friends: friends_read(&deps.storage)
+                .load()?
+                .iter()
+                .map(|friend| deps.api.human_address(friend))
+                .collect::<StdResult<Vec<_>>>()?,
+            next_visit_at: next_visit_at(&state, time),
+        }),
+        QueryMsg::WorldEvents { time } => {
+            let (active, upcoming): (Vec<_>, Vec<_>) = state
+                .world_events
+                .iter()
+                .filter(|event| event.end > time)
+                .cloned()
+                .partition(|event| event.is_active(time));
+            to_binary(&QueryAnswer::WorldEvents {
+                active,
+                upcoming,
+                feeding_cost: world_cost(state.rules.feeding_cost, &state.world_events, time),
+            })
+        }
+        QueryMsg::Play {} => to_binary(&QueryAnswer::Play {
+            pending: state.play.as_ref().map(|play| PlayInfo {
+                stake: play.stake,
+                committed_at: play.height,
+                expires_at: play.height + PLAY_REVEAL_BLOCKS,
+            }),
+        }),
+        QueryMsg::Graveyard { page, page_size } => {
+            let (memorials, total) = get_memorials(&deps.storage, page.unwrap_or(0), page_size)?;
+            to_binary(&QueryAnswer::Memorials { memorials, total })
+        }
+        QueryMsg::HallOfFame { page, page_size } => {
+            let (memorials, total) = get_hall_of_fame(&deps.storage, page.unwrap_or(0), page_size)?;
+            to_binary(&QueryAnswer::Memorials { memorials, total })
+        }
+        QueryMsg::Caretakers { .. }
+        | QueryMsg::History { .. }
+        | QueryMsg::Vitals { .. }
+        | QueryMsg::Achievements { .. }
+        | QueryMsg::Rules { .. }
+        | QueryMsg::Sickness { .. } => viewing_keys_queries(deps, &state, &msg),
+    }
+}
+
+fn viewing_keys_queries<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    state: &State,
+    msg: &QueryMsg,
+) -> StdResult<Binary> {
+    let (addresses, key) = msg.get_validation_params();
+
+    for address in addresses {
+        let canonical_addr = deps.api.canonical_address(address)?;
+
+        let expected_key = read_viewing_key(&deps.storage, &canonical_addr);
+
+        if expected_key.is_none() {
+            // Checking the key will take significant time. We don't want to exit immediately if it isn't set
+            // in a way which will allow to time the command and determine if a viewing key doesn't exist
+            key.check_viewing_key(&[0u8; VIEWING_KEY_SIZE]);
+        } else if key.check_viewing_key(expected_key.unwrap().as_slice()) {
+            // only the owner has anything to see here
+            if canonical_addr != state.owner {
+                return Err(StdError::unauthorized());
+            }
+            return match msg {
+                QueryMsg::Caretakers { .. } => query_caretakers(deps),
+                QueryMsg::History {
+                    page, page_size, ..
+                } => query_history(deps, page.unwrap_or(0), *page_size),
+                QueryMsg::Vitals { time, .. } => to_binary(&QueryAnswer::Vitals {
+                    weight: state.weight,
+                    health: state.health,
+                    is_obese: is_obese(state),
+                    cleanliness: current_stats(state, *time).cleanliness_points(),
+                    happiness: current_stats(state, *time).happiness_points(),
+                }),
+                QueryMsg::Achievements { .. } => to_binary(&QueryAnswer::Achievements {
+                    achievements: achievements_from_bits(state.achievements),
+                    feed_count: state.feed_count,
+                    current_streak: state.current_streak,
+                    best_streak: state.best_streak,
+                }),
+                QueryMsg::Rules { .. } => to_binary(&QueryAnswer::Rules {
+                    difficulty: state.difficulty,
+                    satiated_interval: state.satiated_interval,
+                    starving_interval: state.starving_interval,
+                    rules: state.rules,
+                }),
+                QueryMsg::Sickness { time, .. } => to_binary(&QueryAnswer::Sickness {
+                    is_sick: sick_since(state).is_some(),
+                    sick_since: sick_since(state),
+                    risk: sickness_risk(state, *time)?,
+                    cure_costs: cure_costs(state, *time),
+                }),
+                _ => panic!("This query type does not require authentication"),
+            };
+        }
+    }
+
+    to_binary(&QueryAnswer::ViewingKeyError {
+        msg: "Wrong viewing key for this address or viewing key not set".to_string(),
+    })
+}
+
+fn query_status(state: &State, time: u64) -> StdResult<Binary> {
+    to_binary(&QueryAnswer::Status {
+        last_feed_time: state.last_feed_time,
+        satiated_interval: state.satiated_interval,
+        starving_interval: state.starving_interval,
+        is_hungry: is_hungry(state, time)?,
+        is_dead: is_dead(state, time)?,
+        vacation_until: on_vacation(state, time).map(|vacation| vacation.end),
+        vacation_allowance: vacation_allowance(state, time),
+        adoption_deadline: state
+            .adoptions
+            .iter()
+            .find(|adoption| adoption.ended_at.is_none() && time < adoption.deadline)
+            .map(|adoption| adoption.deadline),
+        feeding_mode: state.feeding_mode,
+    })
+}
+
+fn query_profile(state: &State) -> StdResult<Binary> {
+    let profile = &state.profile;
+    let public = |field| profile.is_public(field);
+    to_binary(&QueryAnswer::Profile {
+        name: profile.name.clone(),
+        bio: profile.bio.clone().filter(|_| public(ProfileField::Bio)),
+        avatar: profile
+            .avatar
+            .clone()
+            .filter(|_| public(ProfileField::Avatar)),
+        alive_since: Some(state.alive_since).filter(|_| public(ProfileField::AliveSince)),
+        difficulty: Some(state.difficulty).filter(|_| public(ProfileField::Difficulty)),
+        achievements: Some(achievements_from_bits(state.achievements))
+            .filter(|_| public(ProfileField::Achievements)),
+    })
+}
+
+fn query_caretakers<S: Storage, A: Api, Q: Querier>(deps: &Extern<S, A, Q>) -> StdResult<Binary> {
+    let caretakers = caretakers_read(&deps.storage)
+        .load()?
+        .into_iter()
+        .map(|caretaker| {
+            Ok(CaretakerInfo {
+                address: deps.api.human_address(&caretaker.address)?,
+                expires_at: caretaker.expires_at,
+                permissions: caretaker.permissions,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    to_binary(&QueryAnswer::Caretakers { caretakers })
+}
+
+fn query_history<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    page: u32,
+    page_size: u32,
+) -> StdResult<Binary> {
+    let (events, total) = get_events(&deps.storage, page, page_size)?;
+    to_binary(&QueryAnswer::History { events, total })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
+    use cosmwasm_std::{from_slice, Empty, QuerierResult, QueryRequest, WasmMsg, WasmQuery};
+
+    use crate::history::Event;
+    use crate::msg::{FactoryQueryAnswer, FactoryQueryMsg, RuleOverrides};
+
+    const BIRTH: u64 = 1_571_797_419;
+    const TOKENS_PER_FEEDING: u16 = 100;
+
+    /// Answers the pet's queries like its factory would
+    #[derive(Default)]
+    struct FactoryQuerier {
+        events: Vec<WorldEvent>,
+    }
+
+    impl Querier for FactoryQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let msg = match from_slice(bin_request).unwrap() {
+                QueryRequest::<Empty>::Wasm(WasmQuery::Smart { msg, .. }) => msg,
+                _ => panic!("Unexpected query"),
+            };
+            let answer = match from_binary(&msg).unwrap() {
+                FactoryQueryMsg::WorldEvents {} => to_binary(&FactoryQueryAnswer::WorldEvents {
+                    events: self.events.clone(),
+                }),
+            };
+            Ok(answer)
+        }
+    }
+
+    fn init_helper(
+        satiated_interval: Option<Duration>,
+        starving_interval: Option<Duration>,
+    ) -> (
+        StdResult<InitResponse>,
+        Extern<MockStorage, MockApi, FactoryQuerier>,
+    ) {
+        init_with_difficulty(None, satiated_interval, starving_interval, None)
+    }
+
+    fn init_with_difficulty(
+        difficulty: Option<Difficulty>,
+        satiated_interval: Option<Duration>,
+        starving_interval: Option<Duration>,
+        overrides: Option<RuleOverrides>,
+    ) -> (
+        StdResult<InitResponse>,
+        Extern<MockStorage, MockApi, FactoryQuerier>,
+    ) {
+        let mut deps = Extern {
+            storage: MockStorage::default(),
+            api: MockApi::new(20),
+            querier: FactoryQuerier::default(),
+        };
+        let msg = InitMsg {
+            name: "Tama".to_string(),
+            token_code_hash: "food_hash".to_string(),
+            token_address: "food".to_string(),
+            difficulty,
+            satiated_interval,
+            starving_interval,
+            overrides,
+            owner: None,
+            admin: None,
+            factory: None,
+            prng_seed: Binary::from("seed".as_bytes()),
+        };
+        let res = init(&mut deps, env_at("creator", BIRTH), msg);
+        if res.is_ok() {
+            // stats are owner only, tests query them with the creator's key
+            let msg = HandleMsg::SetViewingKey {
+                key: "key".to_string(),
+                padding: None,
+            };
+            handle(&mut deps, env_at("creator", BIRTH), msg).unwrap();
+        }
+        (res, deps)
+    }
+
+    fn env_at(sender: &str, time: u64) -> Env {
+        let mut env = mock_env(sender, &[]);
+        env.block.time = time;
+        env
+    }
+
+    fn feed(
+        deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>,
+        time: u64,
+    ) -> StdResult<HandleResponse> {
+        receive(deps, time, TOKENS_PER_FEEDING, None)
+    }
+
+    fn receive(
+        deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>,
+        time: u64,
+        amount: u16,
+        msg: Option<ReceiveMsg>,
+    ) -> StdResult<HandleResponse> {
+        receive_from(deps, "creator", time, amount, msg)
+    }
+
+    fn receive_from(
+        deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>,
+        from: &str,
+        time: u64,
+        amount: u16,
+        msg: Option<ReceiveMsg>,
+    ) -> StdResult<HandleResponse> {
+        let msg = HandleMsg::Receive {
+            sender: HumanAddr::from(from),
+            from: HumanAddr::from(from),
+            amount: Uint128(amount as u128),
+            msg: msg.map(|msg| to_binary(&msg).unwrap()),
+        };
+        handle(deps, env_at("food", time), msg)
+    }
+
+    /// A sick pet would starve before its next meal, so it gets medicine right away
+    fn feed_and_cure(
+        deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>,
+        time: u64,
+    ) -> HandleResponse {
+        let res = feed(deps, time).unwrap();
+        cure_if_sick(deps, &res, time);
+        res
+    }
+
+    fn cure_if_sick(
+        deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>,
+        res: &HandleResponse,
+        time: u64,
+    ) {
+        if res.log.iter().any(|attr| attr.key == "fell_sick") {
+            receive(deps, time, 200, Some(ReceiveMsg::Medicine {})).unwrap();
+        }
+    }
+
+    fn achievement_logs(res: &HandleResponse) -> Vec<String> {
+        res.log
+            .iter()
+            .filter(|attr| attr.key == "achievement")
+            .map(|attr| attr.value.clone())
+            .collect()
+    }
+
+    fn extract_error_msg<T>(result: StdResult<T>) -> String {
+        match result {
+            Err(StdError::GenericErr { msg, .. }) => msg,
+            Err(err) => panic!("Unexpected error: {:?}", err),
+            Ok(_) => panic!("Expected an error"),
+        }
+    }
+
+    #[test]
+    fn init_uses_default_intervals() {
+        let (res, deps) = init_helper(None, None);
+        assert_eq!(res.unwrap().messages.len(), 1);
+        let state = pet_read(&deps.storage).load().unwrap();
+        assert_eq!(state.satiated_interval, Seconds(3 * 3600));
+        assert_eq!(state.starving_interval, Seconds(3600));
+        assert_eq!(state.last_feed_time, BIRTH);
+    }
+
+    #[test]
+    fn init_validates_intervals() {
+        let (res, _) = init_helper(Some(Duration::Hours(0)), None);
+        assert_eq!(
+            extract_error_msg(res),
+            "Intervals must be greater than zero"
+        );
+
+        let (res, _) = init_helper(Some(Duration::Seconds(59)), Some(Duration::Seconds(59)));
+        assert!(extract_error_msg(res).contains("Intervals must be between"));
+
+        let (res, _) = init_helper(Some(Duration::Days(31)), None);
+        assert!(extract_error_msg(res).contains("Intervals must be between"));
+
+        let (res, _) = init_helper(Some(Duration::Hours(1)), Some(Duration::Hours(2)));
+        assert_eq!(
+            extract_error_msg(res),
+            "Starving interval can't be longer than the satiated interval"
+        );
+
+        let (res, _) = init_helper(Some(Duration::Days(u64::MAX)), None);
+        assert_eq!(extract_error_msg(res), "Duration is too long");
+
+        let (res, _) = init_helper(Some(Duration::Minutes(90)), Some(Duration::Minutes(90)));
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn feeding_exactly_at_hungry_time() {
+        let (_, mut deps) = init_helper(Some(Duration::Hours(3)), Some(Duration::Hours(1)));
+        let hungry_at = BIRTH + 3 * 3600;
+
+        let res = feed(&mut deps, hungry_at);
+        assert_eq!(extract_error_msg(res), "Pet is not hungry");
+
+        feed(&mut deps, hungry_at + 1).unwrap();
+        let state = pet_read(&deps.storage).load().unwrap();
+        assert_eq!(state.last_feed_time, hungry_at + 1);
+    }
+
+    #[test]
+    fn feeding_exactly_at_death_time() {
+        let (_, mut deps) = init_helper(Some(Duration::Hours(3)), Some(Duration::Hours(1)));
+        let dies_at = BIRTH + 4 * 3600;
+        feed(&mut deps, dies_at).unwrap();
+
+        let (_, mut deps) = init_helper(Some(Duration::Hours(3)), Some(Duration::Hours(1)));
+        let res = feed(&mut deps, dies_at + 1).unwrap();
+        // the food is sent back
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(
+            res.log,
+            vec![log("died_at", dies_at + 1), log("cause", "starvation")]
+        );
+    }
+
+    #[test]
+    fn status_at_boundaries() {
+        let (_, deps) = init_helper(Some(Duration::Hours(3)), Some(Duration::Hours(1)));
+        let status =
+            |time| match from_binary(&query(&deps, QueryMsg::Status { time }).unwrap()).unwrap() {
+                QueryAnswer::Status {
+                    is_hungry, is_dead, ..
+                } => (is_hungry, is_dead),
+                _ => panic!("Unexpected query answer"),
+            };
+        assert_eq!(status(BIRTH + 3 * 3600), (false, false));
+        assert_eq!(status(BIRTH + 3 * 3600 + 1), (true, false));
+        assert_eq!(status(BIRTH + 4 * 3600), (true, false));
+        assert_eq!(status(BIRTH + 4 * 3600 + 1), (true, true));
+    }
+
+    #[test]
+    fn time_overflow_is_an_error() {
+        let (_, mut deps) = init_helper(None, None);
+        pet(&mut deps.storage)
+            .update(|mut state| {
+                state.last_feed_time = u64::MAX - 60;
+                Ok(state)
+            })
+            .unwrap();
+        let res = feed(&mut deps, u64::MAX);
+        assert_eq!(extract_error_msg(res), "Pet is not hungry");
+
+        let vacation = ReceiveMsg::StartVacation {
+            duration: Duration::Hours(2),
+        };
+        let res = receive(&mut deps, u64::MAX - 30, 20, Some(vacation));
+        assert_eq!(extract_error_msg(res), "Time overflow");
+    }
+
+    #[test]
+    fn only_owner_updates_intervals() {
+        let (_, mut deps) = init_helper(None, None);
+        let msg = HandleMsg::UpdateIntervals {
+            satiated_interval: Duration::Hours(6),
+            starving_interval: Duration::Hours(2),
+        };
+        let res = handle(&mut deps, env_at("stranger", BIRTH), msg.clone());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+
+        handle(&mut deps, env_at("creator", BIRTH), msg).unwrap();
+        let state = pet_read(&deps.storage).load().unwrap();
+        assert_eq!(state.satiated_interval, Seconds(6 * 3600));
+
+        let msg = HandleMsg::UpdateIntervals {
+            satiated_interval: Duration::Hours(1),
+            starving_interval: Duration::Hours(6),
+        };
+        let res = handle(&mut deps, env_at("creator", BIRTH), msg);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn streaks_and_achievements() {
+        let (_, mut deps) = init_helper(Some(Duration::Hours(3)), Some(Duration::Hours(1)));
+        let window = 3 * 3600 + 1;
+
+        let res = feed(&mut deps, BIRTH + window).unwrap();
+        assert_eq!(achievement_logs(&res), vec!["first_meal"]);
+
+        let mut time = BIRTH + window;
+        let mut unlocked = vec![];
+        for _ in 1..100 {
+            time += window;
+            let res = feed_and_cure(&mut deps, time);
+            unlocked.extend(achievement_logs(&res));
+        }
+        assert_eq!(
+            unlocked,
+            vec!["long_streak", "survived_week", "hundred_meals"]
+        );
+
+        let res = query(
+            &deps,
+            QueryMsg::Achievements {
+                address: HumanAddr::from("creator"),
+                key: "key".to_string(),
+            },
+        )
+        .unwrap();
+        match from_binary(&res).unwrap() {
+            QueryAnswer::Achievements {
+                achievements,
+                feed_count,
+                current_streak,
+                best_streak,
+            } => {
+                assert_eq!(
+                    achievements,
+                    vec![
+                        Achievement::FirstMeal,
+                        Achievement::HundredMeals,
+                        Achievement::SurvivedWeek,
+                        Achievement::LongStreak,
+                    ]
+                );
+                assert_eq!(feed_count, 100);
+                assert_eq!(current_streak, 100);
+                assert_eq!(best_streak, 100);
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    #[test]
+    fn revive_resets_streak() {
+        let (_, mut deps) = init_helper(Some(Duration::Hours(3)), Some(Duration::Hours(1)));
+        feed(&mut deps, BIRTH + 3 * 3600 + 1).unwrap();
+
+        let res = receive(
+            &mut deps,
+            BIRTH + 4 * 3600,
+            TOKENS_PER_REVIVAL,
+            Some(ReceiveMsg::Revive {}),
+        );
+        assert_eq!(extract_error_msg(res), "Pet is still alive");
+
+        let dead_at = BIRTH + 12 * 3600;
+        let res = receive(
+            &mut deps,
+            dead_at,
+            TOKENS_PER_FEEDING,
+            Some(ReceiveMsg::Revive {}),
+        );
+        assert_eq!(
+            extract_error_msg(res),
+            "You need more tokens to revive the pet"
+        );
+
+        let res = receive(
+            &mut deps,
+            dead_at,
+            TOKENS_PER_REVIVAL,
+            Some(ReceiveMsg::Revive {}),
+        )
+        .unwrap();
+        assert_eq!(achievement_logs(&res), vec!["revived"]);
+
+        let state = pet_read(&deps.storage).load().unwrap();
+        assert_eq!(state.current_streak, 0);
+        assert_eq!(state.best_streak, 1);
+        assert_eq!(state.alive_since, dead_at);
+        assert_eq!(state.last_feed_time, dead_at);
+    }
+
+    #[test]
+    fn achievement_bonus_mints_through_market() {
+        let (_, mut deps) = init_helper(None, None);
+        let bonus = AchievementBonus {
+            market_address: HumanAddr::from("market"),
+            market_code_hash: "market_hash".to_string(),
+            amount: Uint128(50),
+        };
+        let msg = HandleMsg::SetAchievementBonus { bonus: Some(bonus) };
+        let res = handle(&mut deps, env_at("stranger", BIRTH), msg.clone());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        handle(&mut deps, env_at("creator", BIRTH), msg).unwrap();
+
+        let res = feed(&mut deps, BIRTH + 3 * 3600 + 1).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        let res = feed(&mut deps, BIRTH + 6 * 3600 + 2).unwrap();
+        assert_eq!(res.messages.len(), 1);
+    }
+
+    #[test]
+    fn only_food_can_be_received() {
+        let (_, mut deps) = init_helper(None, None);
+        let msg = HandleMsg::Receive {
+            sender: HumanAddr::from("creator"),
+            from: HumanAddr::from("creator"),
+            amount: Uint128(TOKENS_PER_FEEDING as u128),
+            msg: None,
+        };
+        let res = handle(&mut deps, env_at("fake_food", BIRTH + 3 * 3600 + 1), msg);
+        assert_eq!(
+            extract_error_msg(res),
+            "Only FOOD or medicine tokens can be sent to the pet"
+        );
+    }
+
+    #[test]
+    fn caretakers_act_within_permissions_until_expiry() {
+        let (_, mut deps) = init_helper(Some(Duration::Hours(1)), Some(Duration::Hours(1)));
+        let add_sitter = HandleMsg::AddCaretaker {
+            address: HumanAddr::from("sitter"),
+            expires_at: BIRTH + 4 * 3600,
+            permissions: vec![Permission::Feed],
+        };
+        let res = handle(&mut deps, env_at("sitter", BIRTH), add_sitter.clone());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = receive_from(&mut deps, "sitter", BIRTH + 3601, TOKENS_PER_FEEDING, None);
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+
+        handle(&mut deps, env_at("creator", BIRTH), add_sitter).unwrap();
+        receive_from(&mut deps, "sitter", BIRTH + 3601, TOKENS_PER_FEEDING, None).unwrap();
+
+        // the sitter can't revive the pet, but the owner can
+        let revive = Some(ReceiveMsg::Revive {});
+        let dead_at = BIRTH + 3 * 3600 + 2;
+        let res = receive_from(
+            &mut deps,
+            "sitter",
+            dead_at,
+            TOKENS_PER_REVIVAL,
+            revive.clone(),
+        );
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        receive(&mut deps, dead_at, TOKENS_PER_REVIVAL, revive).unwrap();
+
+        // hungry again, but the delegation has expired
+        let res = receive_from(
+            &mut deps,
+            "sitter",
+            dead_at + 3601,
+            TOKENS_PER_FEEDING,
+            None,
+        );
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+    }
+
+    #[test]
+    fn add_and_remove_caretakers() {
+        let (_, mut deps) = init_helper(None, None);
+        let add = |permissions, expires_at| HandleMsg::AddCaretaker {
+            address: HumanAddr::from("sitter"),
+            expires_at,
+            permissions,
+        };
+        let res = handle(
+            &mut deps,
+            env_at("creator", BIRTH),
+            add(vec![Permission::Feed], BIRTH),
+        );
+        assert_eq!(
+            extract_error_msg(res),
+            "Expiration time has to be in the future"
+        );
+        let res = handle(&mut deps, env_at("creator", BIRTH), add(vec![], BIRTH + 1));
+        assert_eq!(
+            extract_error_msg(res),
+            "At least one permission is required"
+        );
+
+        handle(
+            &mut deps,
+            env_at("creator", BIRTH),
+            add(vec![Permission::Feed], BIRTH + 1),
+        )
+        .unwrap();
+        handle(
+            &mut deps,
+            env_at("creator", BIRTH),
+            add(vec![Permission::Play, Permission::Revive], BIRTH + 2),
+        )
+        .unwrap();
+        let caretakers = caretakers_read(&deps.storage).load().unwrap();
+        assert_eq!(caretakers.len(), 1);
+        assert_eq!(caretakers[0].expires_at, BIRTH + 2);
+
+        let remove = HandleMsg::RemoveCaretaker {
+            address: HumanAddr::from("sitter"),
+        };
+        handle(&mut deps, env_at("creator", BIRTH), remove.clone()).unwrap();
+        let res = handle(&mut deps, env_at("creator", BIRTH), remove);
+        assert_eq!(extract_error_msg(res), "Address is not a caretaker");
+    }
+
+    #[test]
+    fn owner_queries_caretakers_with_viewing_key() {
+        let (_, mut deps) = init_helper(None, None);
+        let msg = HandleMsg::AddCaretaker {
+            address: HumanAddr::from("sitter"),
+            expires_at: BIRTH + 3600,
+            permissions: vec![Permission::Feed, Permission::Medicine],
+        };
+        handle(&mut deps, env_at("creator", BIRTH), msg).unwrap();
+
+        let query_msg = |address: &str, key: &str| QueryMsg::Caretakers {
+            address: HumanAddr::from(address),
+            key: key.to_string(),
+        };
+        let res = query(&deps, query_msg("creator", "key")).unwrap();
+        assert!(matches!(
+            from_binary(&res).unwrap(),
+            QueryAnswer::ViewingKeyError { .. }
+        ));
+
+        for sender in ["creator", "sitter"] {
+            let msg = HandleMsg::SetViewingKey {
+                key: "key".to_string(),
+                padding: None,
+            };
+            handle(&mut deps, env_at(sender, BIRTH), msg).unwrap();
+        }
+        let res = query(&deps, query_msg("sitter", "key"));
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+
+        let res = query(&deps, query_msg("creator", "key")).unwrap();
+        match from_binary(&res).unwrap() {
+            QueryAnswer::Caretakers { caretakers } => assert_eq!(
+                caretakers,
+                vec![CaretakerInfo {
+                    address: HumanAddr::from("sitter"),
+                    expires_at: BIRTH + 3600,
+                    permissions: vec![Permission::Feed, Permission::Medicine],
+                }]
+            ),
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    fn start_vacation(
+        deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>,
+        time: u64,
+        duration: Duration,
+        amount: u16,
+    ) -> StdResult<HandleResponse> {
+        receive(
+            deps,
+            time,
+            amount,
+            Some(ReceiveMsg::StartVacation { duration }),
+        )
+    }
+
+    fn status(deps: &Extern<MockStorage, MockApi, FactoryQuerier>, time: u64) -> QueryAnswer {
+        from_binary(&query(deps, QueryMsg::Status { time }).unwrap()).unwrap()
+    }
+
+    fn hungry_and_dead(
+        deps: &Extern<MockStorage, MockApi, FactoryQuerier>,
+        time: u64,
+    ) -> (bool, bool) {
+        match status(deps, time) {
+            QueryAnswer::Status {
+                is_hungry, is_dead, ..
+            } => (is_hungry, is_dead),
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    #[test]
+    fn vacation_freezes_the_clock() {
+        let (_, mut deps) = init_helper(Some(Duration::Hours(3)), Some(Duration::Hours(1)));
+        let start = BIRTH + 3600;
+        let res = start_vacation(&mut deps, start, Duration::Hours(10), 99);
+        assert_eq!(
+            extract_error_msg(res),
+            "You need 100 tokens for this vacation"
+        );
+        start_vacation(&mut deps, start, Duration::Hours(10), 100).unwrap();
+
+        let res = start_vacation(&mut deps, start + 60, Duration::Hours(1), 10);
+        assert_eq!(extract_error_msg(res), "Pet is already on vacation");
+        let res = feed(&mut deps, start + 5 * 3600);
+        assert_eq!(extract_error_msg(res), "Pet is on vacation");
+
+        // an hour passed before the vacation, the other two start when it ends
+        let back = start + 10 * 3600;
+        assert_eq!(hungry_and_dead(&deps, back - 1), (false, false));
+        assert_eq!(hungry_and_dead(&deps, back + 2 * 3600), (false, false));
+        assert_eq!(hungry_and_dead(&deps, back + 2 * 3600 + 1), (true, false));
+        assert_eq!(hungry_and_dead(&deps, back + 3 * 3600), (true, false));
+        assert_eq!(hungry_and_dead(&deps, back + 3 * 3600 + 1), (true, true));
+
+        feed(&mut deps, back + 3 * 3600).unwrap();
+        let state = pet_read(&deps.storage).load().unwrap();
+        assert!(state.vacations.is_empty());
+    }
+
+    #[test]
+    fn ending_vacation_early_restores_allowance() {
+        let (_, mut deps) = init_helper(Some(Duration::Hours(3)), Some(Duration::Hours(1)));
+        let start = BIRTH + 3600;
+        start_vacation(&mut deps, start, Duration::Days(2), 480).unwrap();
+        match status(&deps, start) {
+            QueryAnswer::Status {
+                vacation_until,
+                vacation_allowance,
+                ..
+            } => {
+                assert_eq!(vacation_until, Some(start + 2 * 86400));
+                assert_eq!(vacation_allowance, Seconds(12 * 86400));
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+
+        let res = handle(
+            &mut deps,
+            env_at("stranger", start + 3600),
+            HandleMsg::EndVacation {},
+        );
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        handle(
+            &mut deps,
+            env_at("creator", start + 3600),
+            HandleMsg::EndVacation {},
+        )
+        .unwrap();
+        let res = handle(
+            &mut deps,
+            env_at("creator", start + 3600),
+            HandleMsg::EndVacation {},
+        );
+        assert_eq!(extract_error_msg(res), "Pet is not on vacation");
+
+        match status(&deps, start + 3600) {
+            QueryAnswer::Status {
+                vacation_until,
+                vacation_allowance,
+                ..
+            } => {
+                assert_eq!(vacation_until, None);
+                assert_eq!(vacation_allowance, Seconds(14 * 86400 - 3600));
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+        // one hour before and one after the vacation
+        assert_eq!(hungry_and_dead(&deps, start + 3 * 3600), (false, false));
+        assert_eq!(hungry_and_dead(&deps, start + 3 * 3600 + 1), (true, false));
+    }
+
+    #[test]
+    fn vacation_allowance_is_yearly() {
+        let (_, mut deps) = init_helper(Some(Duration::Days(30)), Some(Duration::Days(30)));
+        start_vacation(&mut deps, BIRTH, Duration::Days(10), 2400).unwrap();
+        let res = start_vacation(&mut deps, BIRTH + 10 * 86400, Duration::Days(5), 1200);
+        assert_eq!(
+            extract_error_msg(res),
+            "Vacation allowance exceeded, 345600 seconds left this year"
+        );
+        start_vacation(&mut deps, BIRTH + 10 * 86400, Duration::Days(4), 960).unwrap();
+
+        let next_year = BIRTH + 365 * 86400;
+        pet(&mut deps.storage)
+            .update(|mut state| {
+                state.last_feed_time = next_year;
+                Ok(state)
+            })
+            .unwrap();
+        start_vacation(&mut deps, next_year, Duration::Days(14), 3360).unwrap();
+    }
+
+    #[test]
+    fn only_owner_starts_vacation() {
+        let (_, mut deps) = init_helper(None, None);
+        let msg = HandleMsg::AddCaretaker {
+            address: HumanAddr::from("sitter"),
+            expires_at: BIRTH + 86400,
+            permissions: vec![Permission::Feed, Permission::Revive],
+        };
+        handle(&mut deps, env_at("creator", BIRTH), msg).unwrap();
+        let vacation = ReceiveMsg::StartVacation {
+            duration: Duration::Hours(1),
+        };
+        let res = receive_from(&mut deps, "sitter", BIRTH, 10, Some(vacation));
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+    }
+
+    fn sickness(
+        deps: &Extern<MockStorage, MockApi, FactoryQuerier>,
+        time: u64,
+    ) -> (bool, Option<u64>) {
+        match from_binary(
+            &query(
+                deps,
+                QueryMsg::Sickness {
+                    address: HumanAddr::from("creator"),
+                    key: "key".to_string(),
+                    time,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        {
+            QueryAnswer::Sickness {
+                is_sick,
+                sick_since,
+                ..
+            } => (is_sick, sick_since),
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    #[test]
+    fn long_hunger_makes_pet_sick_until_cured() {
+        let (_, mut deps) = init_helper(Some(Duration::Hours(3)), Some(Duration::Hours(1)));
+        // feeding late in the starving window raises the risk
+        let late = 3 * 3600 + 45 * 60;
+        match from_binary(
+            &query(
+                &deps,
+                QueryMsg::Sickness {
+                    address: HumanAddr::from("creator"),
+                    key: "key".to_string(),
+                    time: BIRTH + late,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        {
+            QueryAnswer::Sickness {
+                risk, cure_costs, ..
+            } => {
+                assert_eq!(risk, 12);
+                assert_eq!(
+                    cure_costs,
+                    vec![CureCost {
+                        token: HumanAddr::from("food"),
+                        code_hash: "food_hash".to_string(),
+                        amount: Uint128(200),
+                    }]
+                );
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+
+        let mut time = BIRTH;
+        let mut fell_sick = None;
+        for _ in 0..100 {
+            time += late;
+            let res = feed(&mut deps, time).unwrap();
+            if res.log.iter().any(|attr| attr.key == "fell_sick") {
+                fell_sick = Some(time);
+                break;
+            }
+        }
+        let sick_at = fell_sick.expect("Pet never fell sick");
+        assert_eq!(sickness(&deps, sick_at), (true, Some(sick_at)));
+
+        // hunger grows twice as fast
+        assert_eq!(hungry_and_dead(&deps, sick_at + 5400), (false, false));
+        assert_eq!(hungry_and_dead(&deps, sick_at + 5401), (true, false));
+        assert_eq!(hungry_and_dead(&deps, sick_at + 7201), (true, true));
+
+        let medicine = Some(ReceiveMsg::Medicine {});
+        let cured_at = sick_at + 3600;
+        let res = receive_from(&mut deps, "stranger", cured_at, 200, medicine.clone());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = receive(&mut deps, cured_at, 199, medicine.clone());
+        assert_eq!(
+            extract_error_msg(res),
+            "You need more tokens to cure the pet"
+        );
+        receive(&mut deps, cured_at, 200, medicine.clone()).unwrap();
+        assert_eq!(sickness(&deps, cured_at), (false, None));
+        let res = receive(&mut deps, cured_at, 200, medicine);
+        assert_eq!(extract_error_msg(res), "Pet is not sick");
+
+        // two hours passed for the pet while it was sick
+        assert_eq!(hungry_and_dead(&deps, sick_at + 2 * 3600), (false, false));
+        assert_eq!(
+            hungry_and_dead(&deps, sick_at + 2 * 3600 + 1),
+            (true, false)
+        );
+
+        let msg = HandleMsg::SetViewingKey {
+            key: "key".to_string(),
+            padding: None,
+        };
+        handle(&mut deps, env_at("creator", BIRTH), msg).unwrap();
+        let msg = QueryMsg::History {
+            address: HumanAddr::from("creator"),
+            key: "key".to_string(),
+            page: None,
+            page_size: 3,
+        };
+        match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+            QueryAnswer::History { events, total } => {
+                let kinds: Vec<EventKind> = events.into_iter().map(|event| event.kind).collect();
+                assert_eq!(
+                    kinds,
+                    vec![EventKind::Cured, EventKind::FellSick, EventKind::Fed]
+                );
+                assert_eq!(total, (sick_at - BIRTH) / late + 2);
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+        let msg = QueryMsg::History {
+            address: HumanAddr::from("creator"),
+            key: "key".to_string(),
+            page: Some(u32::MAX),
+            page_size: 3,
+        };
+        match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+            QueryAnswer::History { events, .. } => assert!(events.is_empty()),
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    #[test]
+    fn pets_fall_sick_from_more_than_meals() {
+        let (_, mut deps) = init_with_difficulty(
+            Some(Difficulty::Normal),
+            None,
+            None,
+            Some(RuleOverrides {
+                sickness_risk: Some(100),
+                ..RuleOverrides::default()
+            }),
+        );
+        let res = rename(&mut deps, "creator", 50, "Mametchi").unwrap();
+        assert_eq!(
+            res.log,
+            vec![log("name", "Mametchi"), log("fell_sick", BIRTH + 60)]
+        );
+        assert_eq!(sickness(&deps, BIRTH + 60), (true, Some(BIRTH + 60)));
+        assert_eq!(latest_event(&mut deps, "creator").kind, EventKind::FellSick);
+
+        // a sick pet can't get any sicker, once cured it can catch something again
+        let res = clean(&mut deps, "creator", BIRTH + 120).unwrap();
+        assert_eq!(res.log, vec![log("cleaned_at", BIRTH + 120)]);
+        receive(&mut deps, BIRTH + 180, 200, Some(ReceiveMsg::Medicine {})).unwrap();
+        let res = clean(&mut deps, "creator", BIRTH + 240).unwrap();
+        assert!(res.log.contains(&log("fell_sick", BIRTH + 240)));
+        assert_eq!(sickness(&deps, BIRTH + 240), (true, Some(BIRTH + 240)));
+    }
+
+    #[test]
+    fn medicine_token_only_cures() {
+        let (_, mut deps) = init_helper(None, None);
+        let pill = |address: &str| HandleMsg::SetMedicineToken {
+            medicine: Some(MedicineToken {
+                address: HumanAddr::from(address),
+                code_hash: "pill_hash".to_string(),
+                price: Uint128(5),
+            }),
+        };
+        let res = handle(&mut deps, env_at("stranger", BIRTH), pill("pill"));
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = handle(&mut deps, env_at("creator", BIRTH), pill("food"));
+        assert_eq!(
+            extract_error_msg(res),
+            "Medicine token has to be different from FOOD"
+        );
+        let res = handle(&mut deps, env_at("creator", BIRTH), pill("pill")).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        match from_binary(
+            &query(
+                &deps,
+                QueryMsg::Sickness {
+                    address: HumanAddr::from("creator"),
+                    key: "key".to_string(),
+                    time: BIRTH,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        {
+            QueryAnswer::Sickness { cure_costs, .. } => {
+                let costs: Vec<(HumanAddr, Uint128)> = cure_costs
+                    .into_iter()
+                    .map(|cost| (cost.token, cost.amount))
+                    .collect();
+                assert_eq!(
+                    costs,
+                    vec![
+                        (HumanAddr::from("food"), Uint128(200)),
+                        (HumanAddr::from("pill"), Uint128(5)),
+                    ]
+                );
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+
+        let receive_pill = |msg: ReceiveMsg| HandleMsg::Receive {
+            sender: HumanAddr::from("creator"),
+            from: HumanAddr::from("creator"),
+            amount: Uint128(5),
+            msg: Some(to_binary(&msg).unwrap()),
+        };
+        let res = handle(
+            &mut deps,
+            env_at("pill", BIRTH + 3 * 3600 + 1),
+            receive_pill(ReceiveMsg::Feed {}),
+        );
+        assert_eq!(
+            extract_error_msg(res),
+            "Medicine tokens can only be used to cure the pet"
+        );
+
+        pet(&mut deps.storage)
+            .update(|mut state| {
+                state.illnesses.push(Illness {
+                    start: BIRTH,
+                    end: None,
+                });
+                Ok(state)
+            })
+            .unwrap();
+        let res = handle(
+            &mut deps,
+            env_at("pill", BIRTH + 60),
+            receive_pill(ReceiveMsg::Medicine {}),
+        )
+        .unwrap();
+        match &res.messages[0] {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => {
+                assert_eq!(contract_addr, &HumanAddr::from("pill"))
+            }
+            _ => panic!("Expected a burn message"),
+        }
+        assert_eq!(sickness(&deps, BIRTH + 60), (false, None));
+    }
+
+    fn clean(
+        deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>,
+        sender: &str,
+        time: u64,
+    ) -> StdResult<HandleResponse> {
+        handle(deps, env_at(sender, time), HandleMsg::Clean {})
+    }
+
+    fn stats(deps: &Extern<MockStorage, MockApi, FactoryQuerier>, time: u64) -> (u64, u64) {
+        match vitals(deps, "creator", time) {
+            QueryAnswer::Vitals {
+                cleanliness,
+                happiness,
+                ..
+            } => (cleanliness, happiness),
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    fn risk(deps: &Extern<MockStorage, MockApi, FactoryQuerier>, time: u64) -> u64 {
+        match from_binary(
+            &query(
+                deps,
+                QueryMsg::Sickness {
+                    address: HumanAddr::from("creator"),
+                    key: "key".to_string(),
+                    time,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        {
+            QueryAnswer::Sickness { risk, .. } => risk,
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    #[test]
+    fn stats_decay_and_meals_make_a_mess() {
+        let (_, mut deps) = init_helper(Some(Duration::Hours(3)), Some(Duration::Hours(1)));
+        assert_eq!(stats(&deps, BIRTH), (100, 100));
+        assert_eq!(stats(&deps, BIRTH + 3 * 3600), (94, 97));
+
+        feed(&mut deps, BIRTH + 3 * 3600 + 1).unwrap();
+        assert_eq!(stats(&deps, BIRTH + 3 * 3600 + 1), (78, 96));
+
+        // the mess doesn't grow on vacation
+        start_vacation(&mut deps, BIRTH + 4 * 3600, Duration::Hours(5), 50).unwrap();
+        assert_eq!(
+            stats(&deps, BIRTH + 9 * 3600),
+            stats(&deps, BIRTH + 4 * 3600)
+        );
+        let res = clean(&mut deps, "creator", BIRTH + 5 * 3600);
+        assert_eq!(extract_error_msg(res), "Pet is on vacation");
+    }
+
+    #[test]
+    fn several_days_of_care() {
+        let mut careful = init_helper(Some(Duration::Hours(3)), Some(Duration::Hours(1))).1;
+        let mut messy = init_helper(Some(Duration::Hours(3)), Some(Duration::Hours(1))).1;
+        // fed every three and a half hours, just before the hunger gets risky
+        let window = 3 * 3600 + 30 * 60;
+        let mut time = BIRTH;
+        for meal in 1..=20 {
+            time += window;
+            feed_and_cure(&mut careful, time);
+            feed_and_cure(&mut messy, time);
+            if meal % 4 == 0 {
+                // a dirty pet can catch something while it's cleaned, too
+                let res = clean(&mut careful, "creator", time).unwrap();
+                cure_if_sick(&mut careful, &res, time);
+            }
+            if meal == 8 {
+                assert_eq!(stats(&careful, time), (100, 66));
+                assert_eq!(stats(&messy, time), (0, 41));
+            }
+        }
+        // almost three days later the pet that's never cleaned is miserable
+        assert_eq!(stats(&careful, time), (100, 15));
+        assert_eq!(stats(&messy, time), (0, 0));
+        assert_eq!(risk(&careful, time + 3600), 2);
+        assert_eq!(risk(&messy, time + 3600), 12);
+    }
+
+    #[test]
+    fn caretakers_clean_with_permission() {
+        let (_, mut deps) = init_helper(None, None);
+        let add = |address: &str, permission| HandleMsg::AddCaretaker {
+            address: HumanAddr::from(address),
+            expires_at: BIRTH + 86400,
+            permissions: vec![permission],
+        };
+        handle(
+            &mut deps,
+            env_at("creator", BIRTH),
+            add("cleaner", Permission::Clean),
+        )
+        .unwrap();
+        handle(
+            &mut deps,
+            env_at("creator", BIRTH),
+            add("feeder", Permission::Feed),
+        )
+        .unwrap();
+
+        let res = clean(&mut deps, "stranger", BIRTH + 3600);
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = clean(&mut deps, "feeder", BIRTH + 3600);
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        clean(&mut deps, "cleaner", BIRTH + 3600).unwrap();
+        assert_eq!(stats(&deps, BIRTH + 3600), (100, 99));
+
+        let res = clean(&mut deps, "cleaner", BIRTH + 86400);
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = clean(&mut deps, "creator", BIRTH + 86400);
+        assert_eq!(extract_error_msg(res), "Pet is dead :(");
+    }
+
+    fn env_at_height(sender: &str, height: u64, time: u64) -> Env {
+        let mut env = env_at(sender, time);
+        env.block.height = height;
+        env
+    }
+
+    fn commit_play(
+        deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>,
+        height: u64,
+        time: u64,
+        guess: Direction,
+    ) -> StdResult<HandleResponse> {
+        let mut preimage = guess.name().as_bytes().to_vec();
+        preimage.extend_from_slice(b"salt");
+        let msg = HandleMsg::Receive {
+            sender: HumanAddr::from("creator"),
+            from: HumanAddr::from("creator"),
+            amount: Uint128(TOKENS_PER_PLAY as u128),
+            msg: Some(
+                to_binary(&ReceiveMsg::Play {
+                    commitment: Binary(sha_256(&preimage).to_vec()),
+                })
+                .unwrap(),
+            ),
+        };
+        handle(deps, env_at_height("food", height, time), msg)
+    }
+
+    fn reveal_play(
+        deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>,
+        sender: &str,
+        height: u64,
+        time: u64,
+        guess: Direction,
+        salt: &str,
+    ) -> StdResult<HandleResponse> {
+        let msg = HandleMsg::RevealPlay {
+            guess,
+            salt: Binary::from(salt.as_bytes()),
+        };
+        handle(deps, env_at_height(sender, height, time), msg)
+    }
+
+    fn happiness_and_weight(
+        deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>,
+        time: u64,
+    ) -> (u64, u64) {
+        let happiness = match vitals(deps, "creator", time) {
+            QueryAnswer::Vitals { happiness, .. } => happiness,
+            _ => panic!("Unexpected query answer"),
+        };
+        let (weight, _, _) = fitness(deps);
+        (happiness, weight)
+    }
+
+    #[test]
+    fn play_is_revealed_in_a_later_block() {
+        let (_, mut deps) = init_helper(Some(Duration::Days(1)), Some(Duration::Days(1)));
+        let start = BIRTH + 20 * 3600;
+        assert_eq!(happiness_and_weight(&mut deps, start), (80, 1000));
+
+        commit_play(&mut deps, 12345, start, Direction::Left).unwrap();
+        let res = commit_play(&mut deps, 12345, start, Direction::Left);
+        assert_eq!(extract_error_msg(res), "A game is already in progress");
+        let res = reveal_play(&mut deps, "creator", 12345, start, Direction::Left, "salt");
+        assert_eq!(
+            extract_error_msg(res),
+            "The game has to be revealed in a later block"
+        );
+        let res = reveal_play(
+            &mut deps,
+            "stranger",
+            12346,
+            start + 6,
+            Direction::Left,
+            "salt",
+        );
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = reveal_play(
+            &mut deps,
+            "creator",
+            12346,
+            start + 6,
+            Direction::Right,
+            "salt",
+        );
+        assert_eq!(extract_error_msg(res), "Guess doesn't match the commitment");
+
+        let res = reveal_play(
+            &mut deps,
+            "creator",
+            12346,
+            start + 6,
+            Direction::Left,
+            "salt",
+        )
+        .unwrap();
+        assert_eq!(res.log, vec![log("pet_move", "left"), log("won", true)]);
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(happiness_and_weight(&mut deps, start + 6), (99, 990));
+
+        // the pet runs left again, so guessing right loses
+        commit_play(&mut deps, 12347, start + 12, Direction::Right).unwrap();
+        let res = reveal_play(
+            &mut deps,
+            "creator",
+            12349,
+            start + 24,
+            Direction::Right,
+            "salt",
+        )
+        .unwrap();
+        assert_eq!(res.log, vec![log("pet_move", "left"), log("won", false)]);
+        assert_eq!(happiness_and_weight(&mut deps, start + 24), (100, 970));
+        let res = reveal_play(
+            &mut deps,
+            "creator",
+            12350,
+            start + 30,
+            Direction::Right,
+            "salt",
+        );
+        assert_eq!(extract_error_msg(res), "There is no game to reveal");
+    }
+
+    #[test]
+    fn expired_play_is_reclaimed() {
+        let (_, mut deps) = init_helper(None, None);
+        let res = commit_play(&mut deps, 12345, BIRTH + 5 * 3600, Direction::Left);
+        assert_eq!(extract_error_msg(res), "Pet is dead :(");
+
+        commit_play(&mut deps, 12345, BIRTH, Direction::Left).unwrap();
+        let reclaim = |deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>, sender, height| {
+            handle(
+                deps,
+                env_at_height(sender, height, BIRTH + 600),
+                HandleMsg::ReclaimPlay {},
+            )
+        };
+        let res = reclaim(&mut deps, "creator", 12445);
+        assert_eq!(extract_error_msg(res), "The game can still be revealed");
+        let res = reveal_play(
+            &mut deps,
+            "creator",
+            12446,
+            BIRTH + 600,
+            Direction::Left,
+            "salt",
+        );
+        assert_eq!(
+            extract_error_msg(res),
+            "The game expired, reclaim the stake instead"
+        );
+        let res = reclaim(&mut deps, "stranger", 12446);
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+
+        let res = reclaim(&mut deps, "creator", 12446).unwrap();
+        match &res.messages[0] {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => {
+                assert_eq!(contract_addr, &HumanAddr::from("food"))
+            }
+            _ => panic!("Expected a transfer message"),
+        }
+        match from_binary(&query(&deps, QueryMsg::Play {}).unwrap()).unwrap() {
+            QueryAnswer::Play { pending } => assert_eq!(pending, None),
+            _ => panic!("Unexpected query answer"),
+        }
+        let res = reclaim(&mut deps, "creator", 12446);
+        assert_eq!(extract_error_msg(res), "There is no game to reclaim");
+    }
+
+    fn set_feeding_mode(
+        deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>,
+        sender: &str,
+        mode: FeedingMode,
+    ) -> StdResult<HandleResponse> {
+        handle(
+            deps,
+            env_at(sender, BIRTH),
+            HandleMsg::SetFeedingMode { mode },
+        )
+    }
+
+    fn vitals(
+        deps: &Extern<MockStorage, MockApi, FactoryQuerier>,
+        owner: &str,
+        time: u64,
+    ) -> QueryAnswer {
+        let msg = QueryMsg::Vitals {
+            address: HumanAddr::from(owner),
+            key: "key".to_string(),
+            time,
+        };
+        from_binary(&query(deps, msg).unwrap()).unwrap()
+    }
+
+    fn fitness(deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>) -> (u64, u64, bool) {
+        match vitals(deps, "creator", BIRTH) {
+            QueryAnswer::Vitals {
+                weight,
+                health,
+                is_obese,
+                ..
+            } => (weight, health, is_obese),
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    #[test]
+    fn owner_chooses_the_feeding_mode() {
+        let (_, mut deps) = init_helper(None, None);
+        let res = feed(&mut deps, BIRTH + 60);
+        assert_eq!(extract_error_msg(res), "Pet is not hungry");
+
+        let res = set_feeding_mode(&mut deps, "stranger", FeedingMode::Soft);
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        set_feeding_mode(&mut deps, "creator", FeedingMode::Soft).unwrap();
+        let res = receive(&mut deps, BIRTH + 60, 99, None);
+        assert_eq!(
+            extract_error_msg(res),
+            "You need more tokens to feed the pet"
+        );
+        feed_and_cure(&mut deps, BIRTH + 60);
+
+        set_feeding_mode(&mut deps, "creator", FeedingMode::Strict).unwrap();
+        let res = feed(&mut deps, BIRTH + 120);
+        assert_eq!(extract_error_msg(res), "Pet is not hungry");
+        assert_eq!(fitness(&mut deps), (1100, 90, false));
+    }
+
+    #[test]
+    fn overfed_pet_needs_exercise() {
+        let (_, mut deps) = init_helper(Some(Duration::Hours(3)), Some(Duration::Hours(1)));
+        set_feeding_mode(&mut deps, "creator", FeedingMode::Soft).unwrap();
+        for minute in 1..=5 {
+            let res = feed_and_cure(&mut deps, BIRTH + minute * 60);
+            assert!(res.log.iter().any(|attr| attr.key == "overfed"));
+        }
+        let time = BIRTH + 300;
+        assert_eq!(fitness(&mut deps), (1500, 50, true));
+        // overeating doesn't make the pet any less hungry
+        let state = pet_read(&deps.storage).load().unwrap();
+        assert_eq!(state.last_feed_time, BIRTH);
+        assert_eq!(state.feed_count, 0);
+
+        let res = clean(&mut deps, "creator", time).unwrap();
+        cure_if_sick(&mut deps, &res, time);
+        match from_binary(
+            &query(
+                &deps,
+                QueryMsg::Sickness {
+                    address: HumanAddr::from("creator"),
+                    key: "key".to_string(),
+                    time,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        {
+            QueryAnswer::Sickness { risk, .. } => assert_eq!(risk, 22),
+            _ => panic!("Unexpected query answer"),
+        }
+
+        // a proper meal restores some health, but not the figure
+        let meal_time = BIRTH + 3 * 3600 + 1;
+        feed_and_cure(&mut deps, meal_time);
+        assert_eq!(fitness(&mut deps), (1500, 55, true));
+
+        commit_play(&mut deps, 12345, meal_time, Direction::Left).unwrap();
+        reveal_play(
+            &mut deps,
+            "creator",
+            12346,
+            meal_time + 6,
+            Direction::Left,
+            "salt",
+        )
+        .unwrap();
+        let (weight, _, is_obese) = fitness(&mut deps);
+        assert!(weight < 1500);
+        assert!(!is_obese);
+    }
+
+    fn tick(deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>, time: u64) -> HandleResponse {
+        handle(deps, env_at("anyone", time), HandleMsg::Tick {}).unwrap()
+    }
+
+    fn memorials(
+        deps: &Extern<MockStorage, MockApi, FactoryQuerier>,
+        msg: QueryMsg,
+    ) -> Vec<Memorial> {
+        match from_binary(&query(deps, msg).unwrap()).unwrap() {
+            QueryAnswer::Memorials { memorials, .. } => memorials,
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    #[test]
+    fn dead_pets_get_memorials() {
+        let (_, mut deps) = init_helper(None, None);
+        assert!(tick(&mut deps, BIRTH + 4 * 3600).log.is_empty());
+        let first_death = BIRTH + 4 * 3600 + 1;
+        let res = tick(&mut deps, BIRTH + 5 * 3600);
+        assert_eq!(res.log[0], log("died_at", first_death));
+        // only the first one to notice writes the memorial
+        assert!(tick(&mut deps, BIRTH + 5 * 3600).log.is_empty());
+        let res = feed(&mut deps, BIRTH + 5 * 3600).unwrap();
+        assert!(res.log.is_empty());
+
+        let revived_at = BIRTH + 6 * 3600;
+        receive(&mut deps, revived_at, 500, Some(ReceiveMsg::Revive {})).unwrap();
+        let fed_at = revived_at + 3 * 3600 + 1;
+        feed_and_cure(&mut deps, fed_at);
+        let second_death = fed_at + 4 * 3600 + 1;
+        // reviving writes the memorial if nobody checked on the pet
+        let res = receive(
+            &mut deps,
+            second_death + 60,
+            500,
+            Some(ReceiveMsg::Revive {}),
+        )
+        .unwrap();
+        assert_eq!(res.log[0], log("died_at", second_death));
+
+        let graveyard = QueryMsg::Graveyard {
+            page: None,
+            page_size: 10,
+        };
+        let memorials_by_death = memorials(&deps, graveyard);
+        assert_eq!(memorials_by_death.len(), 2);
+        let (latest, first) = (&memorials_by_death[0], &memorials_by_death[1]);
+        assert_eq!(first.owner, HumanAddr::from("creator"));
+        assert_eq!(first.born_at, BIRTH);
+        assert_eq!(first.lifespan, 4 * 3600 + 1);
+        assert_eq!(first.food_eaten, Uint128(0));
+        assert_eq!(first.cause, CauseOfDeath::Starvation);
+        assert_eq!(latest.born_at, revived_at);
+        assert_eq!(latest.died_at, second_death);
+        assert_eq!(latest.food_eaten, Uint128(TOKENS_PER_FEEDING as u128));
+        let graveyard = QueryMsg::Graveyard {
+            page: Some(1),
+            page_size: 1,
+        };
+        assert_eq!(memorials(&deps, graveyard), vec![first.clone()]);
+
+        let hall_of_fame = QueryMsg::HallOfFame {
+            page: None,
+            page_size: 1,
+        };
+        assert_eq!(memorials(&deps, hall_of_fame), vec![latest.clone()]);
+
+        // pages too far out are empty instead of overflowing
+        let graveyard = QueryMsg::Graveyard {
+            page: Some(u32::MAX),
+            page_size: 10,
+        };
+        assert!(memorials(&deps, graveyard).is_empty());
+        let hall_of_fame = QueryMsg::HallOfFame {
+            page: Some(u32::MAX),
+            page_size: 10,
+        };
+        assert!(memorials(&deps, hall_of_fame).is_empty());
+    }
+
+    fn difficulty(deps: &Extern<MockStorage, MockApi, FactoryQuerier>) -> Difficulty {
+        match from_binary(
+            &query(
+                deps,
+                QueryMsg::Rules {
+                    address: HumanAddr::from("creator"),
+                    key: "key".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        {
+            QueryAnswer::Rules { difficulty, .. } => difficulty,
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    #[test]
+    fn difficulty_presets_and_overrides() {
+        let (_, deps) = init_with_difficulty(Some(Difficulty::Hardcore), None, None, None);
+        match from_binary(
+            &query(
+                &deps,
+                QueryMsg::Rules {
+                    address: HumanAddr::from("creator"),
+                    key: "key".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        {
+            QueryAnswer::Rules {
+                difficulty,
+                satiated_interval,
+                starving_interval,
+                rules,
+            } => {
+                assert_eq!(difficulty, Difficulty::Hardcore);
+                assert_eq!(satiated_interval, Seconds(3600));
+                assert_eq!(starving_interval, Seconds(1800));
+                assert_eq!(rules.feeding_cost, Uint128(200));
+                assert!(!rules.revive_allowed);
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+
+        let (res, _) = init_with_difficulty(Some(Difficulty::Custom), None, None, None);
+        assert_eq!(
+            extract_error_msg(res),
+            "Pick a preset difficulty, overriding its rules makes it custom"
+        );
+        let overrides = |overrides: RuleOverrides| {
+            init_with_difficulty(Some(Difficulty::Hardcore), None, None, Some(overrides))
+        };
+        let (res, _) = overrides(RuleOverrides {
+            cleanliness_decay_per_hour: Some(0),
+            ..RuleOverrides::default()
+        });
+        assert_eq!(
+            extract_error_msg(res),
+            "Decay rates must be between 1 and 25 points per hour"
+        );
+        let (res, _) = overrides(RuleOverrides {
+            sickness_risk: Some(101),
+            ..RuleOverrides::default()
+        });
+        assert_eq!(
+            extract_error_msg(res),
+            "Sickness risk can't be over 100 percent"
+        );
+        let (res, _) = overrides(RuleOverrides {
+            feeding_cost: Some(Uint128(0)),
+            ..RuleOverrides::default()
+        });
+        assert_eq!(
+            extract_error_msg(res),
+            "Feeding cost must be greater than zero"
+        );
+
+        // harder than the preset still ranks with it, easier doesn't
+        let (_, deps) = overrides(RuleOverrides {
+            feeding_cost: Some(Uint128(300)),
+            sickness_risk: Some(20),
+            ..RuleOverrides::default()
+        });
+        assert_eq!(difficulty(&deps), Difficulty::Hardcore);
+        let (_, deps) = overrides(RuleOverrides {
+            revive_allowed: Some(true),
+            ..RuleOverrides::default()
+        });
+        assert_eq!(difficulty(&deps), Difficulty::Custom);
+        let (_, deps) =
+            init_with_difficulty(Some(Difficulty::Easy), Some(Duration::Hours(7)), None, None);
+        assert_eq!(difficulty(&deps), Difficulty::Custom);
+
+        let (_, mut deps) = init_with_difficulty(Some(Difficulty::Hard), None, None, None);
+        let update = |satiated_interval| HandleMsg::UpdateIntervals {
+            satiated_interval,
+            starving_interval: Duration::Hours(1),
+        };
+        handle(
+            &mut deps,
+            env_at("creator", BIRTH),
+            update(Duration::Hours(1)),
+        )
+        .unwrap();
+        assert_eq!(difficulty(&deps), Difficulty::Hard);
+        handle(
+            &mut deps,
+            env_at("creator", BIRTH),
+            update(Duration::Hours(3)),
+        )
+        .unwrap();
+        assert_eq!(difficulty(&deps), Difficulty::Custom);
+        handle(
+            &mut deps,
+            env_at("creator", BIRTH),
+            update(Duration::Hours(2)),
+        )
+        .unwrap();
+        assert_eq!(difficulty(&deps), Difficulty::Custom);
+    }
+
+    #[test]
+    fn hardcore_pets_stay_dead() {
+        let (_, mut deps) = init_with_difficulty(Some(Difficulty::Hardcore), None, None, None);
+        // the sickness roll at this time spares the pet
+        let hungry_at = BIRTH + 3602;
+        let res = feed(&mut deps, hungry_at);
+        assert_eq!(
+            extract_error_msg(res),
+            "You need more tokens to feed the pet"
+        );
+        receive(&mut deps, hungry_at, 200, None).unwrap();
+
+        let dead_at = hungry_at + 2 * 3600;
+        let res = receive(&mut deps, dead_at, 500, Some(ReceiveMsg::Revive {}));
+        assert_eq!(extract_error_msg(res), "This pet can't be revived");
+        tick(&mut deps, dead_at);
+        let graveyard = QueryMsg::Graveyard {
+            page: None,
+            page_size: 1,
+        };
+        let memorial = &memorials(&deps, graveyard)[0];
+        assert_eq!(memorial.difficulty, Difficulty::Hardcore);
+        assert_eq!(memorial.food_eaten, Uint128(200));
+        assert_eq!(memorial.died_at, hungry_at + 5401);
+    }
+
+    fn profile(deps: &Extern<MockStorage, MockApi, FactoryQuerier>) -> QueryAnswer {
+        from_binary(&query(deps, QueryMsg::Profile {}).unwrap()).unwrap()
+    }
+
+    fn rename(
+        deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>,
+        from: &str,
+        amount: u16,
+        name: &str,
+    ) -> StdResult<HandleResponse> {
+        let msg = ReceiveMsg::Rename {
+            name: name.to_string(),
+        };
+        receive_from(deps, from, BIRTH + 60, amount, Some(msg))
+    }
+
+    #[test]
+    fn profile_shows_only_public_fields() {
+        let (_, mut deps) = init_helper(None, None);
+        let set_profile = |avatar: &str| HandleMsg::SetProfile {
+            bio: Some("Likes cake".to_string()),
+            avatar: Some(avatar.to_string()),
+            public: vec![ProfileField::Avatar, ProfileField::Difficulty],
+        };
+        let res = handle(
+            &mut deps,
+            env_at("stranger", BIRTH),
+            set_profile("ipfs://tama"),
+        );
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = handle(
+            &mut deps,
+            env_at("creator", BIRTH),
+            set_profile("ftp://tama"),
+        );
+        assert_eq!(
+            extract_error_msg(res),
+            "Avatar has to be an https or ipfs URI of up to 256 bytes"
+        );
+        handle(
+            &mut deps,
+            env_at("creator", BIRTH),
+            set_profile("ipfs://tama"),
+        )
+        .unwrap();
+        assert_eq!(
+            profile(&deps),
+            QueryAnswer::Profile {
+                name: "Tama".to_string(),
+                bio: None,
+                avatar: Some("ipfs://tama".to_string()),
+                alive_since: None,
+                difficulty: Some(Difficulty::Normal),
+                achievements: None,
+            }
+        );
+    }
+
+    #[test]
+    fn private_stats_need_the_owners_key() {
+        let (_, mut deps) = init_helper(None, None);
+        let msg = HandleMsg::SetViewingKey {
+            key: "stranger_key".to_string(),
+            padding: None,
+        };
+        handle(&mut deps, env_at("stranger", BIRTH), msg).unwrap();
+        let private_queries = |address: &str, key: &str| {
+            let (address, key) = (HumanAddr::from(address), key.to_string());
+            vec![
+                QueryMsg::Vitals {
+                    address: address.clone(),
+                    key: key.clone(),
+                    time: BIRTH,
+                },
+                QueryMsg::Achievements {
+                    address: address.clone(),
+                    key: key.clone(),
+                },
+                QueryMsg::Rules {
+                    address: address.clone(),
+                    key: key.clone(),
+                },
+                QueryMsg::Sickness {
+                    address,
+                    key,
+                    time: BIRTH,
+                },
+            ]
+        };
+        for msg in private_queries("stranger", "stranger_key") {
+            let res = query(&deps, msg);
+            assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        }
+        for msg in private_queries("creator", "guess") {
+            let res = from_binary(&query(&deps, msg).unwrap()).unwrap();
+            assert!(matches!(res, QueryAnswer::ViewingKeyError { .. }));
+        }
+        for msg in private_queries("creator", "key") {
+            let res = from_binary(&query(&deps, msg).unwrap()).unwrap();
+            assert!(!matches!(res, QueryAnswer::ViewingKeyError { .. }));
+        }
+        // the profile only shows what the owner made public
+        assert_eq!(
+            profile(&deps),
+            QueryAnswer::Profile {
+                name: "Tama".to_string(),
+                bio: None,
+                avatar: None,
+                alive_since: None,
+                difficulty: None,
+                achievements: None,
+            }
+        );
+    }
+
+    #[test]
+    fn renaming_costs_food() {
+        let (_, mut deps) = init_helper(None, None);
+        let res = rename(&mut deps, "stranger", 50, "Mametchi");
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        for name in ["Ta", "Tama!", " Tama", "Tama\n"] {
+            let res = rename(&mut deps, "creator", 50, name);
+            assert_eq!(
+                extract_error_msg(res),
+                "Name is not in the expected format (3-30 letters, digits, spaces, dashes or underscores)"
+            );
+        }
+        let res = rename(&mut deps, "creator", 49, "Mametchi");
+        assert_eq!(
+            extract_error_msg(res),
+            "You need 50 tokens to rename the pet"
+        );
+        let res = rename(&mut deps, "creator", 50, "Mametchi").unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.log, vec![log("name", "Mametchi")]);
+        match profile(&deps) {
+            QueryAnswer::Profile { name, .. } => assert_eq!(name, "Mametchi"),
+            _ => panic!("Unexpected query answer"),
+        }
+
+        tick(&mut deps, BIRTH + 5 * 3600);
+        let graveyard = QueryMsg::Graveyard {
+            page: None,
+            page_size: 1,
+        };
+        assert_eq!(memorials(&deps, graveyard)[0].name, "Mametchi");
+    }
+
+    fn abandon(
+        deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>,
+        sender: &str,
+        time: u64,
+    ) -> StdResult<HandleResponse> {
+        handle(deps, env_at(sender, time), HandleMsg::Abandon {})
+    }
+
+    fn adopt(
+        deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>,
+        time: u64,
+        amount: u16,
+    ) -> StdResult<HandleResponse> {
+        receive_from(deps, "adopter", time, amount, Some(ReceiveMsg::Adopt {}))
+    }
+
+    #[test]
+    fn abandoned_pets_wait_for_adoption() {
+        let (_, mut deps) = init_helper(None, None);
+        let res = abandon(&mut deps, "stranger", BIRTH + 3600);
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = adopt(&mut deps, BIRTH + 3600, 300);
+        assert_eq!(extract_error_msg(res), "Pet is not up for adoption");
+
+        let res = abandon(&mut deps, "creator", BIRTH + 3600).unwrap();
+        let deadline = BIRTH + 3600 + 7 * 86400;
+        assert_eq!(res.log, vec![log("adoption_deadline", deadline)]);
+        let res = set_feeding_mode(&mut deps, "creator", FeedingMode::Soft);
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+
+        // the clock stops in the pool
+        let adopted_at = BIRTH + 3 * 86400;
+        assert_eq!(hungry_and_dead(&deps, adopted_at), (false, false));
+        match status(&deps, adopted_at) {
+            QueryAnswer::Status {
+                adoption_deadline, ..
+            } => assert_eq!(adoption_deadline, Some(deadline)),
+            _ => panic!("Unexpected query answer"),
+        }
+        let res = adopt(&mut deps, adopted_at, 299);
+        assert_eq!(
+            extract_error_msg(res),
+            "You need 300 tokens to adopt the pet"
+        );
+        let res = adopt(&mut deps, adopted_at, 300).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.log, vec![log("adopted_by", "adopter")]);
+        let res = adopt(&mut deps, adopted_at, 300);
+        assert_eq!(extract_error_msg(res), "Pet is not up for adoption");
+
+        set_feeding_mode(&mut deps, "adopter", FeedingMode::Soft).unwrap();
+        // it runs again for the new owner
+        assert_eq!(
+            hungry_and_dead(&deps, adopted_at + 2 * 3600),
+            (false, false)
+        );
+        assert_eq!(
+            hungry_and_dead(&deps, adopted_at + 2 * 3600 + 1),
+            (true, false)
+        );
+    }
+
+    #[test]
+    fn unadopted_pets_go_to_the_shelter() {
+        let (_, mut deps) = init_helper(None, None);
+        let shelter = deps
+            .api
+            .canonical_address(&HumanAddr::from("shelter"))
+            .unwrap();
+        pet(&mut deps.storage)
+            .update(|mut state| {
+                state.admin = shelter;
+                Ok(state)
+            })
+            .unwrap();
+        abandon(&mut deps, "creator", BIRTH).unwrap();
+        let deadline = BIRTH + 7 * 86400;
+        assert!(tick(&mut deps, deadline - 1).log.is_empty());
+        let res = adopt(&mut deps, deadline, 300);
+        assert_eq!(extract_error_msg(res), "Pet is not up for adoption");
+
+        let res = tick(&mut deps, deadline + 10);
+        assert_eq!(res.log, vec![log("sheltered_at", deadline)]);
+        assert!(tick(&mut deps, deadline + 20).log.is_empty());
+        set_feeding_mode(&mut deps, "shelter", FeedingMode::Soft).unwrap();
+        assert_eq!(
+            hungry_and_dead(&deps, deadline + 4 * 3600 + 1),
+            (true, true)
+        );
+    }
+
+    fn latest_event(deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>, owner: &str) -> Event {
+        let msg = HandleMsg::SetViewingKey {
+            key: "key".to_string(),
+            padding: None,
+        };
+        handle(deps, env_at(owner, BIRTH), msg).unwrap();
+        let msg = QueryMsg::History {
+            address: HumanAddr::from(owner),
+            key: "key".to_string(),
+            page: None,
+            page_size: 1,
+        };
+        match from_binary(&query(deps, msg).unwrap()).unwrap() {
+            QueryAnswer::History { mut events, .. } => events.remove(0),
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    fn happiness(
+        deps: &Extern<MockStorage, MockApi, FactoryQuerier>,
+        owner: &str,
+        time: u64,
+    ) -> u64 {
+        match vitals(deps, owner, time) {
+            QueryAnswer::Vitals { happiness, .. } => happiness,
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    fn next_visit_at(
+        deps: &Extern<MockStorage, MockApi, FactoryQuerier>,
+        time: u64,
+    ) -> Option<u64> {
+        match from_binary(&query(deps, QueryMsg::Friends { time }).unwrap()).unwrap() {
+            QueryAnswer::Friends { next_visit_at, .. } => next_visit_at,
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    fn join_factory(deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>, pet_id: u64) {
+        pet(&mut deps.storage)
+            .update(|mut state| {
+                state.factory = Some(FactoryInfo {
+                    code_hash: "factory_hash".to_string(),
+                    address: HumanAddr::from("factory"),
+                    pet_id,
+                });
+                Ok(state)
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn friends_visit_each_other() {
+        // pet A lives at the mock contract address, pet B belongs to bob
+        let (_, mut guest) = init_helper(Some(Duration::Days(1)), Some(Duration::Days(1)));
+        let (_, mut host) = init_helper(Some(Duration::Days(1)), Some(Duration::Days(1)));
+        let bob = host.api.canonical_address(&HumanAddr::from("bob")).unwrap();
+        pet(&mut host.storage)
+            .update(|mut state| {
+                state.owner = bob;
+                Ok(state)
+            })
+            .unwrap();
+        let start = BIRTH + 20 * 3600;
+        let visit = || HandleMsg::Visit {
+            pet: HumanAddr::from("pet_b"),
+            code_hash: "pet_hash".to_string(),
+        };
+        let res = handle(&mut guest, env_at("bob", start), visit());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+
+        // nobody could vouch for a pet without a factory
+        let res = handle(&mut guest, env_at("creator", start), visit());
+        assert_eq!(extract_error_msg(res), "Only pets from a factory can visit");
+        join_factory(&mut guest, 0);
+        join_factory(&mut host, 1);
+
+        assert_eq!(happiness(&guest, "creator", start), 80);
+        let res = handle(&mut guest, env_at("creator", start), visit()).unwrap();
+        let message = FactoryHandleMsg::VisitPet {
+            pet_id: 0,
+            host: HumanAddr::from("pet_b"),
+            host_code_hash: "pet_hash".to_string(),
+        }
+        .to_cosmos_msg("factory_hash".to_string(), HumanAddr::from("factory"), None)
+        .unwrap();
+        assert_eq!(res.messages, vec![message]);
+        assert_eq!(happiness(&guest, "creator", start), 90);
+        assert_eq!(next_visit_at(&guest, start + 60), Some(start + 3600));
+        let res = handle(&mut guest, env_at("creator", start + 60), visit());
+        assert_eq!(
+            extract_error_msg(res),
+            format!(
+                "Pet needs some rest after its last visit, try again at {}",
+                start + 3600
+            )
+        );
+        let event = latest_event(&mut guest, "creator");
+        assert_eq!(
+            event.kind,
+            EventKind::Visited {
+                pet: HumanAddr::from("pet_b")
+            }
+        );
+
+        // the host only lets friends in, which fails the guest's visit as well
+        let receive_visit = || HandleMsg::ReceiveVisit {
+            guest: HumanAddr::from("cosmos2contract"),
+        };
+        let res = handle(&mut host, env_at("factory", start), receive_visit());
+        assert_eq!(extract_error_msg(res), "Only friends can visit the pet");
+        let add_friend = HandleMsg::AddFriend {
+            pet: HumanAddr::from("cosmos2contract"),
+        };
+        let res = handle(&mut host, env_at("creator", start), add_friend.clone());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        handle(&mut host, env_at("bob", start), add_friend).unwrap();
+        let msg = HandleMsg::SetViewingKey {
+            key: "key".to_string(),
+            padding: None,
+        };
+        handle(&mut host, env_at("bob", start), msg).unwrap();
+        // a friend listed address can't skip the factory, even if it's the guest's
+        let res = handle(&mut host, env_at("cosmos2contract", start), receive_visit());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        let res = handle(&mut host, env_at("factory", start), receive_visit()).unwrap();
+        assert_eq!(res.log, vec![log("visited_by", "cosmos2contract")]);
+        assert_eq!(happiness(&host, "bob", start), 90);
+        let event = latest_event(&mut host, "bob");
+        assert_eq!(
+            event.kind,
+            EventKind::VisitedBy {
+                pet: HumanAddr::from("cosmos2contract")
+            }
+        );
+        assert_eq!(event.by, None);
+
+        // the cooldown is over, but the friendship isn't
+        let later = start + 3600;
+        assert_eq!(next_visit_at(&host, later), None);
+        let remove_friend = |pet: &str| HandleMsg::RemoveFriend {
+            pet: HumanAddr::from(pet),
+        };
+        let res = handle(&mut host, env_at("bob", later), remove_friend("stranger"));
+        assert_eq!(extract_error_msg(res), "Pet is not a friend");
+        handle(
+            &mut host,
+            env_at("bob", later),
+            remove_friend("cosmos2contract"),
+        )
+        .unwrap();
+        match from_binary(&query(&host, QueryMsg::Friends { time: later }).unwrap()).unwrap() {
+            QueryAnswer::Friends { friends, .. } => assert!(friends.is_empty()),
+            _ => panic!("Unexpected query answer"),
+        }
+        let res = handle(&mut host, env_at("factory", later), receive_visit());
+        assert_eq!(extract_error_msg(res), "Only friends can visit the pet");
+    }
+
+    #[test]
+    fn anyone_can_gift_food_to_a_hungry_pet() {
+        let (_, mut deps) = init_helper(None, None);
+        set_feeding_mode(&mut deps, "creator", FeedingMode::Soft).unwrap();
+        let gift = |deps: &mut Extern<MockStorage, MockApi, FactoryQuerier>, time| {
+            receive_from(
+                deps,
+                "stranger",
+                time,
+                TOKENS_PER_FEEDING,
+                Some(ReceiveMsg::GiftFood {}),
+            )
+        };
+        // unlike the owner, a stranger can't overfeed the pet
+        let res = gift(&mut deps, BIRTH + 60);
+        assert_eq!(extract_error_msg(res), "Pet is not hungry");
+
+        let hungry_at = BIRTH + 3 * 3600 + 1;
+        gift(&mut deps, hungry_at).unwrap();
+        assert_eq!(hungry_and_dead(&deps, hungry_at), (false, false));
+        let event = latest_event(&mut deps, "creator");
+        assert_eq!(event.kind, EventKind::GiftedFood);
+        assert_eq!(event.by, Some(HumanAddr::from("stranger")));
+    }
+
+    fn world_event(
+        name: &str,
+        start: u64,
+        end: u64,
+        hunger_rate: u64,
+        cost_rate: u64,
+    ) -> WorldEvent {
+        WorldEvent {
+            name: name.to_string(),
+            start,
+            end,
+            hunger_rate,
+            cost_rate,
+        }
+    }
+
+    fn world_events(
+        deps: &Extern<MockStorage, MockApi, FactoryQuerier>,
+        time: u64,
+    ) -> (Vec<String>, Vec<String>, Uint128) {
+        match from_binary(&query(deps, QueryMsg::WorldEvents { time }).unwrap()).unwrap() {
+            QueryAnswer::WorldEvents {
+                active,
+                upcoming,
+                feeding_cost,
+            } => {
+                let names = |events: Vec<WorldEvent>| -> Vec<String> {
+                    events.into_iter().map(|event| event.name).collect()
+                };
+                (names(active), names(upcoming), feeding_cost)
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+    }
+
+    #[test]
+    fn pets_go_by_the_factory_schedule() {
+        let (_, mut deps) = init_helper(None, None);
+        let hour = 3600;
+        deps.querier.events = vec![
+            world_event("heatwave", BIRTH + hour, BIRTH + 3 * hour, 150, 200),
+            world_event("festival", BIRTH + 2 * hour, BIRTH + 5 * hour, 100, 50),
+        ];
+        // the factory's schedule is only for its own pets
+        assert_eq!(
+            world_events(&deps, BIRTH + hour),
+            (vec![], vec![], Uint128(100))
+        );
+        join_factory(&mut deps, 0);
+        let res = handle(
+            &mut deps,
+            env_at("creator", BIRTH),
+            HandleMsg::CancelWorldEvent {
+                name: "heatwave".to_string(),
+            },
+        );
+        assert_eq!(
+            extract_error_msg(res),
+            "Pets from a factory go by its world events"
+        );
+        assert_eq!(
+            world_events(&deps, BIRTH),
+            (
+                vec![],
+                vec!["heatwave".to_string(), "festival".to_string()],
+                Uint128(100)
+            )
+        );
+        assert_eq!(
+            world_events(&deps, BIRTH + hour),
+            (
+                vec!["heatwave".to_string()],
+                vec!["festival".to_string()],
+                Uint128(200)
+            )
+        );
+
+        // everything paid to the pet costs more in the heatwave, not just the meals
+        let res = receive(
+            &mut deps,
+            BIRTH + hour,
+            99,
+            Some(ReceiveMsg::Rename {
+                name: "Mametchi".to_string(),
+            }),
+        );
+        assert_eq!(
+            extract_error_msg(res),
+            "You need 100 tokens to rename the pet"
+        );
+        let msg = QueryMsg::Sickness {
+            address: HumanAddr::from("creator"),
+            key: "key".to_string(),
+            time: BIRTH + hour,
+        };
+        match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+            QueryAnswer::Sickness { cure_costs, .. } => {
+                assert_eq!(
+                    cure_costs[0].amount,
+                    Uint128(2 * TOKENS_PER_MEDICINE as u128)
+                )
+            }
+            _ => panic!("Unexpected query answer"),
+        }
+        let res = commit_play(&mut deps, 1, BIRTH + hour, Direction::Left);
+        assert_eq!(extract_error_msg(res), "You need 20 tokens to play");
+
+        // 1h at 100% and 1h at 150% make 2.5h, the heatwave makes the other 0.5h go by in 20 minutes
+        let hungry_at = BIRTH + 2 * hour + 1200;
+        assert_eq!(hungry_and_dead(&deps, hungry_at), (false, false));
+        assert_eq!(hungry_and_dead(&deps, hungry_at + 1), (true, false));
+
+        // the festival halves the doubled cost
+        let both = BIRTH + 2 * hour + 1800;
+        assert_eq!(
+            world_events(&deps, both),
+            (
+                vec!["heatwave".to_string(), "festival".to_string()],
+                vec![],
+                Uint128(100)
+            )
+        );
+        let res = receive(&mut deps, both, 99, None);
+        assert_eq!(
+            extract_error_msg(res),
+            "You need more tokens to feed the pet"
+        );
+        receive(&mut deps, both, 100, None).unwrap();
+        assert_eq!(
+            world_events(&deps, BIRTH + 3 * hour),
+            (vec!["festival".to_string()], vec![], Uint128(50))
+        );
+
+        // the factory cancels the festival, which cuts it short
+        deps.querier.events[1].end = BIRTH + 4 * hour;
+        assert_eq!(
+            world_events(&deps, BIRTH + 4 * hour),
+            (vec![], vec![], Uint128(100))
+        );
+        // fed with 1800s of the heatwave left, so the pet got 2700s hungrier by 3h
+        let hungry_at = BIRTH + 3 * hour + 3 * hour - 2700;
+        assert_eq!(hungry_and_dead(&deps, hungry_at), (false, false));
+        assert_eq!(hungry_and_dead(&deps, hungry_at + 1), (true, false));
+    }
+
+    #[test]
+    fn pets_without_a_factory_keep_their_own_schedule() {
+        let (_, mut deps) = init_helper(None, None);
+        let hour = 3600;
+        let heatwave = HandleMsg::ScheduleWorldEvent {
+            event: world_event("heatwave", BIRTH + hour, BIRTH + 3 * hour, 150, 200),
+        };
+        let res = handle(&mut deps, env_at("stranger", BIRTH), heatwave.clone());
+        assert!(matches!(res, Err(StdError::Unauthorized { .. })));
+        handle(&mut deps, env_at("creator", BIRTH), heatwave.clone()).unwrap();
+        let res = handle(&mut deps, env_at("creator", BIRTH), heatwave);
+        assert_eq!(
+            extract_error_msg(res),
+            "There's already a world event with that name"
+        );
+        assert_eq!(
+            world_events(&deps, BIRTH + hour),
+            (vec!["heatwave".to_string()], vec![], Uint128(200))
+        );
+        let res = commit_play(&mut deps, 1, BIRTH + hour, Direction::Left);
+        assert_eq!(extract_error_msg(res), "You need 20 tokens to play");
+
+        // cancelled halfway, the hour the pet lived through stays
+        handle(
+            &mut deps,
+            env_at("creator", BIRTH + 2 * hour),
+            HandleMsg::CancelWorldEvent {
+                name: "heatwave".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            world_events(&deps, BIRTH + 2 * hour),
+            (vec![], vec![], Uint128(100))
+        );
+        commit_play(&mut deps, 1, BIRTH + 2 * hour, Direction::Left).unwrap();
+        // 1h at 100% and 1h at 150% make 2.5h, the other 0.5h are back at 100%
+        let hungry_at = BIRTH + 2 * hour + 1800;
+        assert_eq!(hungry_and_dead(&deps, hungry_at), (false, false));
+        assert_eq!(hungry_and_dead(&deps, hungry_at + 1), (true, false));
+    }
 }
@@ -0,0 +1,108 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{HumanAddr, ReadonlyStorage, StdResult, Storage, Uint128};
+use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
+
+use secret_toolkit::storage::{AppendStore, AppendStoreMut};
+
+use shared::paging::page_start;
+
+use crate::rules::Difficulty;
+
+const PREFIX_GRAVEYARD: &[u8] = b"graveyard";
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CauseOfDeath {
+    Starvation,
+    /// Starved while sick, when hunger grows faster
+    Sickness,
+}
+
+impl CauseOfDeath {
+    pub fn name(self) -> &'static str {
+        match self {
+            CauseOfDeath::Starvation => "starvation",
+            CauseOfDeath::Sickness => "sickness",
+        }
+    }
+}
+
+/// Record of one of the pet's lives, a revived pet gets a new one every time it dies
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct Memorial {
+    /// The name the pet had when it died
+    pub name: String,
+    pub owner: HumanAddr,
+    pub born_at: u64,
+    pub died_at: u64,
+    /// Seconds between birth, or revival, and death
+    pub lifespan: u64,
+    pub food_eaten: Uint128,
+    pub cause: CauseOfDeath,
+    pub difficulty: Difficulty,
+}
+
+pub fn append_memorial<S: Storage>(store: &mut S, memorial: &Memorial) -> StdResult<()> {
+    let mut store = PrefixedStorage::new(PREFIX_GRAVEYARD, store);
+    let mut store = AppendStoreMut::attach_or_create(&mut store)?;
+    store.push(memorial)
+}
+
+/// Returns a page of memorials, latest death first, and the total count
+pub fn get_memorials<S: ReadonlyStorage>(
+    storage: &S,
+    page: u32,
+    page_size: u32,
+) -> StdResult<(Vec<Memorial>, u64)> {
+    let store = ReadonlyPrefixedStorage::new(PREFIX_GRAVEYARD, storage);
+
+    // If the pet never died, the graveyard is empty
+    let store = AppendStore::<Memorial, _, _>::attach(&store);
+    let store = if let Some(result) = store {
+        result?
+    } else {
+        return Ok((vec![], 0));
+    };
+
+    let memorials: StdResult<Vec<Memorial>> = store
+        .iter()
+        .rev()
+        .skip(page_start(page, page_size))
+        .take(page_size as _)
+        .collect();
+    memorials.map(|memorials| (memorials, store.len() as u64))
+}
+
+/// Returns a page of memorials, longest lifespan first, and the total count.
+/// A single pet doesn't die often enough for sorting all of its lives to get expensive
+pub fn get_hall_of_fame<S: ReadonlyStorage>(
+    storage: &S,
+    page: u32,
+    page_size: u32,
+) -> StdResult<(Vec<Memorial>, u64)> {
+    let mut memorials = all_memorials(storage)?;
+    let total = memorials.len() as u64;
+    // stable, so earlier deaths stay ahead on equal lifespans
+    memorials.sort_by(|a, b| b.lifespan.cmp(&a.lifespan));
+    let page = memorials
+        .into_iter()
+        .skip(page_start(page, page_size))
+        .take(page_size as _)
+        .collect();
+    Ok((page, total))
+}
+
+fn all_memorials<S: ReadonlyStorage>(storage: &S) -> StdResult<Vec<Memorial>> {
+    let store = ReadonlyPrefixedStorage::new(PREFIX_GRAVEYARD, storage);
+
+    // If the pet never died, the graveyard is empty
+    let store = AppendStore::<Memorial, _, _>::attach(&store);
+    let store = if let Some(result) = store {
+        result?
+    } else {
+        return Ok(vec![]);
+    };
+    store.iter().collect()
+}
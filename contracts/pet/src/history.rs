@@ -0,0 +1,93 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{HumanAddr, ReadonlyStorage, StdResult, Storage};
+use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
+
+use secret_toolkit::storage::{AppendStore, AppendStoreMut};
+
+use shared::paging::page_start;
+
+use crate::graveyard::CauseOfDeath;
+
+const PREFIX_HISTORY: &[u8] = b"history";
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Fed,
+    Overfed,
+    Revived,
+    FellSick,
+    Cured,
+    VacationStarted {
+        until: u64,
+    },
+    VacationEnded,
+    Cleaned,
+    Played {
+        won: bool,
+    },
+    Died {
+        cause: CauseOfDeath,
+    },
+    Renamed {
+        name: String,
+    },
+    Abandoned,
+    Adopted,
+    Sheltered,
+    /// Fed by someone who isn't looking after the pet
+    GiftedFood,
+    Visited {
+        pet: HumanAddr,
+    },
+    VisitedBy {
+        pet: HumanAddr,
+    },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct Event {
+    pub time: u64,
+    pub kind: EventKind,
+    /// Who caused the event, `None` for things that just happen to the pet
+    pub by: Option<HumanAddr>,
+}
+
+pub fn append_event<S: Storage>(
+    store: &mut S,
+    time: u64,
+    kind: EventKind,
+    by: Option<HumanAddr>,
+) -> StdResult<()> {
+    let mut store = PrefixedStorage::new(PREFIX_HISTORY, store);
+    let mut store = AppendStoreMut::attach_or_create(&mut store)?;
+    store.push(&Event { time, kind, by })
+}
+
+pub fn get_events<S: ReadonlyStorage>(
+    storage: &S,
+    page: u32,
+    page_size: u32,
+) -> StdResult<(Vec<Event>, u64)> {
+    let store = ReadonlyPrefixedStorage::new(PREFIX_HISTORY, storage);
+
+    // If nothing happened to the pet yet, return an empty list of events.
+    let store = AppendStore::<Event, _, _>::attach(&store);
+    let store = if let Some(result) = store {
+        result?
+    } else {
+        return Ok((vec![], 0));
+    };
+
+    // Take `page_size` events starting from the latest one, potentially skipping `page * page_size`
+    // events from the start. Pages past the end come back empty.
+    let events: StdResult<Vec<Event>> = store
+        .iter()
+        .rev()
+        .skip(page_start(page, page_size))
+        .take(page_size as _)
+        .collect();
+    events.map(|events| (events, store.len() as u64))
+}
@@ -1,6 +1,15 @@
+pub mod achievements;
+pub mod clock;
+pub mod common;
 pub mod contract;
+pub mod graveyard;
+pub mod history;
 pub mod msg;
+pub mod profile;
+pub mod rules;
 pub mod state;
+pub mod stats;
+pub mod world;
 
 #[cfg(target_arch = "wasm32")]
 mod wasm {
@@ -1,18 +1,53 @@
-use cosmwasm_std::{HumanAddr, Uint128, Binary};
+use cosmwasm_std::{Binary, HumanAddr, Uint128};
 use schemars::JsonSchema;
+use secret_toolkit::utils::{HandleCallback, Query};
 use serde::{Deserialize, Serialize};
 
-
-pub type Hours = u32;
-pub type Minutes = u32;
-
+use crate::achievements::Achievement;
+use crate::common::{Duration, Seconds};
+use crate::graveyard::Memorial;
+use crate::history::Event;
+use crate::profile::ProfileField;
+use crate::rules::{Difficulty, Rules};
+use shared::viewing_key::ViewingKey;
+use shared::world::WorldEvent;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InitMsg {
+    pub name: String,
     pub token_code_hash: String,
     pub token_address: String,
-    pub satiated_interval: Option<u32>,
-    pub starving_interval: Option<u32>,
+    /// Defaults to normal
+    pub difficulty: Option<Difficulty>,
+    /// Override the difficulty's intervals
+    pub satiated_interval: Option<Duration>,
+    pub starving_interval: Option<Duration>,
+    pub overrides: Option<RuleOverrides>,
+    /// Defaults to the sender, which is the factory when the pet is created through one
+    pub owner: Option<HumanAddr>,
+    /// Configures game-wide settings such as the achievement bonus, defaults to the sender
+    pub admin: Option<HumanAddr>,
+    pub factory: Option<FactoryCallback>,
+    pub prng_seed: Binary,
+}
+
+/// Changes to the difficulty's rules, making any of them easier ranks the pet as custom
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct RuleOverrides {
+    pub cleanliness_decay_per_hour: Option<u64>,
+    pub happiness_decay_per_hour: Option<u64>,
+    pub feeding_cost: Option<Uint128>,
+    pub revive_allowed: Option<bool>,
+    pub sickness_risk: Option<u64>,
+}
+
+/// Sent by the factory, so the pet can report its address back once instantiated
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FactoryCallback {
+    pub address: HumanAddr,
+    pub code_hash: String,
+    pub pet_id: u64,
+    pub password: Binary,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -20,16 +55,454 @@ pub struct InitMsg {
 pub enum HandleMsg {
     Receive {
         sender: HumanAddr,
-        from: HumanAddr, 
+        from: HumanAddr,
         amount: Uint128,
         msg: Option<Binary>,
+    },
+    UpdateIntervals {
+        satiated_interval: Duration,
+        starving_interval: Duration,
+    },
+    /// Admin only, `None` turns the bonus off
+    SetAchievementBonus {
+        bonus: Option<AchievementBonus>,
+    },
+    /// Admin only, `None` leaves FOOD as the only way to pay for medicine
+    SetMedicineToken {
+        medicine: Option<MedicineToken>,
+    },
+    /// Admin of a pet without a factory only, names have to be unique among the events that
+    /// haven't ended. Pets from a factory go by its schedule
+    ScheduleWorldEvent {
+        event: WorldEvent,
+    },
+    /// Admin of a pet without a factory only, an event that already started ends right away
+    CancelWorldEvent {
+        name: String,
+    },
+    /// Owner only, replaces the caretaker's previous permissions if already added
+    AddCaretaker {
+        address: HumanAddr,
+        expires_at: u64,
+        permissions: Vec<Permission>,
+    },
+    RemoveCaretaker {
+        address: HumanAddr,
+    },
+    /// Owner only, `pet` is the address of a pet contract allowed to visit this one
+    AddFriend {
+        pet: HumanAddr,
+    },
+    RemoveFriend {
+        pet: HumanAddr,
+    },
+    /// Owner only. Makes both pets happier, as long as this pet is on the other's friend list
+    /// and neither of them had a visit recently
+    Visit {
+        pet: HumanAddr,
+        code_hash: String,
+    },
+    /// Sent by the factory on behalf of `guest`, the pet that visits this one
+    ReceiveVisit {
+        guest: HumanAddr,
+    },
+    /// Owner only, unused vacation time goes back to the yearly allowance
+    EndVacation {},
+    /// Free, for the owner and caretakers allowed to clean up after the pet
+    Clean {},
+    /// Reveals the guess committed with `ReceiveMsg::Play`, in a later block
+    RevealPlay {
+        guess: Direction,
+        salt: Binary,
+    },
+    /// Returns the stake of a game that wasn't revealed in time
+    ReclaimPlay {},
+    /// Owner only
+    SetFeedingMode {
+        mode: FeedingMode,
+    },
+    /// Owner only, replaces everything but the name, which is changed with `ReceiveMsg::Rename`
+    SetProfile {
+        bio: Option<String>,
+        avatar: Option<String>,
+        public: Vec<ProfileField>,
+    },
+    /// Owner only. The pet waits in the adoption pool, with its clock stopped, until someone
+    /// adopts it or the deadline passes and it goes to the shelter
+    Abandon {},
+    /// Anyone can check on the pet, which writes its memorial if it died,
+    /// and sends it to the shelter if nobody adopted it in time
+    Tick {},
+    CreateViewingKey {
+        entropy: String,
+        padding: Option<String>,
+    },
+    SetViewingKey {
+        key: String,
+        padding: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleAnswer {
+    CreateViewingKey { key: ViewingKey },
+    SetViewingKey { status: ResponseStatus },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    Success,
+    Failure,
+}
+
+/// SNIP-20 token, other than FOOD, that buys medicine for the pet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MedicineToken {
+    pub address: HumanAddr,
+    pub code_hash: String,
+    pub price: Uint128,
+}
+
+/// What curing the pet costs in one of the tokens that buy medicine
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CureCost {
+    pub token: HumanAddr,
+    pub code_hash: String,
+    pub amount: Uint128,
+}
+
+/// Actions the owner can delegate to a caretaker
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    Feed,
+    Play,
+    Medicine,
+    Revive,
+    Clean,
+}
+
+/// What happens when a pet that isn't hungry is fed
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedingMode {
+    /// The food is refused
+    Strict,
+    /// The pet eats it anyway, and puts on weight at the cost of its health
+    Soft,
+}
+
+/// Which way the pet runs off, the player wins by guessing it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::Left => "left",
+            Direction::Right => "right",
+        }
     }
 }
 
+/// Payload of the FOOD `Send` that ends up in `Receive`, feeding is the default
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    Feed {},
+    Revive {},
+    /// Owner only, priced per started hour
+    StartVacation {
+        duration: Duration,
+    },
+    /// Paid with FOOD, or with the medicine token if the admin set one
+    Medicine {},
+    /// Starts a game, `commitment` is the sha256 of the guess' name followed by a secret salt
+    Play {
+        commitment: Binary,
+    },
+    /// Owner only
+    Rename {
+        name: String,
+    },
+    /// Anyone can adopt a pet from the adoption pool
+    Adopt {},
+    /// Anyone can feed the pet as a gift, but it's refused if the pet isn't hungry
+    GiftFood {},
+}
+
+/// FOOD minted through the Market to the owner for every unlocked achievement.
+/// The Market owner has to allow the pet to mint rewards
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AchievementBonus {
+    pub market_address: HumanAddr,
+    pub market_code_hash: String,
+    pub amount: Uint128,
+}
+
+/// Queries have no access to the current block, so the time (seconds since epoch)
+/// to evaluate the pet at has to be supplied
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    IsHungry,
-    CanEat,
+    IsHungry {
+        time: u64,
+    },
+    CanEat {
+        time: u64,
+    },
+    /// Hunger, vacation and adoption, the stats are in `Vitals`
+    Status {
+        time: u64,
+    },
+    Play {},
+    /// Only the fields the owner made public
+    Profile {},
+    Friends {
+        time: u64,
+    },
+    /// The factory's schedule, or the admin's for a pet without a factory
+    WorldEvents {
+        time: u64,
+    },
+    /// Past lives of the pet, latest death first
+    Graveyard {
+        page: Option<u32>,
+        page_size: u32,
+    },
+    /// Past lives of the pet, longest first
+    HallOfFame {
+        page: Option<u32>,
+        page_size: u32,
+    },
+    /// Owner only
+    Caretakers {
+        address: HumanAddr,
+        key: String,
+    },
+    /// Owner only
+    History {
+        address: HumanAddr,
+        key: String,
+        page: Option<u32>,
+        page_size: u32,
+    },
+    /// Owner only
+    Vitals {
+        address: HumanAddr,
+        key: String,
+        time: u64,
+    },
+    /// Owner only, the profile shows the achievements if the owner made them public
+    Achievements {
+        address: HumanAddr,
+        key: String,
+    },
+    /// Owner only, the profile shows the difficulty if the owner made it public
+    Rules {
+        address: HumanAddr,
+        key: String,
+    },
+    /// Owner only
+    Sickness {
+        address: HumanAddr,
+        key: String,
+        time: u64,
+    },
+}
+
+impl QueryMsg {
+    pub fn get_validation_params(&self) -> (Vec<&HumanAddr>, ViewingKey) {
+        match self {
+            Self::Caretakers { address, key } => (vec![address], ViewingKey(key.clone())),
+            Self::History { address, key, .. } => (vec![address], ViewingKey(key.clone())),
+            Self::Vitals { address, key, .. } => (vec![address], ViewingKey(key.clone())),
+            Self::Achievements { address, key } => (vec![address], ViewingKey(key.clone())),
+            Self::Rules { address, key } => (vec![address], ViewingKey(key.clone())),
+            Self::Sickness { address, key, .. } => (vec![address], ViewingKey(key.clone())),
+            _ => panic!("This query type does not require authentication"),
+        }
+    }
+}
+
+/// A game waiting to be revealed, heights are block heights
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PlayInfo {
+    pub stake: Uint128,
+    pub committed_at: u64,
+    pub expires_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CaretakerInfo {
+    pub address: HumanAddr,
+    pub expires_at: u64,
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryAnswer {
+    IsHungry {
+        is_hungry: bool,
+    },
+    CanEat {
+        can_eat: bool,
+    },
+    Status {
+        last_feed_time: u64,
+        satiated_interval: Seconds,
+        starving_interval: Seconds,
+        is_hungry: bool,
+        is_dead: bool,
+        vacation_until: Option<u64>,
+        vacation_allowance: Seconds,
+        /// Set while the pet is in the adoption pool
+        adoption_deadline: Option<u64>,
+        feeding_mode: FeedingMode,
+    },
+    Achievements {
+        achievements: Vec<Achievement>,
+        feed_count: u64,
+        current_streak: u32,
+        best_streak: u32,
+    },
+    Rules {
+        difficulty: Difficulty,
+        satiated_interval: Seconds,
+        starving_interval: Seconds,
+        rules: Rules,
+    },
+    Play {
+        pending: Option<PlayInfo>,
+    },
+    Friends {
+        friends: Vec<HumanAddr>,
+        /// `None` if the pet can visit or be visited right away
+        next_visit_at: Option<u64>,
+    },
+    WorldEvents {
+        active: Vec<WorldEvent>,
+        upcoming: Vec<WorldEvent>,
+        /// With the active events applied
+        feeding_cost: Uint128,
+    },
+    Profile {
+        name: String,
+        bio: Option<String>,
+        avatar: Option<String>,
+        alive_since: Option<u64>,
+        difficulty: Option<Difficulty>,
+        achievements: Option<Vec<Achievement>>,
+    },
+    Sickness {
+        is_sick: bool,
+        sick_since: Option<u64>,
+        /// Chance in percent that the pet falls ill on the next interaction
+        risk: u64,
+        /// FOOD first, then the medicine token when the admin set one
+        cure_costs: Vec<CureCost>,
+    },
+    Memorials {
+        memorials: Vec<Memorial>,
+        total: u64,
+    },
+    Caretakers {
+        caretakers: Vec<CaretakerInfo>,
+    },
+    History {
+        events: Vec<Event>,
+        total: u64,
+    },
+    Vitals {
+        /// In grams
+        weight: u64,
+        /// Points out of 100, overeating costs health and proper meals restore it
+        health: u64,
+        /// Only exercise gets an obese pet back in shape
+        is_obese: bool,
+        /// Points out of 100, a dirty pet gets sick and unhappy more easily
+        cleanliness: u64,
+        happiness: u64,
+    },
+    ViewingKeyError {
+        msg: String,
+    },
+}
+
+/// Factory messages the pet sends
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FactoryHandleMsg {
+    RegisterPet {
+        pet_id: u64,
+        password: Binary,
+    },
+    BuryPet {
+        pet_id: u64,
+        memorial: Memorial,
+    },
+    RenamePet {
+        pet_id: u64,
+        name: String,
+    },
+    PutUpForAdoption {
+        pet_id: u64,
+        born_at: u64,
+        deadline: u64,
+    },
+    /// Sent when the pet is adopted or goes to the shelter
+    ChangeOwner {
+        pet_id: u64,
+        owner: HumanAddr,
+    },
+    /// Passed on to `host` as a `ReceiveVisit`, vouching that the guest is a pet
+    VisitPet {
+        pet_id: u64,
+        host: HumanAddr,
+        host_code_hash: String,
+    },
 }
 
+impl HandleCallback for FactoryHandleMsg {
+    const BLOCK_SIZE: usize = 256;
+}
+
+/// Factory queries the pet makes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FactoryQueryMsg {
+    WorldEvents {},
+}
+
+impl Query for FactoryQueryMsg {
+    const BLOCK_SIZE: usize = 256;
+}
+
+/// Mirrors the factory's `QueryAnswer`, as far as the pet reads it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FactoryQueryAnswer {
+    WorldEvents { events: Vec<WorldEvent> },
+}
+
+/// Market messages the pet sends
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketHandleMsg {
+    MintReward {
+        recipient: HumanAddr,
+        amount: Uint128,
+        memo: Option<String>,
+    },
+}
+
+impl HandleCallback for MarketHandleMsg {
+    const BLOCK_SIZE: usize = 256;
+}
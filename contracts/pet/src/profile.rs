@@ -0,0 +1,65 @@
+use cosmwasm_std::{StdError, StdResult};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_BIO_LENGTH: usize = 160;
+pub const MAX_AVATAR_LENGTH: usize = 256;
+const AVATAR_SCHEMES: [&str; 2] = ["https://", "ipfs://"];
+
+/// Parts of the profile the owner can show to everyone, the name is always public
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileField {
+    Bio,
+    Avatar,
+    AliveSince,
+    Difficulty,
+    Achievements,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Profile {
+    pub name: String,
+    pub bio: Option<String>,
+    pub avatar: Option<String>,
+    pub public: Vec<ProfileField>,
+}
+
+impl Profile {
+    pub fn new(name: String) -> Self {
+        Profile {
+            name,
+            bio: None,
+            avatar: None,
+            public: vec![],
+        }
+    }
+
+    pub fn is_public(&self, field: ProfileField) -> bool {
+        self.public.contains(&field)
+    }
+}
+
+pub fn validate_bio(bio: &str) -> StdResult<()> {
+    if bio.len() > MAX_BIO_LENGTH {
+        return Err(StdError::generic_err(format!(
+            "Bio can't be longer than {} bytes",
+            MAX_BIO_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+pub fn validate_avatar(avatar: &str) -> StdResult<()> {
+    if avatar.len() > MAX_AVATAR_LENGTH
+        || !AVATAR_SCHEMES
+            .iter()
+            .any(|scheme| avatar.starts_with(scheme) && avatar.len() > scheme.len())
+    {
+        return Err(StdError::generic_err(format!(
+            "Avatar has to be an https or ipfs URI of up to {} bytes",
+            MAX_AVATAR_LENGTH
+        )));
+    }
+    Ok(())
+}
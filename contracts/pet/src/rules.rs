@@ -0,0 +1,147 @@
+use cosmwasm_std::{StdError, StdResult, Uint128};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::common::{Seconds, SECONDS_PER_HOUR, SECONDS_PER_MINUTE};
+use crate::msg::RuleOverrides;
+use crate::stats::DecayRates;
+
+pub const MAX_DECAY_PER_HOUR: u64 = 25;
+pub const MAX_SICKNESS_RISK: u64 = 100;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    /// Dead is dead, there's no reviving
+    Hardcore,
+    /// A preset with any of its rules made easier, ranked apart from the presets
+    Custom,
+}
+
+impl Difficulty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Hardcore => "hardcore",
+            Difficulty::Custom => "custom",
+        }
+    }
+
+    /// Satiated and starving intervals of the preset, `None` for `Custom`
+    pub fn intervals(self) -> Option<(Seconds, Seconds)> {
+        let (satiated, starving) = match self {
+            Difficulty::Easy => (6 * SECONDS_PER_HOUR, 3 * SECONDS_PER_HOUR),
+            Difficulty::Normal => (3 * SECONDS_PER_HOUR, SECONDS_PER_HOUR),
+            Difficulty::Hard => (2 * SECONDS_PER_HOUR, SECONDS_PER_HOUR),
+            Difficulty::Hardcore => (SECONDS_PER_HOUR, 30 * SECONDS_PER_MINUTE),
+            Difficulty::Custom => return None,
+        };
+        Some((Seconds(satiated), Seconds(starving)))
+    }
+
+    /// Rules of the preset, `None` for `Custom`
+    pub fn rules(self) -> Option<Rules> {
+        let (cleanliness, happiness, feeding_cost, revive_allowed, sickness_risk) = match self {
+            Difficulty::Easy => (1, 1, 50, true, 1),
+            Difficulty::Normal => (2, 1, 100, true, 2),
+            Difficulty::Hard => (3, 2, 150, true, 5),
+            Difficulty::Hardcore => (4, 2, 200, false, 10),
+            Difficulty::Custom => return None,
+        };
+        Some(Rules {
+            decay: DecayRates {
+                cleanliness,
+                happiness,
+            },
+            feeding_cost: Uint128(feeding_cost),
+            revive_allowed,
+            sickness_risk,
+        })
+    }
+}
+
+/// Everything the difficulty decides, apart from the intervals, which the owner can update
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct Rules {
+    pub decay: DecayRates,
+    pub feeding_cost: Uint128,
+    pub revive_allowed: bool,
+    /// Chance in percent that the pet falls ill when fed, before anything else raises it
+    pub sickness_risk: u64,
+}
+
+impl Rules {
+    pub fn with_overrides(self, overrides: &RuleOverrides) -> StdResult<Self> {
+        let rules = Rules {
+            decay: DecayRates {
+                cleanliness: overrides
+                    .cleanliness_decay_per_hour
+                    .unwrap_or(self.decay.cleanliness),
+                happiness: overrides
+                    .happiness_decay_per_hour
+                    .unwrap_or(self.decay.happiness),
+            },
+            feeding_cost: overrides.feeding_cost.unwrap_or(self.feeding_cost),
+            revive_allowed: overrides.revive_allowed.unwrap_or(self.revive_allowed),
+            sickness_risk: overrides.sickness_risk.unwrap_or(self.sickness_risk),
+        };
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    fn validate(&self) -> StdResult<()> {
+        for rate in [self.decay.cleanliness, self.decay.happiness] {
+            if rate == 0 || rate > MAX_DECAY_PER_HOUR {
+                return Err(StdError::generic_err(format!(
+                    "Decay rates must be between 1 and {} points per hour",
+                    MAX_DECAY_PER_HOUR
+                )));
+            }
+        }
+        if self.feeding_cost == Uint128(0) {
+            return Err(StdError::generic_err(
+                "Feeding cost must be greater than zero",
+            ));
+        }
+        if self.sickness_risk > MAX_SICKNESS_RISK {
+            return Err(StdError::generic_err(format!(
+                "Sickness risk can't be over {} percent",
+                MAX_SICKNESS_RISK
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether a pet playing by these rules has it at least as hard as with `other`
+    pub fn is_as_hard_as(&self, other: &Rules) -> bool {
+        self.decay.cleanliness >= other.decay.cleanliness
+            && self.decay.happiness >= other.decay.happiness
+            && self.feeding_cost >= other.feeding_cost
+            && (!self.revive_allowed || other.revive_allowed)
+            && self.sickness_risk >= other.sickness_risk
+    }
+}
+
+/// The preset, as long as nothing was made easier than it, `Custom` otherwise
+pub fn ranked_difficulty(
+    difficulty: Difficulty,
+    satiated_interval: Seconds,
+    starving_interval: Seconds,
+    rules: &Rules,
+) -> Difficulty {
+    match (difficulty.intervals(), difficulty.rules()) {
+        (Some((satiated, starving)), Some(preset))
+            if satiated_interval <= satiated
+                && starving_interval <= starving
+                && rules.is_as_hard_as(&preset) =>
+        {
+            difficulty
+        }
+        _ => Difficulty::Custom,
+    }
+}
@@ -1,14 +1,26 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{CanonicalAddr, Storage, HumanAddr};
-use cosmwasm_storage::{singleton, singleton_read, ReadonlySingleton, Singleton};
+use cosmwasm_std::{CanonicalAddr, HumanAddr, ReadonlyStorage, Storage, Uint128};
+use cosmwasm_storage::{
+    singleton, singleton_read, PrefixedStorage, ReadonlyPrefixedStorage, ReadonlySingleton,
+    Singleton,
+};
 
-use crate::msg::Hours;
+use crate::common::Seconds;
+use crate::msg::{AchievementBonus, FeedingMode, MedicineToken, Permission};
+use crate::profile::Profile;
+use crate::rules::{Difficulty, Rules};
+use crate::stats::Stats;
+use shared::viewing_key::ViewingKey;
+use shared::world::WorldEvent;
 
 pub static PET_KEY: &[u8] = b"pet";
 pub static CONFIG_KEY: &[u8] = b"config";
-
+pub static CARETAKERS_KEY: &[u8] = b"caretakers";
+pub static FRIENDS_KEY: &[u8] = b"friends";
+pub static WORLD_EVENTS_KEY: &[u8] = b"world_events";
+pub const PREFIX_VIEW_KEY: &[u8] = b"viewingkey";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct TokenInfo {
@@ -16,17 +28,123 @@ pub struct TokenInfo {
     pub address: HumanAddr,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FactoryInfo {
+    pub code_hash: String,
+    pub address: HumanAddr,
+    pub pet_id: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
+    pub profile: Profile,
     // time is seconds since epoch begin (Jan. 1, 1970)
     pub last_feed_time: u64,
-    pub satiated_interval: Hours,
-    pub starving_interval: Hours,
+    pub satiated_interval: Seconds,
+    pub starving_interval: Seconds,
+    pub difficulty: Difficulty,
+    pub rules: Rules,
     pub owner: CanonicalAddr,
-    pub token_info: TokenInfo
+    pub admin: CanonicalAddr,
+    pub token_info: TokenInfo,
+    pub factory: Option<FactoryInfo>,
+    // start of the current life, reset when the pet is revived
+    pub alive_since: u64,
+    pub feed_count: u64,
+    // number of hungry windows in a row in which the pet was fed before starving
+    pub current_streak: u32,
+    pub best_streak: u32,
+    pub revive_count: u32,
+    // bitset of `Achievement::bit`s
+    pub achievements: u32,
+    pub achievement_bonus: Option<AchievementBonus>,
+    pub prng_seed: Vec<u8>,
+    // vacations that ended before the last meal are dropped
+    pub vacations: Vec<Vacation>,
+    pub vacation_year_start: u64,
+    pub vacation_used: Seconds,
+    // like vacations, illnesses cured before the last meal are dropped
+    pub illnesses: Vec<Illness>,
+    // nonce for sickness rolls, so they differ within a block
+    pub roll_count: u64,
+    pub medicine: Option<MedicineToken>,
+    // as of the last interaction, decayed lazily from there
+    pub stats: Stats,
+    // in grams
+    pub weight: u64,
+    pub play: Option<PendingPlay>,
+    // points, like the stats, but they don't decay with time
+    pub health: u64,
+    pub feeding_mode: FeedingMode,
+    // burned in meals during the current life
+    pub food_eaten: Uint128,
+    // whether the current life already has a memorial
+    pub buried: bool,
+    // like vacations, stays that ended before the last meal are dropped
+    pub adoptions: Vec<Adoption>,
+    // last time the pet visited, or was visited by, another pet
+    pub last_visit: Option<u64>,
+    // the factory's schedule, or the pet's own without a factory. Not stored with the pet,
+    // `load_state` reads it where costs or decay are worked out
+    #[serde(skip)]
+    pub world_events: Vec<WorldEvent>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct Vacation {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Vacation {
+    pub fn is_active(&self, current_time: u64) -> bool {
+        self.start <= current_time && current_time < self.end
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct Illness {
+    pub start: u64,
+    // `None` until cured
+    pub end: Option<u64>,
+}
+
+/// Stay in the adoption pool. The center looks after the pet, so its clock stops
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct Adoption {
+    pub listed_at: u64,
+    pub deadline: u64,
+    // when the pet was adopted or sent to the shelter, `None` while it waits
+    pub ended_at: Option<u64>,
+}
+
+impl Adoption {
+    pub fn end(&self) -> u64 {
+        self.ended_at.unwrap_or(self.deadline)
+    }
+}
+
+/// Game committed to with `ReceiveMsg::Play`, the stake is held until it's revealed or reclaimed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingPlay {
+    pub player: CanonicalAddr,
+    pub commitment: Vec<u8>,
+    pub stake: Uint128,
+    pub height: u64,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Caretaker {
+    pub address: CanonicalAddr,
+    pub expires_at: u64,
+    pub permissions: Vec<Permission>,
+}
 
+impl Caretaker {
+    pub fn can(&self, permission: Permission, current_time: u64) -> bool {
+        current_time < self.expires_at && self.permissions.contains(&permission)
+    }
+}
 
 pub fn config<S: Storage>(storage: &mut S) -> Singleton<S, State> {
     singleton(storage, PET_KEY)
@@ -42,5 +160,40 @@ pub fn pet<S: Storage>(storage: &mut S) -> Singleton<S, State> {
 
 pub fn pet_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, State> {
     singleton_read(storage, PET_KEY)
+}
+
+pub fn caretakers<S: Storage>(storage: &mut S) -> Singleton<S, Vec<Caretaker>> {
+    singleton(storage, CARETAKERS_KEY)
+}
+
+pub fn caretakers_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Vec<Caretaker>> {
+    singleton_read(storage, CARETAKERS_KEY)
+}
+
+/// Pet contracts allowed to visit this pet
+pub fn friends<S: Storage>(storage: &mut S) -> Singleton<S, Vec<CanonicalAddr>> {
+    singleton(storage, FRIENDS_KEY)
+}
 
-}
\ No newline at end of file
+pub fn friends_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Vec<CanonicalAddr>> {
+    singleton_read(storage, FRIENDS_KEY)
+}
+
+/// Schedule of a pet without a factory, kept by its admin
+pub fn world_events<S: Storage>(storage: &mut S) -> Singleton<S, Vec<WorldEvent>> {
+    singleton(storage, WORLD_EVENTS_KEY)
+}
+
+pub fn world_events_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, Vec<WorldEvent>> {
+    singleton_read(storage, WORLD_EVENTS_KEY)
+}
+
+pub fn write_viewing_key<S: Storage>(store: &mut S, owner: &CanonicalAddr, key: &ViewingKey) {
+    let mut key_store = PrefixedStorage::new(PREFIX_VIEW_KEY, store);
+    key_store.set(owner.as_slice(), &key.to_hashed());
+}
+
+pub fn read_viewing_key<S: Storage>(store: &S, owner: &CanonicalAddr) -> Option<Vec<u8>> {
+    let key_store = ReadonlyPrefixedStorage::new(PREFIX_VIEW_KEY, store);
+    key_store.get(owner.as_slice())
+}
@@ -0,0 +1,89 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::common::SECONDS_PER_HOUR;
+
+/// Stats are stored in points times `STAT_SCALE`, so that decay per hour is
+/// exact per second and frequent updates don't round it away
+pub const STAT_SCALE: u64 = SECONDS_PER_HOUR;
+pub const MAX_POINTS: u64 = 100;
+
+/// Extra happiness lost per hour while the pet sits in its own mess
+pub const DIRTY_HAPPINESS_DECAY_PER_HOUR: u64 = 2;
+/// Below this many cleanliness points the pet counts as dirty
+pub const DIRTY_BELOW: u64 = 30;
+
+/// Points lost per hour of the pet's time, set by the difficulty
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct DecayRates {
+    pub cleanliness: u64,
+    pub happiness: u64,
+}
+
+/// Stats that decay with the pet's clock, as of `updated_at`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct Stats {
+    pub cleanliness: u64,
+    pub happiness: u64,
+    pub updated_at: u64,
+}
+
+impl Stats {
+    pub fn new(time: u64) -> Self {
+        Stats {
+            cleanliness: MAX_POINTS * STAT_SCALE,
+            happiness: MAX_POINTS * STAT_SCALE,
+            updated_at: time,
+        }
+    }
+
+    /// Stats after `elapsed` seconds of the pet's time, as of `time`
+    pub fn decayed(&self, elapsed: u64, time: u64, rates: &DecayRates) -> Self {
+        let cleanliness = self
+            .cleanliness
+            .saturating_sub(elapsed.saturating_mul(rates.cleanliness));
+        // the pet only gets unhappy about the mess once it's dirty
+        let clean_for =
+            self.cleanliness.saturating_sub(DIRTY_BELOW * STAT_SCALE) / rates.cleanliness;
+        let dirty_for = elapsed.saturating_sub(clean_for);
+        let happiness = self
+            .happiness
+            .saturating_sub(elapsed.saturating_mul(rates.happiness))
+            .saturating_sub(dirty_for.saturating_mul(DIRTY_HAPPINESS_DECAY_PER_HOUR));
+        Stats {
+            cleanliness,
+            happiness,
+            updated_at: time,
+        }
+    }
+
+    pub fn cleanliness_points(&self) -> u64 {
+        self.cleanliness / STAT_SCALE
+    }
+
+    pub fn happiness_points(&self) -> u64 {
+        self.happiness / STAT_SCALE
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.cleanliness < DIRTY_BELOW * STAT_SCALE
+    }
+
+    pub fn add_cleanliness(&mut self, points: i64) {
+        self.cleanliness = add_points(self.cleanliness, points);
+    }
+
+    pub fn add_happiness(&mut self, points: i64) {
+        self.happiness = add_points(self.happiness, points);
+    }
+}
+
+/// Adds (or takes away) whole points, keeping the stat within [0, MAX_POINTS]
+fn add_points(value: u64, points: i64) -> u64 {
+    let delta = points.unsigned_abs().saturating_mul(STAT_SCALE);
+    if points < 0 {
+        value.saturating_sub(delta)
+    } else {
+        value.saturating_add(delta).min(MAX_POINTS * STAT_SCALE)
+    }
+}
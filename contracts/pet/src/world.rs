@@ -0,0 +1,33 @@
+use cosmwasm_std::{Querier, StdResult, Storage};
+use secret_toolkit::utils::Query;
+
+use crate::clock::RateWindow;
+use crate::msg::{FactoryQueryAnswer, FactoryQueryMsg};
+use crate::state::{world_events_read, FactoryInfo};
+use shared::world::WorldEvent;
+
+/// The factory keeps one schedule for all of its pets, a pet without a factory keeps its own
+pub fn load_world_events<S: Storage, Q: Querier>(
+    storage: &S,
+    querier: &Q,
+    factory: &Option<FactoryInfo>,
+) -> StdResult<Vec<WorldEvent>> {
+    let factory = match factory {
+        Some(factory) => factory,
+        None => return Ok(world_events_read(storage).may_load()?.unwrap_or_default()),
+    };
+    let FactoryQueryAnswer::WorldEvents { events } = FactoryQueryMsg::WorldEvents {}.query(
+        querier,
+        factory.code_hash.clone(),
+        factory.address.clone(),
+    )?;
+    Ok(events)
+}
+
+pub fn rate_window(event: &WorldEvent) -> RateWindow {
+    RateWindow {
+        start: event.start,
+        end: event.end,
+        rate: event.hunger_rate,
+    }
+}
@@ -5,10 +5,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::batch;
 use crate::transaction_history::{RichTx, Tx};
-use crate::viewing_key::ViewingKey;
 use cosmwasm_std::{Binary, HumanAddr, StdError, StdResult, Uint128};
 use secret_toolkit::permit::Permit;
 use secret_toolkit::utils::InitCallback;
+use shared::viewing_key::ViewingKey;
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 pub struct InitialBalance {
     pub address: HumanAddr,
@@ -0,0 +1,8 @@
+//! Helpers used by more than one of the contracts, kept in one place so they can't drift apart
+
+pub mod name;
+pub mod paging;
+pub mod rand;
+pub mod utils;
+pub mod viewing_key;
+pub mod world;
@@ -0,0 +1,22 @@
+use cosmwasm_std::{StdError, StdResult};
+
+/// Pet names, checked by the Pet and by the factory before it instantiates one
+pub fn validate_name(name: &str) -> StdResult<()> {
+    if !is_valid_name(name) {
+        return Err(StdError::generic_err(
+            "Name is not in the expected format (3-30 letters, digits, spaces, dashes or underscores)",
+        ));
+    }
+    Ok(())
+}
+
+/// Like FOOD's token names, but the name also ends up in logs and the factory's index,
+/// so it's limited to characters that are safe to display
+fn is_valid_name(name: &str) -> bool {
+    let len = name.len();
+    (3..=30).contains(&len)
+        && name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b" -_".contains(&byte))
+        && name.trim() == name
+}
@@ -0,0 +1,5 @@
+/// Items to skip before `page`. Pages too far out start past the end instead of overflowing,
+/// so they come back empty
+pub fn page_start(page: u32, page_size: u32) -> usize {
+    (page as usize).saturating_mul(page_size as usize)
+}
@@ -0,0 +1,125 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdError, StdResult, Uint128};
+
+pub const MAX_WORLD_EVENTS: usize = 20;
+/// Ended events kept for the pets that haven't been fed since, the oldest go first
+pub const MAX_PAST_WORLD_EVENTS: usize = 20;
+/// Highest rate, in percent, an event can set
+pub const MAX_EVENT_RATE: u64 = 1000;
+const MAX_EVENT_NAME_LENGTH: usize = 30;
+
+/// Modifier the admin schedules for a while, the factory's for all of its pets, like a heatwave or
+/// a festival. Rates are in percent, 100 leaves things as they are
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct WorldEvent {
+    pub name: String,
+    pub start: u64,
+    pub end: u64,
+    /// How fast the pet's clock runs, so hunger and the stats decay at this rate
+    pub hunger_rate: u64,
+    /// Applies to everything paid to the pet, like meals, medicine, vacations and adoptions
+    pub cost_rate: u64,
+}
+
+impl WorldEvent {
+    pub fn is_active(&self, current_time: u64) -> bool {
+        self.start <= current_time && current_time < self.end
+    }
+
+    pub fn validate(&self, current_time: u64) -> StdResult<()> {
+        if self.name.is_empty() || self.name.len() > MAX_EVENT_NAME_LENGTH {
+            return Err(StdError::generic_err(format!(
+                "World event name has to be 1-{} bytes long",
+                MAX_EVENT_NAME_LENGTH
+            )));
+        }
+        // changing the past would change how hungry the pets already got
+        if self.start < current_time {
+            return Err(StdError::generic_err(
+                "World events can't start in the past",
+            ));
+        }
+        if self.end <= self.start {
+            return Err(StdError::generic_err(
+                "World event has to end after it starts",
+            ));
+        }
+        if self.hunger_rate > MAX_EVENT_RATE || self.cost_rate > MAX_EVENT_RATE {
+            return Err(StdError::generic_err(format!(
+                "World event rates can't be over {}%",
+                MAX_EVENT_RATE
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// `base` cost at `current_time`. Like the clock rates, the rates of overlapping events multiply
+pub fn world_cost(base: Uint128, events: &[WorldEvent], current_time: u64) -> Uint128 {
+    Uint128(
+        events
+            .iter()
+            .filter(|event| event.is_active(current_time))
+            .fold(base.u128(), |cost, event| {
+                cost.saturating_mul(event.cost_rate as u128) / 100
+            }),
+    )
+}
+
+/// Adds `event` to the schedule, checking it against the events that haven't ended yet
+pub fn schedule_world_event(
+    events: &mut Vec<WorldEvent>,
+    event: WorldEvent,
+    current_time: u64,
+) -> StdResult<()> {
+    event.validate(current_time)?;
+    let scheduled = events.iter().filter(|event| event.end > current_time);
+    if scheduled.clone().any(|other| other.name == event.name) {
+        return Err(StdError::generic_err(
+            "There's already a world event with that name",
+        ));
+    }
+    if scheduled.count() >= MAX_WORLD_EVENTS {
+        return Err(StdError::generic_err(format!(
+            "Only {} world events can be scheduled at once",
+            MAX_WORLD_EVENTS
+        )));
+    }
+    forget_past_world_events(events, current_time);
+    events.push(event);
+    Ok(())
+}
+
+pub fn cancel_world_event(
+    events: &mut Vec<WorldEvent>,
+    name: &str,
+    current_time: u64,
+) -> StdResult<()> {
+    let index = events
+        .iter()
+        .position(|event| event.name == name && event.end > current_time)
+        .ok_or_else(|| StdError::generic_err("No such world event"))?;
+    // the part that already happened stays, the pets lived through it
+    if events[index].start < current_time {
+        events[index].end = current_time;
+    } else {
+        events.remove(index);
+    }
+    Ok(())
+}
+
+fn forget_past_world_events(events: &mut Vec<WorldEvent>, current_time: u64) {
+    let (mut past, scheduled): (Vec<_>, Vec<_>) = events
+        .drain(..)
+        .partition(|event| event.end <= current_time);
+    past.sort_by_key(|event| event.end);
+    events.extend(
+        past.into_iter()
+            .rev()
+            .take(MAX_PAST_WORLD_EVENTS)
+            .rev()
+            .chain(scheduled),
+    );
+}
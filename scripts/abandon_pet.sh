@@ -0,0 +1,2 @@
+source "./_config.sh"
+secretd tx compute execute $PET_ADDRESS '{"abandon":{}}' --from a --gas 20000000
@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./add_caretaker.sh <address> <expires_at> (seconds since epoch)
+MSG='{"add_caretaker": {"address": "'$1'", "expires_at": '$2', "permissions": ["feed", "play", "medicine"]}}'
+secretd tx compute execute $PET_ADDRESS "$MSG" --from a --gas 20000000
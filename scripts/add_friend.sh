@@ -0,0 +1,3 @@
+source "./_config.sh"
+# usage: ./add_friend.sh <pet_address>, lets that pet visit yours
+secretd tx compute execute $PET_ADDRESS "{\"add_friend\":{\"pet\":\"$1\"}}" --from a --gas 20000000
@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./add_promo_code.sh <code> <name> <bonus_percent> <max_uses> <per_address_limit> <expires_at>, owner only
+MSG='{"add_promo_code": {"code": "'$1'", "name": "'$2'", "bonus": {"percent": '$3'}, "max_uses": '$4', "per_address_limit": '$5', "expires_at": '$6'}}'
+secretd tx compute execute $MARKET_ADDRESS "$MSG" --from a --gas 20000000
@@ -0,0 +1,6 @@
+source "./_config.sh"
+# usage: ./add_sale.sh <name> <start> <end> <rate_multiplier> [per_address_cap]
+# times are seconds since epoch, the multiplier is in percent (150 gives half as much FOOD again), owner only
+if [ -z "$5" ]; then CAP=null; else CAP="\"$5\""; fi
+MSG='{"add_sale": {"sale": {"name": "'$1'", "start": '$2', "end": '$3', "rate_multiplier": '$4', "per_address_cap": '$CAP'}}}'
+secretd tx compute execute $MARKET_ADDRESS "$MSG" --from a --gas 20000000
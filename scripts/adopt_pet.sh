@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./adopt_pet.sh <pet_address>, adopting costs 300 food
+ADOPT=$(echo -n '{"adopt":{}}' | base64)
+secretd tx compute execute $FOOD_ADDRESS "{\"send\":{\"recipient\":\"$1\",\"amount\":\"300\",\"msg\":\"$ADOPT\"}}" --from a --gas 20000000
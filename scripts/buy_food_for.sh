@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./buy_food_for.sh <recipient> <amount> [memo], e.g. ./buy_food_for.sh secret1... 100uscrt "Happy birthday"
+MSG='{"buy_food": {"recipient": "'$1'", "memo": "'$3'"}}'
+secretd tx compute execute $MARKET_ADDRESS "$MSG" --amount="$2" --from a --gas 20000000
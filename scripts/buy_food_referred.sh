@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./buy_food_referred.sh <amount> <referrer>, e.g. ./buy_food_referred.sh 100uscrt secret1...
+MSG='{"buy_food": {"referrer": "'$2'"}}'
+secretd tx compute execute $MARKET_ADDRESS "$MSG" --amount="$1" --from a --gas 20000000
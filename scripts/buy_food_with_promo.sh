@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./buy_food_with_promo.sh <amount> <promo_code>, e.g. ./buy_food_with_promo.sh 100uscrt LAUNCH
+MSG='{"buy_food": {"promo_code": "'$2'"}}'
+secretd tx compute execute $MARKET_ADDRESS "$MSG" --amount="$1" --from a --gas 20000000
@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./buy_food_with_token.sh <token_address> <amount>, for SNIP-20 tokens the market accepts, e.g. sSCRT
+BUY=$(echo -n '{"buy_food":{}}' | base64)
+secretd tx compute execute $1 "{\"send\":{\"recipient\":\"$MARKET_ADDRESS\",\"amount\":\"$2\",\"msg\":\"$BUY\"}}" --from a --gas 20000000
@@ -0,0 +1,3 @@
+source "./_config.sh"
+# usage: ./buy_item.sh <sku> <price>, e.g. ./buy_item.sh medicine 50uscrt, for items priced in uscrt
+secretd tx compute execute $MARKET_ADDRESS '{"buy_item": {"sku": "'$1'"}}' --amount="$2" --from a --gas 20000000
@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./buy_item_with_food.sh <sku> <price>, for items priced in FOOD
+BUY=$(echo -n '{"buy_item":{"sku":"'$1'"}}' | base64)
+secretd tx compute execute $FOOD_ADDRESS "{\"send\":{\"recipient\":\"$MARKET_ADDRESS\",\"amount\":\"$2\",\"msg\":\"$BUY\"}}" --from a --gas 20000000
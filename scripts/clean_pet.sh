@@ -0,0 +1,3 @@
+source "./_config.sh"
+# cleaning is free, a dirty pet gets sick more often and is less happy
+secretd tx compute execute $PET_ADDRESS '{"clean":{}}' --from a --gas 20000000
@@ -0,0 +1,6 @@
+source "./_config.sh"
+CONTRACT_ID=$1
+INIT='{"pet_code_id": '$PET_CONTRACT_ID', "pet_code_hash": "'$PET_CODE_HASH'", "token_code_hash": "'$FOOD_CODE_HASH'", "token_address": "'$FOOD_ADDRESS'", "prng_seed": "bmdpbml0ZQ=="}'
+
+echo  "Instantiating Factory from contract, id=$CONTRACT_ID..."
+secretd tx compute instantiate $CONTRACT_ID "$INIT" --label "Factory $1 $2" --from a -y --keyring-backend test
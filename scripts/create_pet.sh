@@ -1,6 +1,8 @@
 source "./_config.sh"
 CONTRACT_ID=$1
-INIT='{"token_code_hash": "'$FOOD_CODE_HASH'", "token_address": "'$FOOD_ADDRESS'", "satiated_interval": 3, "starving_interval": 1}'
+NAME=$2
+DIFFICULTY=${3:-normal}
+INIT='{"name": "'$NAME'", "token_code_hash": "'$FOOD_CODE_HASH'", "token_address": "'$FOOD_ADDRESS'", "difficulty": "'$DIFFICULTY'", "prng_seed": "'$(head -c 32 /dev/urandom | base64)'"}'
 
 echo  "$INIT"
 secretd tx compute instantiate $CONTRACT_ID "$INIT" --label "Pet $1 $2" --from a -y --keyring-backend test
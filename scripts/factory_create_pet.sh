@@ -0,0 +1,6 @@
+source "./_config.sh"
+DIFFICULTY=${2:-normal}
+MSG='{"create_pet": {"name": "'$1'", "difficulty": "'$DIFFICULTY'"}}'
+
+echo  "$MSG"
+secretd tx compute execute $FACTORY_ADDRESS "$MSG" --from a --gas 20000000
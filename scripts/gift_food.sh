@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./gift_food.sh <pet_address>, feeds anyone's pet while it's hungry
+GIFT=$(echo -n '{"gift_food":{}}' | base64)
+secretd tx compute execute $FOOD_ADDRESS "{\"send\":{\"recipient\":\"$1\",\"amount\":\"100\",\"msg\":\"$GIFT\"}}" --from a --gas 20000000
@@ -0,0 +1,4 @@
+source "./_config.sh"
+# curing a sick pet costs 200 food
+MEDICINE=$(echo -n '{"medicine":{}}' | base64)
+secretd tx compute execute $FOOD_ADDRESS "{\"send\":{\"recipient\":\"$PET_ADDRESS\",\"amount\":\"200\",\"msg\":\"$MEDICINE\"}}" --from a --gas 20000000
@@ -0,0 +1,11 @@
+source "./_config.sh"
+# usage: ./play_pet.sh commit <left|right> <salt>, then in a later block ./play_pet.sh reveal <left|right> <salt>
+# a game costs 10 food, it can be reclaimed if it isn't revealed within 100 blocks
+if [ "$1" == "commit" ]; then
+    COMMITMENT=$(echo -n "$2$3" | sha256sum | cut -d ' ' -f 1 | xxd -r -p | base64)
+    PLAY=$(echo -n '{"play":{"commitment":"'$COMMITMENT'"}}' | base64)
+    secretd tx compute execute $FOOD_ADDRESS "{\"send\":{\"recipient\":\"$PET_ADDRESS\",\"amount\":\"10\",\"msg\":\"$PLAY\"}}" --from a --gas 20000000
+else
+    SALT=$(echo -n "$3" | base64)
+    secretd tx compute execute $PET_ADDRESS "{\"reveal_play\":{\"guess\":\"$2\",\"salt\":\"$SALT\"}}" --from a --gas 20000000
+fi
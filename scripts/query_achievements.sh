@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./query_achievements.sh <pet_viewing_key>, create one with
+# secretd tx compute execute $PET_ADDRESS '{"create_viewing_key":{"entropy":"..."}}' --from a
+secretd q compute query $PET_ADDRESS "{\"achievements\":{\"address\":\"$USER_ADDRES\",\"key\":\"$1\"}}"
@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./query_adoption_pool.sh [baby|child|adult|elder]
+if [ -n "$1" ]; then STAGE='"'$1'"'; else STAGE=null; fi
+secretd q compute query $FACTORY_ADDRESS '{"adoption_pool": {"time": '$(date +%s)', "stage": '$STAGE', "page_size": 10}}'
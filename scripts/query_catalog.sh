@@ -0,0 +1,3 @@
+source "./_config.sh"
+# usage: ./query_catalog.sh [page]
+secretd q compute query $MARKET_ADDRESS '{"catalog":{"page":'${1:-0}',"page_size":10}}'
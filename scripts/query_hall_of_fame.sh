@@ -0,0 +1,4 @@
+source "./_config.sh"
+DIFFICULTY=${1:-normal}
+secretd q compute query $FACTORY_ADDRESS '{"hall_of_fame": {"difficulty": "'$DIFFICULTY'", "page_size": 10}}'
+secretd q compute query $FACTORY_ADDRESS '{"graveyard": {"page_size": 10}}'
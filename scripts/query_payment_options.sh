@@ -0,0 +1,2 @@
+source "./_config.sh"
+secretd q compute query $MARKET_ADDRESS '{"payment_options":{}}'
@@ -0,0 +1,2 @@
+source "./_config.sh"
+secretd q compute query $FACTORY_ADDRESS '{"pets_by_owner": {"owner": "'$USER_ADDRES'", "page_size": 10}}'
@@ -0,0 +1,3 @@
+source "./_config.sh"
+# usage: ./query_promo_code.sh <name>
+secretd q compute query $MARKET_ADDRESS '{"promo_code":{"name":"'$1'"}}'
@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./query_purchase_history.sh <market_viewing_key>, create one with
+# secretd tx compute execute $MARKET_ADDRESS '{"create_viewing_key":{"entropy":"..."}}' --from a
+secretd q compute query $MARKET_ADDRESS "{\"purchase_history\":{\"address\":\"$USER_ADDRES\",\"key\":\"$1\",\"page_size\":10}}"
@@ -0,0 +1,3 @@
+source "./_config.sh"
+# usage: ./query_quote.sh <uscrt_amount>, FOOD the payment buys on the bonding curve
+secretd q compute query $MARKET_ADDRESS '{"buy_quote":{"payment":"'$1'"}}'
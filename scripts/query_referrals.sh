@@ -0,0 +1,3 @@
+source "./_config.sh"
+# usage: ./query_referrals.sh <market_viewing_key>
+secretd q compute query $MARKET_ADDRESS "{\"referral_totals\":{\"address\":\"$USER_ADDRES\",\"key\":\"$1\"}}"
@@ -0,0 +1,3 @@
+source "./_config.sh"
+secretd q compute query $MARKET_ADDRESS "{\"active_sales\":{\"time\":$(date +%s)}}"
+secretd q compute query $MARKET_ADDRESS "{\"upcoming_sales\":{\"time\":$(date +%s)}}"
@@ -0,0 +1,2 @@
+source "./_config.sh"
+secretd q compute query $PET_ADDRESS "{\"world_events\":{\"time\":$(date +%s)}}"
@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./rename_pet.sh <name>, renaming costs 50 food
+RENAME=$(echo -n "{\"rename\":{\"name\":\"$1\"}}" | base64)
+secretd tx compute execute $FOOD_ADDRESS "{\"send\":{\"recipient\":\"$PET_ADDRESS\",\"amount\":\"50\",\"msg\":\"$RENAME\"}}" --from a --gas 20000000
@@ -0,0 +1,4 @@
+source "./_config.sh"
+# reviving costs 500 food, the pet contract reads the action from the send msg
+REVIVE=$(echo -n '{"revive":{}}' | base64)
+secretd tx compute execute $FOOD_ADDRESS "{\"send\":{\"recipient\":\"$PET_ADDRESS\",\"amount\":\"500\",\"msg\":\"$REVIVE\"}}" --from a --gas 20000000
@@ -0,0 +1,6 @@
+source "./_config.sh"
+# usage: ./schedule_world_event.sh <name> <start> <end> <hunger_rate> <cost_rate> [contract]
+# times are seconds since epoch, rates are in percent (100 changes nothing), factory admin only.
+# A pet without a factory keeps its own schedule, pass $PET_ADDRESS as the contract for it
+MSG='{"schedule_world_event": {"event": {"name": "'$1'", "start": '$2', "end": '$3', "hunger_rate": '$4', "cost_rate": '$5'}}}'
+secretd tx compute execute ${6:-$FACTORY_ADDRESS} "$MSG" --from a --gas 20000000
@@ -0,0 +1,3 @@
+source "./_config.sh"
+# usage: ./sell_food.sh <amount>, sells FOOD back down the bonding curve when the market allows it
+secretd tx compute execute $FOOD_ADDRESS "{\"send\":{\"recipient\":\"$MARKET_ADDRESS\",\"amount\":\"$1\"}}" --from a --gas 20000000
@@ -0,0 +1,5 @@
+source "./_config.sh"
+# usage: ./set_bonding_curve.sh <base_price> <slope> [sell_back], prices in millionths of a uscrt per FOOD unit, owner only
+if [ -z "$3" ]; then SELL_BACK=false; else SELL_BACK=$3; fi
+MSG='{"set_pricing": {"pricing": {"bonding_curve": {"denom": "uscrt", "shape": {"linear": {"base_price": "'$1'", "slope": "'$2'"}}, "sell_back": '$SELL_BACK'}}}}'
+secretd tx compute execute $MARKET_ADDRESS "$MSG" --from a --gas 20000000
@@ -0,0 +1,3 @@
+source "./_config.sh"
+# usage: ./set_feeding_mode.sh <strict|soft>, in the soft mode a full pet eats anyway and gets fat
+secretd tx compute execute $PET_ADDRESS "{\"set_feeding_mode\":{\"mode\":\"$1\"}}" --from a --gas 20000000
@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./start_vacation.sh <hours>, every started hour costs 10 food
+VACATION=$(echo -n '{"start_vacation":{"duration":{"hours":'$1'}}}' | base64)
+secretd tx compute execute $FOOD_ADDRESS "{\"send\":{\"recipient\":\"$PET_ADDRESS\",\"amount\":\"$(($1 * 10))\",\"msg\":\"$VACATION\"}}" --from a --gas 20000000
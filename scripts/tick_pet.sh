@@ -0,0 +1,3 @@
+source "./_config.sh"
+# anyone can check on a pet, a dead one gets its memorial written
+secretd tx compute execute $PET_ADDRESS '{"tick":{}}' --from a --gas 20000000
@@ -0,0 +1,3 @@
+source "./_config.sh"
+# usage: ./visit_pet.sh <pet_address>, only works if your pet is on its friend list
+secretd tx compute execute $PET_ADDRESS "{\"visit\":{\"pet\":\"$1\",\"code_hash\":\"$PET_CODE_HASH\"}}" --from a --gas 20000000
@@ -0,0 +1,4 @@
+source "./_config.sh"
+# usage: ./withdraw.sh <denom> [amount], withdraws the whole balance of the denom by default, owner only
+if [ -z "$2" ]; then AMOUNT=null; else AMOUNT="\"$2\""; fi
+secretd tx compute execute $MARKET_ADDRESS "{\"withdraw\":{\"denom\":\"$1\",\"amount\":$AMOUNT}}" --from a --gas 20000000